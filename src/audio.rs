@@ -0,0 +1,300 @@
+use std::process::Stdio;
+use std::time::Duration;
+
+use futures::channel::mpsc::Sender;
+use futures::sink::SinkExt;
+use futures::stream::Stream;
+use iced::Subscription;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, BufReader};
+
+use crate::config::primitives::AudioCommand;
+use crate::config::smart::audio::{Audio, Device, is_relevant_event, parse_mute, parse_volume};
+use crate::daemon::Message;
+
+/// Volume keys and scrolling produce a burst of events per step; one query
+/// answers all events this close together.
+const BURST: Duration = Duration::from_millis(30);
+
+/// `pactl` output and event lines are translated, so it runs in the C locale.
+/// `pactl` (from PulseAudio, or pipewire-pulse's tools) is a runtime
+/// dependency of the whole namespace.
+async fn pactl(args: &[&str]) -> Option<String> {
+    let out = match tokio::process::Command::new("pactl")
+        .args(args)
+        .env("LC_ALL", "C")
+        .stdin(Stdio::null())
+        .output()
+        .await
+    {
+        Ok(out) => out,
+        Err(e) => {
+            log::warn!("audio: cannot run pactl: {e}");
+            return None;
+        }
+    };
+    out.status
+        .success()
+        .then(|| String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+fn names(source: bool) -> (&'static str, &'static str) {
+    if source {
+        ("source", "@DEFAULT_SOURCE@")
+    } else {
+        ("sink", "@DEFAULT_SINK@")
+    }
+}
+
+/// `None` when the server does not answer.
+async fn query_device(source: bool) -> Option<Device> {
+    let (kind, default) = names(source);
+    let name = pactl(&[&format!("get-default-{kind}")]).await?;
+    let volume = pactl(&[&format!("get-{kind}-volume"), default]).await;
+    let muted = pactl(&[&format!("get-{kind}-mute"), default]).await;
+    Some(Device {
+        name: name.trim().to_string(),
+        volume: volume.as_deref().and_then(parse_volume).unwrap_or(0.0),
+        muted: muted.as_deref().and_then(parse_mute).unwrap_or(false),
+    })
+}
+
+async fn query() -> Audio {
+    let Some(sink) = query_device(false).await else {
+        return Audio::default();
+    };
+    Audio {
+        connected: true,
+        sink,
+        source: query_device(true).await.unwrap_or_default(),
+    }
+}
+
+async fn publish(output: &mut Sender<Message>, audio: Audio) {
+    if crate::config::smart::update_audio(|a| *a = audio) {
+        let _ = output
+            .send(Message::SmartRefresh(vec!["iwwc.audio".into()]))
+            .await;
+    }
+}
+
+/// Publishes `query()` once, then again after every burst of sink, source
+/// or server events read from `events`, until it closes.
+async fn follow<R, Q, F>(events: R, mut query: Q, output: &mut Sender<Message>)
+where
+    R: AsyncBufRead + Unpin,
+    Q: FnMut() -> F,
+    F: Future<Output = Audio>,
+{
+    publish(output, query().await).await;
+    let mut lines = events.lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if !is_relevant_event(&line) {
+            continue;
+        }
+        let mut open = true;
+        while let Ok(next) = tokio::time::timeout(BURST, lines.next_line()).await {
+            if !matches!(next, Ok(Some(_))) {
+                open = false;
+                break;
+            }
+        }
+        publish(output, query().await).await;
+        if !open {
+            break;
+        }
+    }
+}
+
+async fn watch(output: &mut Sender<Message>) -> std::io::Result<()> {
+    let mut child = tokio::process::Command::new("pactl")
+        .arg("subscribe")
+        .env("LC_ALL", "C")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+    let stdout = child.stdout.take().expect("stdout is piped");
+    follow(BufReader::new(stdout), query, output).await;
+    Ok(())
+}
+
+fn audio_stream() -> impl Stream<Item = Message> {
+    iced::stream::channel(16, async |mut output| {
+        let mut warned = false;
+        loop {
+            // ends when the server goes away; pactl can't reconnect
+            if let Err(e) = watch(&mut output).await {
+                // a missing pactl would otherwise warn every retry
+                if !std::mem::replace(&mut warned, true) {
+                    log::warn!("audio: cannot run pactl subscribe: {e}");
+                } else {
+                    log::debug!("audio: pactl subscribe: {e}");
+                }
+            }
+            publish(&mut output, Audio::default()).await;
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        }
+    })
+}
+
+pub fn subscription() -> Subscription<Message> {
+    Subscription::run(audio_stream)
+}
+
+/// Runs a volume or mute command on the default sink (or source). While the
+/// watcher knows the device, the result is stored right away so repeated
+/// scrolls add up before the server event arrives; returns whether it was.
+pub async fn control(source: bool, command: AudioCommand) -> bool {
+    control_with(source, command, |args: Vec<String>| async move {
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        pactl(&args).await.is_some()
+    })
+    .await
+}
+
+/// Serializes [`control`]: a scroll step reads the volume the previous one
+/// stored, instead of both reading the same level across the `pactl` call.
+static CONTROL: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// [`control`] with `set` running the `pactl` command.
+async fn control_with<S, F>(source: bool, command: AudioCommand, set: S) -> bool
+where
+    S: FnOnce(Vec<String>) -> F,
+    F: Future<Output = bool>,
+{
+    let _serial = CONTROL.lock().await;
+    let (kind, default) = names(source);
+    let current = crate::config::smart::audio();
+    let known = current.connected;
+    let dev = if source {
+        &current.source
+    } else {
+        &current.sink
+    };
+    let mut volume = None;
+    let mut muted = None;
+    let (verb, arg) = match command {
+        AudioCommand::SetVolume(v) => {
+            let v = v.max(0.0);
+            volume = Some(v);
+            ("volume", format!("{v}%"))
+        }
+        AudioCommand::AdjustVolume(step) if known => {
+            // never past 100% by scrolling, but don't cut a louder level
+            let v = (dev.volume + step).clamp(0.0, dev.volume.max(100.0));
+            volume = Some(v);
+            ("volume", format!("{v}%"))
+        }
+        AudioCommand::AdjustVolume(step) => ("volume", format!("{step:+}%")),
+        AudioCommand::Mute(m) => {
+            muted = Some(m.unwrap_or(!dev.muted));
+            let arg = match m {
+                None => "toggle",
+                Some(true) => "1",
+                Some(false) => "0",
+            };
+            ("mute", arg.to_string())
+        }
+    };
+    if !set(vec![
+        format!("set-{kind}-{verb}"),
+        default.to_string(),
+        arg.clone(),
+    ])
+    .await
+    {
+        log::warn!("audio: pactl set-{kind}-{verb} {arg} failed");
+        return false;
+    }
+    known
+        && crate::config::smart::update_audio(|a| {
+            let d = a.device_mut(source);
+            d.volume = volume.unwrap_or(d.volume);
+            d.muted = muted.unwrap_or(d.muted);
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use tokio::io::AsyncWriteExt;
+
+    /// Both tests drive the one global audio state.
+    static STATE: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+    #[tokio::test]
+    async fn one_query_per_event_burst() {
+        let _state = STATE.lock().await;
+        crate::config::smart::update_audio(|a| *a = Audio::default());
+        // the stand-in server: `pactl subscribe` lines over a socket
+        let (mut server, client) = tokio::net::UnixStream::pair().unwrap();
+        server
+            .write_all(
+                b"Event 'change' on client #12\n\
+                  Event 'change' on sink #47\n\
+                  Event 'change' on sink #47\n\
+                  Event 'change' on server #-1\n",
+            )
+            .await
+            .unwrap();
+        drop(server);
+
+        let calls = Cell::new(0);
+        let query = || {
+            calls.set(calls.get() + 1);
+            let volume = 10.0 * calls.get() as f64;
+            async move {
+                Audio {
+                    connected: true,
+                    sink: Device {
+                        name: "speakers".into(),
+                        volume,
+                        muted: false,
+                    },
+                    source: Device::default(),
+                }
+            }
+        };
+        let (mut tx, mut rx) = futures::channel::mpsc::channel(16);
+        follow(BufReader::new(client), query, &mut tx).await;
+        drop(tx);
+
+        // initial state, then one for the sink/server burst
+        assert_eq!(calls.get(), 2);
+        assert_eq!(crate::config::smart::audio().sink.volume, 20.0);
+        let mut refreshes = 0;
+        while let Ok(Some(m)) = rx.try_next() {
+            assert!(matches!(m, Message::SmartRefresh(ns) if ns == ["iwwc.audio"]));
+            refreshes += 1;
+        }
+        assert_eq!(refreshes, 2);
+    }
+
+    #[tokio::test]
+    async fn concurrent_steps_add_up() {
+        let _state = STATE.lock().await;
+        crate::config::smart::update_audio(|a| {
+            *a = Audio {
+                connected: true,
+                sink: Device {
+                    name: "speakers".into(),
+                    volume: 20.0,
+                    muted: false,
+                },
+                source: Device::default(),
+            }
+        });
+        // pactl takes a while; the second step must not read 20% meanwhile
+        let set = |args: Vec<String>| async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            args[0] == "set-sink-volume"
+        };
+        let (a, b) = tokio::join!(
+            control_with(false, AudioCommand::AdjustVolume(5.0), set),
+            control_with(false, AudioCommand::AdjustVolume(5.0), set),
+        );
+        assert!(a && b);
+        assert_eq!(crate::config::smart::audio().sink.volume, 30.0);
+    }
+}
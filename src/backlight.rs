@@ -0,0 +1,98 @@
+use std::path::Path;
+
+use futures::channel::mpsc::Sender;
+use futures::sink::SinkExt;
+use futures::stream::{Stream, StreamExt};
+use iced::Subscription;
+use inotify::{Inotify, WatchMask};
+
+use crate::config::primitives::BrightnessChange;
+use crate::config::smart::backlight::{ROOT, devices};
+use crate::daemon::Message;
+
+/// Resamples `iwwc.backlight` whenever a device's brightness changes.
+/// Writes show up on `brightness`; changes the firmware makes on its own
+/// (brightness keys handled by ACPI) are announced on `actual_brightness`.
+async fn watch(output: &mut Sender<Message>) -> std::io::Result<bool> {
+    let devices = devices(Path::new(ROOT));
+    if devices.is_empty() {
+        return Ok(false);
+    }
+    let inotify = Inotify::init()?;
+    for d in &devices {
+        for file in ["brightness", "actual_brightness"] {
+            let _ = inotify.watches().add(d.dir.join(file), WatchMask::MODIFY);
+        }
+    }
+    let mut events = inotify.into_event_stream([0u8; 1024])?;
+    while let Some(event) = events.next().await {
+        event?;
+        let _ = output
+            .send(Message::SmartRefresh(vec!["iwwc.backlight".into()]))
+            .await;
+    }
+    Ok(true)
+}
+
+fn backlight_stream() -> impl Stream<Item = Message> {
+    iced::stream::channel(16, async |mut output| {
+        loop {
+            match watch(&mut output).await {
+                Ok(false) => {
+                    log::debug!("backlight: no devices in {ROOT}");
+                    return;
+                }
+                Ok(true) => {}
+                Err(e) => log::warn!("backlight: inotify: {e}"),
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+        }
+    })
+}
+
+pub fn subscription() -> Subscription<Message> {
+    Subscription::run(backlight_stream)
+}
+
+/// Changes the brightness of `device` (a directory name or its
+/// `iwwc.backlight` key), or the preferred device. Goes through logind,
+/// which may write for the session's user; when that fails the sysfs file
+/// is written directly, which needs a udev rule granting access.
+pub async fn set(device: Option<String>, change: BrightnessChange) -> bool {
+    let devices = devices(Path::new(ROOT));
+    let target = match &device {
+        Some(name) => devices
+            .iter()
+            .find(|d| d.name == *name || crate::config::smart::sanitize(&d.name) == *name),
+        None => devices.first(),
+    };
+    let Some(d) = target else {
+        log::warn!(
+            "brightness action: no backlight device {}",
+            device
+                .as_deref()
+                .map_or("found".into(), |d| format!("\"{d}\""))
+        );
+        return false;
+    };
+    let raw = d.target(change);
+    let logind = async {
+        crate::logind::session()
+            .await?
+            .set_brightness("backlight", &d.name, raw as u32)
+            .await
+    };
+    match logind.await {
+        Ok(()) => true,
+        Err(e) => {
+            log::debug!("brightness: logind: {e}; writing sysfs");
+            match tokio::fs::write(d.dir.join("brightness"), raw.to_string()).await {
+                Ok(()) => true,
+                Err(e) => {
+                    log::warn!("brightness action: {}: {e}", d.name);
+                    false
+                }
+            }
+        }
+    }
+}
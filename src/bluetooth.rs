@@ -0,0 +1,451 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use futures::channel::mpsc::Sender;
+use futures::sink::SinkExt;
+use futures::stream::{Stream, StreamExt};
+use iced::Subscription;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue};
+
+use crate::config::primitives::BluetoothCommand;
+use crate::config::smart::bluetooth::{Adapter, Bluetooth, Device};
+use crate::daemon::Message;
+
+const BLUEZ: &str = "org.bluez";
+
+/// Connecting a device changes several of its properties in a row; one
+/// publish answers all signals this close together.
+const BURST: Duration = Duration::from_millis(100);
+
+const ADAPTER: &str = "org.bluez.Adapter1";
+const DEVICE: &str = "org.bluez.Device1";
+const BATTERY: &str = "org.bluez.Battery1";
+
+#[zbus::proxy(interface = "org.bluez.Adapter1", default_service = "org.bluez")]
+trait Adapter1 {
+    #[zbus(property)]
+    fn set_powered(&self, powered: bool) -> zbus::Result<()>;
+}
+
+#[zbus::proxy(interface = "org.bluez.Device1", default_service = "org.bluez")]
+trait Device1 {
+    fn connect(&self) -> zbus::Result<()>;
+    fn disconnect(&self) -> zbus::Result<()>;
+}
+
+/// path -> interface -> property
+type Objects = HashMap<String, HashMap<String, HashMap<String, OwnedValue>>>;
+
+fn str_prop(props: &HashMap<String, OwnedValue>, key: &str) -> String {
+    props
+        .get(key)
+        .and_then(|v| String::try_from(&**v).ok())
+        .unwrap_or_default()
+}
+
+fn bool_prop(props: &HashMap<String, OwnedValue>, key: &str) -> bool {
+    props
+        .get(key)
+        .and_then(|v| bool::try_from(&**v).ok())
+        .unwrap_or(false)
+}
+
+fn from_objects(objects: &Objects) -> Bluetooth {
+    let mut bt = Bluetooth::default();
+    for (path, interfaces) in objects {
+        if let Some(a) = interfaces.get(ADAPTER) {
+            bt.adapters.push(Adapter {
+                path: path.clone(),
+                alias: str_prop(a, "Alias"),
+                powered: bool_prop(a, "Powered"),
+                discovering: bool_prop(a, "Discovering"),
+            });
+        }
+        if let Some(d) = interfaces.get(DEVICE) {
+            let device = Device {
+                path: path.clone(),
+                address: str_prop(d, "Address"),
+                name: str_prop(d, "Alias"),
+                icon: str_prop(d, "Icon"),
+                paired: bool_prop(d, "Paired"),
+                connected: bool_prop(d, "Connected"),
+                battery: interfaces
+                    .get(BATTERY)
+                    .and_then(|b| b.get("Percentage"))
+                    .and_then(|v| u8::try_from(&**v).ok()),
+            };
+            if device.paired || device.connected {
+                bt.devices.push(device);
+            }
+        }
+    }
+    bt.adapters.sort_by(|a, b| a.path.cmp(&b.path));
+    bt.devices.sort_by(|a, b| a.path.cmp(&b.path));
+    bt
+}
+
+async fn read_objects(conn: &zbus::Connection) -> zbus::Result<Objects> {
+    let manager = zbus::fdo::ObjectManagerProxy::builder(conn)
+        .destination(BLUEZ)?
+        .path("/")?
+        .build()
+        .await?;
+    let objects: Objects = manager
+        .get_managed_objects()
+        .await?
+        .into_iter()
+        .map(|(path, interfaces)| {
+            let interfaces = interfaces
+                .into_iter()
+                .map(|(name, props)| (name.to_string(), props))
+                .collect();
+            (path.to_string(), interfaces)
+        })
+        .collect();
+    Ok(objects)
+}
+
+async fn read(conn: &zbus::Connection) -> zbus::Result<Bluetooth> {
+    Ok(from_objects(&read_objects(conn).await?))
+}
+
+/// A BlueZ signal, as a change to [`Objects`].
+#[derive(Debug)]
+enum Change {
+    Added(String, HashMap<String, HashMap<String, OwnedValue>>),
+    Removed(String, Vec<String>),
+    Properties {
+        path: String,
+        interface: String,
+        changed: HashMap<String, OwnedValue>,
+        invalidated: Vec<String>,
+    },
+}
+
+fn change(msg: &zbus::Message) -> Option<Change> {
+    let header = msg.header();
+    let body = msg.body();
+    match header.member()?.as_str() {
+        "InterfacesAdded" => {
+            let (path, interfaces): (OwnedObjectPath, HashMap<_, _>) = body.deserialize().ok()?;
+            Some(Change::Added(path.to_string(), interfaces))
+        }
+        "InterfacesRemoved" => {
+            let (path, interfaces): (OwnedObjectPath, Vec<String>) = body.deserialize().ok()?;
+            Some(Change::Removed(path.to_string(), interfaces))
+        }
+        "PropertiesChanged" => {
+            let (interface, changed, invalidated): (String, HashMap<_, _>, Vec<String>) =
+                body.deserialize().ok()?;
+            Some(Change::Properties {
+                path: header.path()?.to_string(),
+                interface,
+                changed,
+                invalidated,
+            })
+        }
+        _ => None,
+    }
+}
+
+fn patch(objects: &mut Objects, change: Change) {
+    match change {
+        Change::Added(path, interfaces) => objects.entry(path).or_default().extend(interfaces),
+        Change::Removed(path, interfaces) => {
+            if let Some(object) = objects.get_mut(&path) {
+                for i in &interfaces {
+                    object.remove(i);
+                }
+                if object.is_empty() {
+                    objects.remove(&path);
+                }
+            }
+        }
+        Change::Properties {
+            path,
+            interface,
+            changed,
+            invalidated,
+        } => {
+            // an interface not added yet gets all its properties when it is
+            if let Some(props) = objects.get_mut(&path).and_then(|o| o.get_mut(&interface)) {
+                props.extend(changed);
+                for p in &invalidated {
+                    props.remove(p);
+                }
+            }
+        }
+    }
+}
+
+async fn publish(output: &mut Sender<Message>, bt: Bluetooth) {
+    if crate::config::smart::update_bluetooth(|b| *b = bt) {
+        let _ = output
+            .send(Message::SmartRefresh(vec!["iwwc.bluetooth".into()]))
+            .await;
+    }
+}
+
+/// Reads the object tree once, then patches it with the signals that
+/// touch what `iwwc.bluetooth` shows: objects coming and going, and property
+/// changes of adapters, devices and batteries. BlueZ restarting reads it
+/// again.
+async fn watch(output: &mut Sender<Message>) -> zbus::Result<()> {
+    use zbus::MatchRule;
+    use zbus::message::Type;
+    let conn = crate::dbus::system().await?;
+    let mut rules = vec![
+        MatchRule::builder()
+            .msg_type(Type::Signal)
+            .sender(BLUEZ)?
+            .interface("org.freedesktop.DBus.ObjectManager")?
+            .build(),
+    ];
+    for interface in [ADAPTER, DEVICE, BATTERY] {
+        rules.push(
+            MatchRule::builder()
+                .msg_type(Type::Signal)
+                .sender(BLUEZ)?
+                .interface("org.freedesktop.DBus.Properties")?
+                .member("PropertiesChanged")?
+                .arg(0, interface)?
+                .build(),
+        );
+    }
+    let mut streams = Vec::new();
+    for rule in rules {
+        streams.push(zbus::MessageStream::for_match_rule(rule, conn, None).await?);
+    }
+    let changes = futures::stream::select_all(streams)
+        .filter_map(|m| std::future::ready(m.ok().as_ref().and_then(change)))
+        .map(Some);
+    let dbus = zbus::fdo::DBusProxy::new(conn).await?;
+    // `None`: BlueZ restarted, everything is new
+    let owner_changes = dbus
+        .receive_name_owner_changed_with_args(&[(0, BLUEZ)])
+        .await?
+        .map(|_| None);
+    let mut events = futures::stream::select(changes, owner_changes);
+
+    let mut objects = read_objects(conn).await.unwrap_or_default();
+    publish(output, from_objects(&objects)).await;
+    while let Some(first) = events.next().await {
+        let mut restarted = false;
+        let mut next = Some(first);
+        while let Some(event) = next {
+            match event {
+                Some(c) => patch(&mut objects, c),
+                None => restarted = true,
+            }
+            next = tokio::time::timeout(BURST, events.next())
+                .await
+                .ok()
+                .flatten();
+        }
+        if restarted {
+            objects = read_objects(conn).await.unwrap_or_default();
+        }
+        publish(output, from_objects(&objects)).await;
+    }
+    Ok(())
+}
+
+fn bluetooth_stream() -> impl Stream<Item = Message> {
+    iced::stream::channel(16, async |mut output| {
+        loop {
+            if let Err(e) = watch(&mut output).await {
+                log::debug!("bluetooth: {e}");
+            }
+            publish(&mut output, Bluetooth::default()).await;
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        }
+    })
+}
+
+pub fn subscription() -> Subscription<Message> {
+    Subscription::run(bluetooth_stream)
+}
+
+/// Switches the first adapter on or off, or connects or disconnects a
+/// paired device (a key from `iwwc.bluetooth.devices`, a name or an
+/// address).
+pub async fn control(command: BluetoothCommand) -> zbus::Result<()> {
+    let conn = crate::dbus::system().await?;
+    let bt = read(conn).await?;
+    let device = |name: &str| {
+        bt.lookup(name)
+            .map(|d| d.path.clone())
+            .ok_or_else(|| zbus::Error::Failure(format!("no device \"{name}\"")))
+    };
+    match command {
+        BluetoothCommand::Power(on) => {
+            let adapter = bt
+                .adapters
+                .first()
+                .ok_or_else(|| zbus::Error::Failure("no adapter".into()))?;
+            Adapter1Proxy::builder(conn)
+                .path(adapter.path.clone())?
+                .build()
+                .await?
+                .set_powered(on.unwrap_or(!adapter.powered))
+                .await
+        }
+        BluetoothCommand::Connect(name) => {
+            Device1Proxy::builder(conn)
+                .path(device(&name)?)?
+                .build()
+                .await?
+                .connect()
+                .await
+        }
+        BluetoothCommand::Disconnect(name) => {
+            Device1Proxy::builder(conn)
+                .path(device(&name)?)?
+                .build()
+                .await?
+                .disconnect()
+                .await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zbus::zvariant::Value;
+
+    fn props(entries: &[(&str, Value<'static>)]) -> HashMap<String, OwnedValue> {
+        entries
+            .iter()
+            .map(|(k, v)| (k.to_string(), OwnedValue::try_from(v.clone()).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn reads_managed_objects() {
+        let device = |alias: &str, address: &str, paired: bool, connected: bool| {
+            props(&[
+                ("Address", Value::from(address.to_string())),
+                ("Alias", Value::from(alias.to_string())),
+                ("Icon", Value::from("audio-headset")),
+                ("Paired", Value::from(paired)),
+                ("Connected", Value::from(connected)),
+            ])
+        };
+        let objects: Objects = HashMap::from([
+            (
+                "/org/bluez/hci0".to_string(),
+                HashMap::from([(
+                    "org.bluez.Adapter1".to_string(),
+                    props(&[
+                        ("Alias", Value::from("laptop")),
+                        ("Powered", Value::from(true)),
+                        ("Discovering", Value::from(false)),
+                    ]),
+                )]),
+            ),
+            (
+                "/org/bluez/hci0/dev_AA_01".to_string(),
+                HashMap::from([
+                    (
+                        "org.bluez.Device1".to_string(),
+                        device("Headset", "AA:01", true, true),
+                    ),
+                    (
+                        "org.bluez.Battery1".to_string(),
+                        props(&[("Percentage", Value::from(70u8))]),
+                    ),
+                ]),
+            ),
+            (
+                "/org/bluez/hci0/dev_AA_02".to_string(),
+                HashMap::from([(
+                    "org.bluez.Device1".to_string(),
+                    device("Keyboard", "AA:02", true, false),
+                )]),
+            ),
+            // seen by a scan, never paired
+            (
+                "/org/bluez/hci0/dev_AA_03".to_string(),
+                HashMap::from([(
+                    "org.bluez.Device1".to_string(),
+                    device("TV", "AA:03", false, false),
+                )]),
+            ),
+            ("/org/bluez".to_string(), HashMap::new()),
+        ]);
+        let bt = from_objects(&objects);
+        assert_eq!(bt.adapters.len(), 1);
+        assert!(bt.adapters[0].powered);
+        assert_eq!(bt.adapters[0].alias, "laptop");
+        let names: Vec<&str> = bt.devices.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, ["Headset", "Keyboard"]);
+        assert_eq!(bt.devices[0].battery, Some(70));
+        assert!(bt.devices[0].connected);
+        assert_eq!(bt.devices[1].battery, None);
+    }
+
+    #[test]
+    fn signals_patch_objects_in_place() {
+        let path = "/org/bluez/hci0/dev_AA_01".to_string();
+        let mut objects: Objects = HashMap::new();
+        patch(
+            &mut objects,
+            Change::Added(
+                path.clone(),
+                HashMap::from([(
+                    DEVICE.to_string(),
+                    props(&[
+                        ("Address", Value::from("AA:01")),
+                        ("Alias", Value::from("Headset")),
+                        ("Paired", Value::from(true)),
+                        ("Connected", Value::from(false)),
+                    ]),
+                )]),
+            ),
+        );
+        assert!(!from_objects(&objects).devices[0].connected);
+
+        let changed = |interface: &str, entries: &[(&str, Value<'static>)]| Change::Properties {
+            path: path.clone(),
+            interface: interface.to_string(),
+            changed: props(entries),
+            invalidated: Vec::new(),
+        };
+        patch(
+            &mut objects,
+            changed(DEVICE, &[("Connected", Value::from(true))]),
+        );
+        // a battery before its InterfacesAdded is dropped
+        patch(
+            &mut objects,
+            changed(BATTERY, &[("Percentage", Value::from(40u8))]),
+        );
+        let bt = from_objects(&objects);
+        assert!(bt.devices[0].connected);
+        assert_eq!(bt.devices[0].name, "Headset");
+        assert_eq!(bt.devices[0].battery, None);
+
+        patch(
+            &mut objects,
+            Change::Added(
+                path.clone(),
+                HashMap::from([(
+                    BATTERY.to_string(),
+                    props(&[("Percentage", Value::from(40u8))]),
+                )]),
+            ),
+        );
+        patch(
+            &mut objects,
+            changed(BATTERY, &[("Percentage", Value::from(35u8))]),
+        );
+        assert_eq!(from_objects(&objects).devices[0].battery, Some(35));
+
+        patch(
+            &mut objects,
+            Change::Removed(path.clone(), vec![DEVICE.into(), BATTERY.into()]),
+        );
+        assert!(objects.is_empty());
+    }
+}
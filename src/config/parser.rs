@@ -1,9 +1,10 @@
 use crate::config::primitives::{
-    AnchorError, parse_align_x, parse_align_y, parse_anchor, parse_color, parse_event_type,
-    parse_font_stretch, parse_font_style, parse_font_weight, parse_interval, parse_layer,
-    parse_output, parse_text_align_x, parse_transition,
+    AnchorError, INTERNAL_ACTIONS, parse_action_qualifier, parse_align_x, parse_align_y,
+    parse_anchor, parse_color, parse_content_fit, parse_dbus_bus, parse_event_type,
+    parse_font_stretch, parse_font_style, parse_font_weight, parse_internal_action, parse_interval,
+    parse_key_chord, parse_layer, parse_output, parse_text_align_x, parse_transition,
 };
-use crate::config::types::PullDecl;
+use crate::config::types::{ActionDecl, DbusDecl, PullDecl, SmartDecl};
 use crate::config::types::{FieldValue, ParsedConfig, SourceText, Span};
 use crate::config::types::{VarDecl, VarValue};
 use crate::config::{ConfigError, ConfigErrorKind, Severity};
@@ -144,6 +145,197 @@ pub(crate) fn build_pull(
     ))
 }
 
+pub(crate) fn build_dbus(
+    node: &kdl::KdlNode,
+    source: &SourceText,
+    errs: &mut Vec<ConfigError>,
+) -> Option<(String, DbusDecl)> {
+    let node_span = span_of_node(node, source);
+    let err = |errs: &mut Vec<ConfigError>, msg: String| {
+        errs.push(err_at(
+            node_span.clone(),
+            ConfigErrorKind::InvalidFieldType,
+            msg,
+        ));
+    };
+    let Some(name) = first_positional_string(node) else {
+        errs.push(err_at(
+            node_span,
+            ConfigErrorKind::MissingRequiredField,
+            "dbus requires a variable name, e.g. dbus profile bus=\"system\" ...".into(),
+        ));
+        return None;
+    };
+
+    let mut bus = None;
+    let mut fields: [(&str, Option<String>); 4] = [
+        ("service", None),
+        ("path", None),
+        ("interface", None),
+        ("property", None),
+    ];
+    let mut default = VarValue::Str(String::new());
+    for entry in node.entries().iter() {
+        let Some(key) = entry.name().map(|k| k.value()) else {
+            continue;
+        };
+        match key {
+            "bus" => match entry.value().as_string().map(|s| (s, parse_dbus_bus(s))) {
+                Some((_, Some(b))) => bus = Some(b),
+                _ => {
+                    err(errs, "dbus bus must be \"system\" or \"session\"".into());
+                    return None;
+                }
+            },
+            "default" => {
+                default = match entry.value() {
+                    kdl::KdlValue::Integer(i) => VarValue::Int(*i),
+                    kdl::KdlValue::Float(f) => VarValue::Float(*f),
+                    kdl::KdlValue::Bool(b) => VarValue::Bool(*b),
+                    kdl::KdlValue::String(s) => VarValue::Str(s.clone()),
+                    kdl::KdlValue::Null => {
+                        err(errs, "dbus default must not be #null".into());
+                        return None;
+                    }
+                }
+            }
+            other => match fields.iter_mut().find(|(k, _)| *k == other) {
+                Some((_, slot)) => match entry.value().as_string() {
+                    Some(s) => *slot = Some(s.to_string()),
+                    None => {
+                        err(errs, format!("dbus {other} must be a string"));
+                        return None;
+                    }
+                },
+                None => errs.push(warn_at(
+                    span_of_entry(entry, source),
+                    ConfigErrorKind::UnknownField,
+                    format!("unknown property `{}=` on `dbus {}`", other, name),
+                )),
+            },
+        }
+    }
+
+    let Some(bus) = bus else {
+        err(
+            errs,
+            "dbus requires bus=\"system\" or bus=\"session\"".into(),
+        );
+        return None;
+    };
+    if let Some((k, _)) = fields.iter().find(|(_, v)| v.is_none()) {
+        err(errs, format!("dbus requires {k}="));
+        return None;
+    }
+    let [service, path, interface, property] = fields.map(|(_, v)| v.unwrap_or_default());
+    if !path.starts_with('/') {
+        err(errs, format!("dbus path \"{path}\" must start with /"));
+        return None;
+    }
+    Some((
+        name,
+        DbusDecl {
+            bus,
+            service,
+            path,
+            interface,
+            property,
+            default,
+            span: node_span.clone(),
+        },
+    ))
+}
+
+pub(crate) fn build_smart(
+    node: &kdl::KdlNode,
+    source: &SourceText,
+    errs: &mut Vec<ConfigError>,
+) -> Option<(String, SmartDecl)> {
+    let node_span = span_of_node(node, source);
+    let Some(namespace) = first_positional_string(node) else {
+        errs.push(err_at(
+            node_span,
+            ConfigErrorKind::MissingRequiredField,
+            "smart requires a namespace, e.g. smart iwwc.disk mounts=\"/ /home\"".into(),
+        ));
+        return None;
+    };
+    if namespace == "iwwc" || !crate::config::smart::is_namespace(&namespace) {
+        errs.push(err_at(
+            node_span,
+            ConfigErrorKind::UnresolvedReference,
+            format!("unknown smart namespace \"{}\"", namespace),
+        ));
+        return None;
+    }
+    let mut decl = SmartDecl {
+        interval: None,
+        mounts: None,
+        timeout: None,
+        pause: false,
+        span: node_span,
+    };
+    for entry in node.entries().iter() {
+        let Some(key) = entry.name().map(|k| k.value()) else {
+            continue;
+        };
+        match key {
+            "i" | "interval" if crate::config::smart::poll_interval(&namespace).is_some() => {
+                match entry.value().as_string().map(|s| (s, parse_interval(s))) {
+                    Some((_, Some(d))) if !d.is_zero() => decl.interval = Some(d),
+                    Some((s, _)) => errs.push(err_at(
+                        span_of_entry(entry, source),
+                        ConfigErrorKind::InvalidFieldType,
+                        format!("invalid interval \"{}\"", s),
+                    )),
+                    None => errs.push(err_at(
+                        span_of_entry(entry, source),
+                        ConfigErrorKind::InvalidFieldType,
+                        "smart interval must be a string".into(),
+                    )),
+                }
+            }
+            "timeout" if namespace == "iwwc.idle" => {
+                match entry.value().as_string().map(|s| (s, parse_interval(s))) {
+                    Some((_, Some(d))) if !d.is_zero() => decl.timeout = Some(d),
+                    Some((s, _)) => errs.push(err_at(
+                        span_of_entry(entry, source),
+                        ConfigErrorKind::InvalidFieldType,
+                        format!("invalid timeout \"{}\"", s),
+                    )),
+                    None => errs.push(err_at(
+                        span_of_entry(entry, source),
+                        ConfigErrorKind::InvalidFieldType,
+                        "idle timeout must be a string".into(),
+                    )),
+                }
+            }
+            "pause" if namespace == "iwwc.idle" => match entry.value() {
+                kdl::KdlValue::Bool(b) => decl.pause = *b,
+                _ => errs.push(err_at(
+                    span_of_entry(entry, source),
+                    ConfigErrorKind::InvalidFieldType,
+                    "`pause=` expects #true or #false".into(),
+                )),
+            },
+            "mounts" if namespace == "iwwc.disk" => match entry.value().as_string() {
+                Some(s) => decl.mounts = Some(s.split_whitespace().map(String::from).collect()),
+                None => errs.push(err_at(
+                    span_of_entry(entry, source),
+                    ConfigErrorKind::InvalidFieldType,
+                    "`mounts=` expects a string of mountpoints, e.g. \"/ /home\"".into(),
+                )),
+            },
+            other => errs.push(warn_at(
+                span_of_entry(entry, source),
+                ConfigErrorKind::UnknownField,
+                format!("unknown property `{}=` on `smart {}`", other, namespace),
+            )),
+        }
+    }
+    Some((namespace, decl))
+}
+
 fn node_spec(name: &str) -> Option<(usize, &'static str)> {
     Some(match name {
         "widget" => (
@@ -153,16 +345,22 @@ fn node_spec(name: &str) -> Option<(usize, &'static str)> {
         "container" => (1, "w h padding align_x align_y clip style child"),
         "button" => (
             1,
-            "w h padding action clip style style:hover style:active style:disabled child",
+            "w h padding action action:* clip style style:hover style:active style:disabled child",
         ),
         "row" | "column" => (1, "children w h padding spacing clip align"),
         "text" => (2, "w h align_x align_y color font text"),
+        "input" => (
+            1,
+            "var placeholder password w padding size font style style:hover style:focus on_submit on_change",
+        ),
+        "image" => (1, "src fallback w h fit"),
         "style" => (1, "text bg border shadow snap"),
         "border" => (1, "color w radius"),
         "shadow" => (1, "color offset blur_radius"),
         "font" => (1, "family weight stretch style"),
         "revealer" => (1, "transition active duration child"),
         "event" => (1, "type var action duration child"),
+        "keybind" => (1, "key widget action"),
         "apptray" => (
             0,
             "icon_size spacing padding bg border swap_buttons vertical",
@@ -174,6 +372,10 @@ fn node_spec(name: &str) -> Option<(usize, &'static str)> {
              menu_container_padding menu_container_style button_padding
              button_style button_style_hover button_style_active button_style_disabled",
         ),
+        "taskbar" => (
+            0,
+            "icon_size spacing padding bg border active_bg vertical own_output",
+        ),
         "notification" => (
             0,
             "width primary_text secondary_text bg border font anchor margin gap max dnd
@@ -198,11 +400,13 @@ pub(crate) fn parse_document_into(
     for node in doc.nodes() {
         let name = node.name().value();
         if let Some((max_positional, known)) = node_spec(name) {
-            if max_positional > 0 && first_positional_string(node).as_deref() == Some("apptray") {
+            if max_positional > 0
+                && let Some(id @ ("apptray" | "taskbar")) = first_positional_string(node).as_deref()
+            {
                 errs.push(err_at(
                     span_of_node(node, source),
                     ConfigErrorKind::DuplicateElement,
-                    "\"apptray\" is a reserved id".into(),
+                    format!("\"{id}\" is a reserved id"),
                 ));
                 continue;
             }
@@ -235,6 +439,9 @@ pub(crate) fn parse_document_into(
             "row" => insert!(out.rows, build_row),
             "column" => insert!(out.columns, build_column),
             "text" => insert!(out.texts, build_text),
+            "input" => insert!(out.inputs, build_input),
+            "image" => insert!(out.images, build_image),
+            "keybind" => insert!(out.keybinds, build_keybind),
             "notification" => {
                 let ns = build_notification(node, source, errs);
                 if out.notification.is_some() {
@@ -271,6 +478,14 @@ pub(crate) fn parse_document_into(
                     out.apptraymenu_advanced = Some(m);
                 }
             }
+            "taskbar" => {
+                let t = build_taskbar_settings(node, source, errs);
+                if out.taskbar.is_some() {
+                    errs.push(dup_warning("taskbar block".into(), node, source));
+                } else {
+                    out.taskbar = Some(t);
+                }
+            }
             "icon_theme" => match first_positional_string(node) {
                 Some(_) if out.icon_theme.is_some() => {
                     errs.push(dup_warning("icon_theme".into(), node, source))
@@ -283,6 +498,7 @@ pub(crate) fn parse_document_into(
                         .into(),
                 )),
             },
+            "smart" => insert!(out.smart, build_smart),
             "pull" => {
                 if let Some((id, decl)) = build_pull(node, source, errs) {
                     if out.vars.contains_key(&id) || out.pulls.contains_key(&id) {
@@ -299,6 +515,22 @@ pub(crate) fn parse_document_into(
                     }
                 }
             }
+            "dbus" => {
+                if let Some((id, decl)) = build_dbus(node, source, errs) {
+                    if out.vars.contains_key(&id) {
+                        errs.push(dup_warning(format!("variable {}", id), node, source));
+                    } else {
+                        out.vars.insert(
+                            id.clone(),
+                            VarDecl {
+                                value: decl.default.clone(),
+                                span: decl.span.clone(),
+                            },
+                        );
+                        out.dbus.insert(id, decl);
+                    }
+                }
+            }
             "import" => {
                 let node_span = span_of_node(node, source);
                 let Some(dir) = base_dir else {
@@ -537,7 +769,11 @@ fn check_node(
     if let Some(block) = node.children() {
         for child in block.nodes() {
             let name = child.name().value();
-            if !known.split_whitespace().any(|k| k == name) {
+            let is_known = known.split_whitespace().any(|k| match k.strip_suffix('*') {
+                Some(prefix) => name.starts_with(prefix),
+                None => k == name,
+            });
+            if !is_known {
                 errs.push(warn_at(
                     span_of_node(child, source),
                     ConfigErrorKind::UnknownField,
@@ -737,6 +973,107 @@ pub(crate) fn field_string(
     string_field_from_entry(entry, name, source, errs)
 }
 
+pub(crate) fn field_action(
+    name: &str,
+    node: &kdl::KdlNode,
+    source: &SourceText,
+    errs: &mut Vec<ConfigError>,
+) -> Option<ActionDecl> {
+    let child = field_node(node, name)?;
+    let mut command = None;
+    let mut positional = 0usize;
+    let mut internal = Vec::new();
+    let mut stdout = None;
+    let mut status = None;
+    let mut on_error = None;
+    let mut ok = true;
+    for entry in child.entries() {
+        let Some(prop) = entry.name() else {
+            positional += 1;
+            command = string_field_from_entry(entry, name, source, errs);
+            ok &= command.is_some();
+            continue;
+        };
+        match prop.value() {
+            "stdout" | "status" => {
+                let var = match entry.value() {
+                    kdl::KdlValue::String(s) if !looks_like_expr(s) => Some(s.clone()),
+                    _ => {
+                        errs.push(err_at(
+                            span_of_entry(entry, source),
+                            ConfigErrorKind::InvalidFieldType,
+                            format!("`{}=` on `{}` expects a variable name", prop.value(), name),
+                        ));
+                        None
+                    }
+                };
+                if prop.value() == "stdout" {
+                    stdout = var;
+                } else {
+                    status = var;
+                }
+            }
+            "on_error" => on_error = string_field_from_entry(entry, "on_error", source, errs),
+            p if INTERNAL_ACTIONS.contains(&p) => {
+                match string_field_from_entry(entry, p, source, errs) {
+                    Some(FieldValue::Literal(v)) if parse_internal_action(p, &v).is_none() => {
+                        ok = false;
+                        errs.push(err_at(
+                            span_of_entry(entry, source),
+                            ConfigErrorKind::InvalidFieldType,
+                            format!("invalid `{}=` value \"{}\" on `{}`", p, v, name),
+                        ));
+                    }
+                    Some(v) => internal.push((p.to_string(), v)),
+                    None => ok = false,
+                }
+            }
+            other => errs.push(err_at(
+                span_of_entry(entry, source),
+                ConfigErrorKind::UnknownField,
+                format!(
+                    "unknown property `{}=` on `{}`, expected stdout, status, on_error or one of {}",
+                    other,
+                    name,
+                    INTERNAL_ACTIONS.join(", ")
+                ),
+            )),
+        }
+    }
+    if positional > 1 || (positional == 0 && internal.is_empty() && ok) {
+        errs.push(err_at(
+            span_of_node(child, source),
+            ConfigErrorKind::InvalidFieldType,
+            format!(
+                "field `{}` takes one command and/or internal actions, got {} commands",
+                name, positional
+            ),
+        ));
+        return None;
+    }
+    if positional == 0 && (stdout.is_some() || status.is_some() || on_error.is_some()) {
+        errs.push(err_at(
+            span_of_node(child, source),
+            ConfigErrorKind::InvalidFieldType,
+            format!(
+                "`stdout=`, `status=` and `on_error=` on `{}` need a command",
+                name
+            ),
+        ));
+    }
+    if !ok {
+        return None;
+    }
+    Some(ActionDecl {
+        command,
+        internal,
+        stdout,
+        status,
+        on_error,
+        span: span_of_node(child, source),
+    })
+}
+
 pub(crate) fn field_anchor(
     name: &str,
     node: &kdl::KdlNode,
@@ -1109,7 +1446,7 @@ pub(crate) fn build_event(
         source,
         errs,
         parse_event_type,
-        "onhover, onhoverexit, rightclick, watchon, watchoff or timeout",
+        "onhover, onhoverexit, rightclick, middleclick, doubleclick, scrollup, scrolldown, watchon, watchoff or timeout",
     )? {
         FieldValue::Literal(t) => t,
         FieldValue::Expr(_) => {
@@ -1137,7 +1474,7 @@ pub(crate) fn build_event(
         }
         None => None,
     };
-    let action = field_string("action", node, source, errs);
+    let action = field_action("action", node, source, errs);
     let duration = field_parsed(
         "duration",
         node,
@@ -1158,11 +1495,7 @@ pub(crate) fn build_event(
         );
     }
     use crate::config::primitives::EventType;
-    let is_pointer = matches!(
-        evtype,
-        EventType::OnHover | EventType::OnHoverExit | EventType::RightClick
-    );
-    if is_pointer {
+    if evtype.is_pointer() {
         if child.is_none() {
             event_err(
                 errs,
@@ -1197,7 +1530,7 @@ pub(crate) fn build_event(
                 node,
                 source,
                 ConfigErrorKind::InvalidFieldType,
-                "`child` is only valid for onhover, onhoverexit and rightclick events".into(),
+                "`child` is only valid for pointer events".into(),
             );
         }
     }
@@ -1232,6 +1565,55 @@ pub(crate) fn build_event(
     Some((id, e))
 }
 
+use crate::config::types::Keybind;
+
+pub(crate) fn build_keybind(
+    node: &kdl::KdlNode,
+    source: &SourceText,
+    errs: &mut Vec<ConfigError>,
+) -> Option<(String, Keybind)> {
+    let id = first_positional_string(node)?;
+    let key = field_parsed(
+        "key",
+        node,
+        source,
+        errs,
+        parse_key_chord,
+        "a key with optional modifiers e.g. \"ctrl+q\", \"Escape\"",
+    );
+    let widget = match field_id_ref("widget", node, source, errs) {
+        Some(FieldValue::Literal(w)) => Some(w),
+        Some(FieldValue::Expr(_)) => {
+            errs.push(err_at(
+                span_of_node(node, source),
+                ConfigErrorKind::InvalidFieldType,
+                "keybind `widget` must be a widget name literal, not an expression".into(),
+            ));
+            None
+        }
+        None => None,
+    };
+    let action = field_action("action", node, source, errs);
+    for (missing, field) in [(key.is_none(), "key"), (action.is_none(), "action")] {
+        if missing && field_node(node, field).is_none() {
+            errs.push(err_at(
+                span_of_node(node, source),
+                ConfigErrorKind::MissingRequiredField,
+                format!("keybind `{}` is required", field),
+            ));
+        }
+    }
+    Some((
+        id,
+        Keybind {
+            key,
+            widget,
+            action,
+            span: span_of_node(node, source),
+        },
+    ))
+}
+
 use crate::config::types::Style;
 
 pub(crate) fn build_style(
@@ -1430,7 +1812,7 @@ pub(crate) fn field_children(
     Some(FieldValue::Literal(ids))
 }
 
-use crate::config::types::Button;
+use crate::config::types::{BoundAction, Button};
 
 pub(crate) fn build_button(
     node: &kdl::KdlNode,
@@ -1444,7 +1826,8 @@ pub(crate) fn build_button(
         w: field_length("w", node, source, errs),
         h: field_length("h", node, source, errs),
         padding: field_padding("padding", node, source, errs),
-        action: field_string("action", node, source, errs),
+        action: field_action("action", node, source, errs),
+        bindings: field_bindings(node, source, errs),
         clip: field_bool("clip", node, source, errs),
         style: field_id_ref("style", node, source, errs),
         style_hover: field_id_ref("style:hover", node, source, errs),
@@ -1456,6 +1839,53 @@ pub(crate) fn build_button(
     Some((id, b))
 }
 
+fn field_bindings(
+    node: &kdl::KdlNode,
+    source: &SourceText,
+    errs: &mut Vec<ConfigError>,
+) -> Vec<BoundAction> {
+    let Some(block) = node.children() else {
+        return Vec::new();
+    };
+    let mut out: Vec<BoundAction> = Vec::new();
+    for child in block.nodes() {
+        let name = child.name().value();
+        let Some(qualifier) = name.strip_prefix("action:") else {
+            continue;
+        };
+        let Some((trigger, modifiers)) = parse_action_qualifier(qualifier) else {
+            errs.push(err_at(
+                span_of_node(child, source),
+                ConfigErrorKind::InvalidFieldType,
+                format!(
+                    "invalid `{}`, expected one of right, middle, double, scrollup, scrolldown plus any of shift, ctrl, alt, super",
+                    name
+                ),
+            ));
+            continue;
+        };
+        if out
+            .iter()
+            .any(|b| b.trigger == trigger && b.modifiers == modifiers)
+        {
+            errs.push(warn_at(
+                span_of_node(child, source),
+                ConfigErrorKind::DuplicateElement,
+                format!("`{}` repeats an earlier binding, using first", name),
+            ));
+            continue;
+        }
+        if let Some(action) = field_action(name, node, source, errs) {
+            out.push(BoundAction {
+                trigger,
+                modifiers,
+                action,
+            });
+        }
+    }
+    out
+}
+
 use crate::config::types::Row;
 
 pub(crate) fn build_row(
@@ -1588,6 +2018,86 @@ pub(crate) fn build_text(
     Some((id, t))
 }
 
+use crate::config::types::Input;
+
+pub(crate) fn build_input(
+    node: &kdl::KdlNode,
+    source: &SourceText,
+    errs: &mut Vec<ConfigError>,
+) -> Option<(String, Input)> {
+    let id = first_positional_string(node)?;
+    let var = match field_id_ref("var", node, source, errs) {
+        Some(FieldValue::Literal(v)) => Some(v),
+        Some(FieldValue::Expr(_)) => {
+            errs.push(err_at(
+                span_of_node(node, source),
+                ConfigErrorKind::InvalidFieldType,
+                "input `var` must be a variable name literal, not an expression".into(),
+            ));
+            None
+        }
+        None => {
+            if field_node(node, "var").is_none() {
+                errs.push(err_at(
+                    span_of_node(node, source),
+                    ConfigErrorKind::MissingRequiredField,
+                    "input `var` is required".into(),
+                ));
+            }
+            None
+        }
+    };
+    let i = Input {
+        var,
+        placeholder: field_string("placeholder", node, source, errs),
+        password: field_bool("password", node, source, errs),
+        w: field_length("w", node, source, errs),
+        padding: field_padding("padding", node, source, errs),
+        size: field_f32("size", node, source, errs),
+        font: field_string("font", node, source, errs),
+        style: field_id_ref("style", node, source, errs),
+        style_hover: field_id_ref("style:hover", node, source, errs),
+        style_focus: field_id_ref("style:focus", node, source, errs),
+        on_submit: field_action("on_submit", node, source, errs),
+        on_change: field_action("on_change", node, source, errs),
+        span: span_of_node(node, source),
+    };
+    Some((id, i))
+}
+
+use crate::config::types::Image;
+
+pub(crate) fn build_image(
+    node: &kdl::KdlNode,
+    source: &SourceText,
+    errs: &mut Vec<ConfigError>,
+) -> Option<(String, Image)> {
+    let id = first_positional_string(node)?;
+    let i = Image {
+        src: field_string("src", node, source, errs),
+        fallback: field_string("fallback", node, source, errs),
+        w: field_length("w", node, source, errs),
+        h: field_length("h", node, source, errs),
+        fit: field_parsed(
+            "fit",
+            node,
+            source,
+            errs,
+            parse_content_fit,
+            "contain, cover, fill, none or scale-down",
+        ),
+        span: span_of_node(node, source),
+    };
+    if i.src.is_none() && i.fallback.is_none() {
+        errs.push(err_at(
+            i.span.clone(),
+            ConfigErrorKind::MissingRequiredField,
+            "image needs `src` or `fallback`".into(),
+        ));
+    }
+    Some((id, i))
+}
+
 use crate::config::types::Shadow;
 
 pub(crate) fn build_shadow(
@@ -1624,6 +2134,26 @@ pub(crate) fn build_apptray_settings(
     }
 }
 
+use crate::config::types::TaskbarSettings;
+
+pub(crate) fn build_taskbar_settings(
+    node: &kdl::KdlNode,
+    source: &SourceText,
+    errs: &mut Vec<ConfigError>,
+) -> TaskbarSettings {
+    TaskbarSettings {
+        icon_size: field_f32("icon_size", node, source, errs),
+        spacing: field_f32("spacing", node, source, errs),
+        padding: field_padding("padding", node, source, errs),
+        bg: field_color("bg", node, source, errs),
+        border: field_id_ref("border", node, source, errs),
+        active_bg: field_color("active_bg", node, source, errs),
+        vertical: field_bool("vertical", node, source, errs),
+        own_output: field_bool("own_output", node, source, errs),
+        span: span_of_node(node, source),
+    }
+}
+
 use crate::config::types::{ApptrayMenuAdvancedSettings, ApptrayMenuSettings};
 
 pub(crate) fn build_apptraymenu_settings(
@@ -2458,6 +2988,156 @@ mod tests {
                 kdl: "button btn1 { child t1; action 42 }",
                 expect: Expect::Err("field `action` expects a string"),
             },
+            Case {
+                label: "action capture props",
+                kdl: r#"button btn1 { child t1; action "vpn up" stdout=out status=code on_error="true" }"#,
+                expect: Expect::Ok,
+            },
+            Case {
+                label: "action unknown prop",
+                kdl: r#"button btn1 { child t1; action "true" stderr=out }"#,
+                expect: Expect::Err(
+                    "unknown property `stderr=` on `action`, expected stdout, status, on_error or one of update, open, close, toggle, refresh, dnd, dismiss",
+                ),
+            },
+            Case {
+                label: "action capture expr",
+                kdl: r#"button btn1 { child t1; action "true" stdout="${x}" }"#,
+                expect: Expect::Err("`stdout=` on `action` expects a variable name"),
+            },
+            Case {
+                label: "action internal only",
+                kdl: r#"button btn1 { child t1; action update="folded=#true" open="powerbuttons" dnd="1" dismiss="all" }"#,
+                expect: Expect::Ok,
+            },
+            Case {
+                label: "action internal with command",
+                kdl: r#"button btn1 { child t1; action "notify-send hi" toggle="panel" refresh="battery" }"#,
+                expect: Expect::Ok,
+            },
+            Case {
+                label: "action internal invalid literal",
+                kdl: r#"button btn1 { child t1; action update="folded" }"#,
+                expect: Expect::Err("invalid `update=` value \"folded\" on `action`"),
+            },
+            Case {
+                label: "action internal expr deferred",
+                kdl: r#"button btn1 { child t1; action open="${target}" }"#,
+                expect: Expect::Ok,
+            },
+            Case {
+                label: "action empty",
+                kdl: "button btn1 { child t1; action }",
+                expect: Expect::Err(
+                    "field `action` takes one command and/or internal actions, got 0 commands",
+                ),
+            },
+            Case {
+                label: "action without command",
+                kdl: r#"button btn1 { child t1; action dnd="1" status=code }"#,
+                expect: Expect::Err(
+                    "`stdout=`, `status=` and `on_error=` on `action` need a command",
+                ),
+            },
+        ]);
+    }
+
+    #[test]
+    fn keybind() {
+        run_cases(&[
+            Case {
+                label: "full",
+                kdl: r#"keybind quit { key "ctrl+q"; widget menu; action close="menu" }"#,
+                expect: Expect::Ok,
+            },
+            Case {
+                label: "bad key",
+                kdl: r#"keybind k { key "ctrl+nope"; action "true" }"#,
+                expect: Expect::Err(
+                    "invalid `key` \"ctrl+nope\", expected a key with optional modifiers e.g. \"ctrl+q\", \"Escape\"",
+                ),
+            },
+            Case {
+                label: "missing key",
+                kdl: r#"keybind k { action "true" }"#,
+                expect: Expect::Err("keybind `key` is required"),
+            },
+            Case {
+                label: "missing action",
+                kdl: r#"keybind k { key "Escape" }"#,
+                expect: Expect::Err("keybind `action` is required"),
+            },
+            Case {
+                label: "unknown field",
+                kdl: r#"keybind k { key "q"; action "true"; child t1 }"#,
+                expect: Expect::Warn("unknown field `child` on `keybind`"),
+            },
+        ]);
+    }
+
+    #[test]
+    fn input() {
+        run_cases(&[
+            Case {
+                label: "full",
+                kdl: r#"input search { var query; placeholder "Search"; password #false; w fill; size 14; style s1; style:focus s2; on_submit "xdg-open $IWWC_INPUT" close="launcher"; on_change "true" }"#,
+                expect: Expect::Ok,
+            },
+            Case {
+                label: "missing var",
+                kdl: r#"input i1 { placeholder "x" }"#,
+                expect: Expect::Err("input `var` is required"),
+            },
+            Case {
+                label: "expr var",
+                kdl: r#"input i1 { var "${name}" }"#,
+                expect: Expect::Err(
+                    "input `var` must be a variable name literal, not an expression",
+                ),
+            },
+            Case {
+                label: "bad password",
+                kdl: r#"input i1 { var q; password "yes" }"#,
+                expect: Expect::Err("invalid bool, expected #true or #false"),
+            },
+            Case {
+                label: "style:active is a button state",
+                kdl: r#"input i1 { var q; style:active s1 }"#,
+                expect: Expect::Warn("unknown field `style:active` on `input`"),
+            },
+        ]);
+    }
+
+    #[test]
+    fn image() {
+        run_cases(&[
+            Case {
+                label: "full",
+                kdl: r#"image cover { src "${iwwc.mpris.art}"; fallback "audio-x-generic"; w 48; h 48; fit cover }"#,
+                expect: Expect::Ok,
+            },
+            Case {
+                label: "fallback only",
+                kdl: r#"image logo { fallback "distributor-logo" }"#,
+                expect: Expect::Ok,
+            },
+            Case {
+                label: "nothing to show",
+                kdl: "image i1 { w 48 }",
+                expect: Expect::Err("image needs `src` or `fallback`"),
+            },
+            Case {
+                label: "bad fit",
+                kdl: r#"image i1 { src "/a.png"; fit stretch }"#,
+                expect: Expect::Err(
+                    "invalid `fit` \"stretch\", expected contain, cover, fill, none or scale-down",
+                ),
+            },
+            Case {
+                label: "unknown field",
+                kdl: r#"image i1 { src "/a.png"; child t1 }"#,
+                expect: Expect::Warn("unknown field `child` on `image`"),
+            },
         ]);
     }
 
@@ -2665,6 +3345,32 @@ mod tests {
         ]);
     }
 
+    #[test]
+    fn taskbar_block() {
+        run_cases(&[
+            Case {
+                label: "ref as child",
+                kdl: "widget bar { child taskbar }\ntaskbar { icon_size 20; own_output #true }",
+                expect: Expect::Ok,
+            },
+            Case {
+                label: "active bg",
+                kdl: "taskbar { active_bg 44475a; vertical #true }",
+                expect: Expect::Ok,
+            },
+            Case {
+                label: "reserved id",
+                kdl: "text taskbar",
+                expect: Expect::Err("\"taskbar\" is a reserved id"),
+            },
+            Case {
+                label: "duplicate block",
+                kdl: "taskbar { icon_size 24 }\ntaskbar { icon_size 30 }",
+                expect: Expect::Warn("taskbar block is defined twice, using first"),
+            },
+        ]);
+    }
+
     #[test]
     fn notification_block() {
         run_cases(&[
@@ -2715,6 +3421,89 @@ mod tests {
         ]);
     }
 
+    #[test]
+    fn smart_block() {
+        run_cases(&[
+            Case {
+                label: "disk mounts",
+                kdl: r#"smart iwwc.disk mounts="/ /home""#,
+                expect: Expect::Ok,
+            },
+            Case {
+                label: "unknown namespace",
+                kdl: r#"smart iwwc.nope mounts="/""#,
+                expect: Expect::Err("unknown smart namespace \"iwwc.nope\""),
+            },
+            Case {
+                label: "missing namespace",
+                kdl: r#"smart mounts="/""#,
+                expect: Expect::Err(
+                    "smart requires a namespace, e.g. smart iwwc.disk mounts=\"/ /home\"",
+                ),
+            },
+            Case {
+                label: "interval",
+                kdl: r#"smart iwwc.cpu interval="2s""#,
+                expect: Expect::Ok,
+            },
+            Case {
+                label: "interval shorthand",
+                kdl: r#"smart iwwc.temps i="10s""#,
+                expect: Expect::Ok,
+            },
+            Case {
+                label: "bad interval",
+                kdl: r#"smart iwwc.cpu interval="soon""#,
+                expect: Expect::Err("invalid interval \"soon\""),
+            },
+            Case {
+                label: "interval not a string",
+                kdl: r#"smart iwwc.cpu interval=2"#,
+                expect: Expect::Err("smart interval must be a string"),
+            },
+            Case {
+                label: "event-driven namespace has no interval",
+                kdl: r#"smart iwwc.activesong interval="2s""#,
+                expect: Expect::Warn("unknown property `interval=` on `smart iwwc.activesong`"),
+            },
+            Case {
+                label: "mounts on another namespace",
+                kdl: r#"smart iwwc.cpu mounts="/""#,
+                expect: Expect::Warn("unknown property `mounts=` on `smart iwwc.cpu`"),
+            },
+            Case {
+                label: "mounts not a string",
+                kdl: r#"smart iwwc.disk mounts=1"#,
+                expect: Expect::Err("`mounts=` expects a string of mountpoints, e.g. \"/ /home\""),
+            },
+            Case {
+                label: "idle timeout and pause",
+                kdl: r#"smart iwwc.idle timeout="10m" pause=#true"#,
+                expect: Expect::Ok,
+            },
+            Case {
+                label: "bad idle timeout",
+                kdl: r#"smart iwwc.idle timeout="soon""#,
+                expect: Expect::Err("invalid timeout \"soon\""),
+            },
+            Case {
+                label: "pause not a bool",
+                kdl: r#"smart iwwc.idle pause="yes""#,
+                expect: Expect::Err("`pause=` expects #true or #false"),
+            },
+            Case {
+                label: "timeout on another namespace",
+                kdl: r#"smart iwwc.cpu timeout="1m""#,
+                expect: Expect::Warn("unknown property `timeout=` on `smart iwwc.cpu`"),
+            },
+            Case {
+                label: "duplicate",
+                kdl: "smart iwwc.disk mounts=\"/\"\nsmart iwwc.disk mounts=\"/home\"",
+                expect: Expect::Warn("smart iwwc.disk is defined twice, using first"),
+            },
+        ]);
+    }
+
     #[test]
     fn pull_block() {
         run_cases(&[
@@ -2761,6 +3550,58 @@ mod tests {
         ]);
     }
 
+    #[test]
+    fn dbus_block() {
+        macro_rules! profile {
+            () => {
+                r#"service="net.hadess.PowerProfiles" path="/net/hadess/PowerProfiles" interface="net.hadess.PowerProfiles" property="ActiveProfile""#
+            };
+        }
+        run_cases(&[
+            Case {
+                label: "basic",
+                kdl: concat!(r#"dbus profile bus="system" "#, profile!()),
+                expect: Expect::Ok,
+            },
+            Case {
+                label: "typed default",
+                kdl: concat!(r#"dbus profile bus="system" default=0 "#, profile!()),
+                expect: Expect::Ok,
+            },
+            Case {
+                label: "no name",
+                kdl: concat!(r#"dbus bus="system" "#, profile!()),
+                expect: Expect::Err(
+                    "dbus requires a variable name, e.g. dbus profile bus=\"system\" ...",
+                ),
+            },
+            Case {
+                label: "bad bus",
+                kdl: concat!(r#"dbus profile bus="user" "#, profile!()),
+                expect: Expect::Err("dbus bus must be \"system\" or \"session\""),
+            },
+            Case {
+                label: "missing property",
+                kdl: r#"dbus profile bus="system" service="a.b" path="/a" interface="a.b""#,
+                expect: Expect::Err("dbus requires property="),
+            },
+            Case {
+                label: "relative path",
+                kdl: r#"dbus profile bus="system" service="a.b" path="a" interface="a.b" property="P""#,
+                expect: Expect::Err("dbus path \"a\" must start with /"),
+            },
+            Case {
+                label: "dup name",
+                kdl: concat!(
+                    "var profile=1\n",
+                    r#"dbus profile bus="system" "#,
+                    profile!()
+                ),
+                expect: Expect::Warn("variable profile is defined twice, using first"),
+            },
+        ]);
+    }
+
     #[test]
     fn event_field_matrix() {
         run_cases(&[
@@ -2814,9 +3655,7 @@ mod tests {
             Case {
                 label: "watch forbids child",
                 kdl: "var flag=#false\nevent e1 { type watchon; var flag; action \"true\"; child t1 }\ntext t1",
-                expect: Expect::Err(
-                    "`child` is only valid for onhover, onhoverexit and rightclick events",
-                ),
+                expect: Expect::Err("`child` is only valid for pointer events"),
             },
             Case {
                 label: "timeout requires duration",
@@ -2837,7 +3676,7 @@ mod tests {
                 label: "type unrecognized",
                 kdl: "event e1 { type bogus; action \"true\"; child t1 }\ntext t1",
                 expect: Expect::Err(
-                    "invalid `type` \"bogus\", expected onhover, onhoverexit, rightclick, watchon, watchoff or timeout",
+                    "invalid `type` \"bogus\", expected onhover, onhoverexit, rightclick, middleclick, doubleclick, scrollup, scrolldown, watchon, watchoff or timeout",
                 ),
             },
         ]);
@@ -2,6 +2,8 @@ use iced::Color;
 use iced::advanced::text::Alignment as TextAlignment;
 use iced::alignment::{Horizontal, Vertical};
 use iced::font::{Stretch, Style as FontStyle, Weight};
+use iced::keyboard::key::Named;
+use iced::keyboard::{Key, Modifiers};
 use iced_layershell::reexport::{Anchor, Layer, OutputOption};
 use std::str::FromStr;
 
@@ -179,11 +181,67 @@ pub enum EventType {
     OnHover,
     OnHoverExit,
     RightClick,
+    MiddleClick,
+    DoubleClick,
+    ScrollUp,
+    ScrollDown,
     WatchOn,
     WatchOff,
     Timeout,
 }
 
+impl EventType {
+    pub fn is_pointer(self) -> bool {
+        !matches!(
+            self,
+            EventType::WatchOn | EventType::WatchOff | EventType::Timeout
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PointerTrigger {
+    #[default]
+    Left,
+    Right,
+    Middle,
+    Double,
+    ScrollUp,
+    ScrollDown,
+}
+
+pub fn parse_action_qualifier(s: &str) -> Option<(PointerTrigger, Modifiers)> {
+    let mut trigger = None;
+    let mut modifiers = Modifiers::empty();
+    for part in s.split(':') {
+        let t = match part {
+            "right" => PointerTrigger::Right,
+            "middle" => PointerTrigger::Middle,
+            "double" => PointerTrigger::Double,
+            "scrollup" => PointerTrigger::ScrollUp,
+            "scrolldown" => PointerTrigger::ScrollDown,
+            _ => {
+                let m = match part {
+                    "shift" => Modifiers::SHIFT,
+                    "ctrl" => Modifiers::CTRL,
+                    "alt" => Modifiers::ALT,
+                    "super" => Modifiers::LOGO,
+                    _ => return None,
+                };
+                if modifiers.contains(m) {
+                    return None;
+                }
+                modifiers |= m;
+                continue;
+            }
+        };
+        if trigger.replace(t).is_some() {
+            return None;
+        }
+    }
+    Some((trigger.unwrap_or_default(), modifiers))
+}
+
 pub fn parse_transition(s: &str) -> Option<Transition> {
     match s.trim() {
         "none" => Some(Transition::None),
@@ -195,11 +253,58 @@ pub fn parse_transition(s: &str) -> Option<Transition> {
     }
 }
 
+pub fn parse_content_fit(s: &str) -> Option<iced::ContentFit> {
+    match s.trim() {
+        "contain" => Some(iced::ContentFit::Contain),
+        "cover" => Some(iced::ContentFit::Cover),
+        "fill" => Some(iced::ContentFit::Fill),
+        "none" => Some(iced::ContentFit::None),
+        "scale-down" => Some(iced::ContentFit::ScaleDown),
+        _ => None,
+    }
+}
+
+/// A local path from `file:///...` (percent-decoded) or a plain absolute
+/// path. Remote URLs give `None`.
+pub fn local_image_path(src: &str) -> Option<std::path::PathBuf> {
+    let src = src.trim();
+    let path = match src.strip_prefix("file://") {
+        Some(rest) => {
+            let bytes = rest.as_bytes();
+            let mut out = Vec::with_capacity(bytes.len());
+            let mut i = 0;
+            while i < bytes.len() {
+                let hex = bytes
+                    .get(i + 1..i + 3)
+                    .and_then(|h| std::str::from_utf8(h).ok())
+                    .and_then(|h| u8::from_str_radix(h, 16).ok());
+                match (bytes[i], hex) {
+                    (b'%', Some(b)) => {
+                        out.push(b);
+                        i += 3;
+                    }
+                    (c, _) => {
+                        out.push(c);
+                        i += 1;
+                    }
+                }
+            }
+            String::from_utf8(out).ok()?
+        }
+        None => src.to_string(),
+    };
+    path.starts_with('/').then(|| path.into())
+}
+
 pub fn parse_event_type(s: &str) -> Option<EventType> {
     match s.trim() {
         "onhover" => Some(EventType::OnHover),
         "onhoverexit" => Some(EventType::OnHoverExit),
         "rightclick" => Some(EventType::RightClick),
+        "middleclick" => Some(EventType::MiddleClick),
+        "doubleclick" => Some(EventType::DoubleClick),
+        "scrollup" => Some(EventType::ScrollUp),
+        "scrolldown" => Some(EventType::ScrollDown),
         "watchon" => Some(EventType::WatchOn),
         "watchoff" => Some(EventType::WatchOff),
         "timeout" => Some(EventType::Timeout),
@@ -207,6 +312,454 @@ pub fn parse_event_type(s: &str) -> Option<EventType> {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyChord {
+    pub modifiers: Modifiers,
+    pub key: Key,
+}
+
+impl KeyChord {
+    pub fn matches(&self, key: &Key, modifiers: Modifiers) -> bool {
+        if self.modifiers != modifiers {
+            return false;
+        }
+        match (&self.key, key) {
+            (Key::Character(want), Key::Character(got)) => want.as_str() == got.to_lowercase(),
+            (want, got) => want == got,
+        }
+    }
+
+    /// Whether the chord should still fire while a text input has focus.
+    /// Plain keys are left to the input; Escape and chords with ctrl, alt
+    /// or super are not text and reach the keybind.
+    pub fn fires_over_input(&self) -> bool {
+        self.key == Key::Named(Named::Escape)
+            || self
+                .modifiers
+                .intersects(Modifiers::CTRL | Modifiers::ALT | Modifiers::LOGO)
+    }
+}
+
+pub fn parse_key_chord(s: &str) -> Option<KeyChord> {
+    let mut modifiers = Modifiers::empty();
+    let mut key = None;
+    for part in s.trim().split('+') {
+        let lower = part.trim().to_lowercase();
+        let m = match lower.as_str() {
+            "shift" => Modifiers::SHIFT,
+            "ctrl" | "control" => Modifiers::CTRL,
+            "alt" => Modifiers::ALT,
+            "super" | "logo" => Modifiers::LOGO,
+            _ => {
+                if key.replace(parse_key(&lower)?).is_some() {
+                    return None;
+                }
+                continue;
+            }
+        };
+        modifiers |= m;
+    }
+    Some(KeyChord {
+        modifiers,
+        key: key?,
+    })
+}
+
+fn parse_key(s: &str) -> Option<Key> {
+    let named = match s {
+        "escape" | "esc" => Named::Escape,
+        "enter" | "return" => Named::Enter,
+        "tab" => Named::Tab,
+        "space" => Named::Space,
+        "backspace" => Named::Backspace,
+        "delete" | "del" => Named::Delete,
+        "insert" => Named::Insert,
+        "up" => Named::ArrowUp,
+        "down" => Named::ArrowDown,
+        "left" => Named::ArrowLeft,
+        "right" => Named::ArrowRight,
+        "home" => Named::Home,
+        "end" => Named::End,
+        "pageup" => Named::PageUp,
+        "pagedown" => Named::PageDown,
+        "print" => Named::PrintScreen,
+        "f1" => Named::F1,
+        "f2" => Named::F2,
+        "f3" => Named::F3,
+        "f4" => Named::F4,
+        "f5" => Named::F5,
+        "f6" => Named::F6,
+        "f7" => Named::F7,
+        "f8" => Named::F8,
+        "f9" => Named::F9,
+        "f10" => Named::F10,
+        "f11" => Named::F11,
+        "f12" => Named::F12,
+        _ if s.chars().count() == 1 => return Some(Key::Character(s.into())),
+        _ => return None,
+    };
+    Some(Key::Named(named))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InternalAction {
+    Update {
+        name: String,
+        value: String,
+    },
+    Open(String),
+    Close(String),
+    Toggle(String),
+    Refresh(String),
+    Dnd(u8),
+    Dismiss(Option<u32>),
+    /// Player name (as in `iwwc.mpris.players`), `None` for the active one.
+    Mpris(Option<String>, MprisCommand),
+    /// `true` for the default source, else the default sink.
+    Audio(bool, AudioCommand),
+    /// Name, id or key from `iwwc.workspaces.list`.
+    Workspace(String),
+    /// Backlight device directory name, `None` for the preferred one.
+    Brightness(Option<String>, BrightnessChange),
+    Network(NetworkCommand),
+    Bluetooth(BluetoothCommand),
+    Power(PowerCommand),
+    /// logind idle/sleep inhibitor; `None` toggles.
+    Inhibit(Option<bool>),
+    DbusCall(DbusCall),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DbusBus {
+    System,
+    Session,
+}
+
+pub fn parse_dbus_bus(s: &str) -> Option<DbusBus> {
+    match s {
+        "system" => Some(DbusBus::System),
+        "session" => Some(DbusBus::Session),
+        _ => None,
+    }
+}
+
+/// One method argument, typed by a `busctl`-style signature character.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DbusArg {
+    Bool(bool),
+    Byte(u8),
+    I16(i16),
+    U16(u16),
+    I32(i32),
+    U32(u32),
+    I64(i64),
+    U64(u64),
+    Double(f64),
+    Str(String),
+    ObjectPath(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DbusCall {
+    pub bus: DbusBus,
+    pub service: String,
+    pub path: String,
+    pub interface: String,
+    pub method: String,
+    pub args: Vec<DbusArg>,
+}
+
+fn parse_dbus_arg(kind: char, s: &str) -> Option<DbusArg> {
+    Some(match kind {
+        'b' => DbusArg::Bool(match s {
+            "true" | "yes" | "1" => true,
+            "false" | "no" | "0" => false,
+            _ => return None,
+        }),
+        'y' => DbusArg::Byte(s.parse().ok()?),
+        'n' => DbusArg::I16(s.parse().ok()?),
+        'q' => DbusArg::U16(s.parse().ok()?),
+        'i' => DbusArg::I32(s.parse().ok()?),
+        'u' => DbusArg::U32(s.parse().ok()?),
+        'x' => DbusArg::I64(s.parse().ok()?),
+        't' => DbusArg::U64(s.parse().ok()?),
+        'd' => DbusArg::Double(s.parse().ok()?),
+        's' => DbusArg::Str(s.to_string()),
+        'o' if s.starts_with('/') => DbusArg::ObjectPath(s.to_string()),
+        _ => return None,
+    })
+}
+
+/// `<bus> <service> <path> <interface> <method> [<signature> <arg>...]`,
+/// like `busctl call`. Only basic types; arguments are split on
+/// whitespace.
+fn parse_dbus_call(value: &str) -> Option<InternalAction> {
+    let mut words = value.split_whitespace();
+    let bus = parse_dbus_bus(words.next()?)?;
+    let service = words.next()?.to_string();
+    let path = words.next()?.to_string();
+    let interface = words.next()?.to_string();
+    let method = words.next()?.to_string();
+    if !path.starts_with('/') {
+        return None;
+    }
+    let signature = words.next().unwrap_or("");
+    let rest: Vec<&str> = words.collect();
+    if signature.chars().count() != rest.len() {
+        return None;
+    }
+    let args = signature
+        .chars()
+        .zip(rest)
+        .map(|(kind, s)| parse_dbus_arg(kind, s))
+        .collect::<Option<Vec<_>>>()?;
+    Some(InternalAction::DbusCall(DbusCall {
+        bus,
+        service,
+        path,
+        interface,
+        method,
+        args,
+    }))
+}
+
+/// logind session and machine actions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PowerCommand {
+    Suspend,
+    Hibernate,
+    Reboot,
+    PowerOff,
+    Lock,
+}
+
+fn parse_power(value: &str) -> Option<InternalAction> {
+    let command = match value {
+        "suspend" => PowerCommand::Suspend,
+        "hibernate" => PowerCommand::Hibernate,
+        "reboot" => PowerCommand::Reboot,
+        "poweroff" => PowerCommand::PowerOff,
+        "lock" => PowerCommand::Lock,
+        _ => return None,
+    };
+    Some(InternalAction::Power(command))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BluetoothCommand {
+    /// First adapter; `None` toggles.
+    Power(Option<bool>),
+    /// Key from `iwwc.bluetooth.devices`, name or address.
+    Connect(String),
+    Disconnect(String),
+}
+
+/// `power`, `power:on`, `power:off`, `connect:<device>` or
+/// `disconnect:<device>`.
+fn parse_bluetooth(value: &str) -> Option<InternalAction> {
+    let (verb, arg) = match value.split_once(':') {
+        Some((v, a)) => (v.trim(), a.trim()),
+        None => (value, ""),
+    };
+    let command = match (verb, arg) {
+        ("power", "" | "toggle") => BluetoothCommand::Power(None),
+        ("power", "on") => BluetoothCommand::Power(Some(true)),
+        ("power", "off") => BluetoothCommand::Power(Some(false)),
+        ("connect", d) if !d.is_empty() => BluetoothCommand::Connect(d.to_string()),
+        ("disconnect", d) if !d.is_empty() => BluetoothCommand::Disconnect(d.to_string()),
+        _ => return None,
+    };
+    Some(InternalAction::Bluetooth(command))
+}
+
+/// A saved NetworkManager connection, by id or `iwwc.network.connections` key.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NetworkCommand {
+    Up(String),
+    Down(String),
+}
+
+/// `home`, `up:home` or `down:home`.
+fn parse_network(value: &str) -> Option<InternalAction> {
+    let command = match value.split_once(':') {
+        Some(("up", name)) => NetworkCommand::Up(name.trim().to_string()),
+        Some(("down", name)) => NetworkCommand::Down(name.trim().to_string()),
+        _ => NetworkCommand::Up(value.to_string()),
+    };
+    match &command {
+        NetworkCommand::Up(name) | NetworkCommand::Down(name) if name.is_empty() => None,
+        _ => Some(InternalAction::Network(command)),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BrightnessChange {
+    /// Percent.
+    Set(f64),
+    /// Percent points.
+    Adjust(f64),
+}
+
+/// `50`, `+5`, `-5`, optionally prefixed by a device and `:`, e.g.
+/// `intel_backlight:+5`.
+fn parse_brightness(value: &str) -> Option<InternalAction> {
+    let (device, v) = match value.split_once(':') {
+        Some((d, v)) if !d.trim().is_empty() => (Some(d.trim().to_string()), v.trim()),
+        Some(_) => return None,
+        None => (None, value),
+    };
+    let n: f64 = v.parse().ok().filter(|n: &f64| n.is_finite())?;
+    let change = if v.starts_with(['+', '-']) {
+        BrightnessChange::Adjust(n)
+    } else {
+        BrightnessChange::Set(n)
+    };
+    Some(InternalAction::Brightness(device, change))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AudioCommand {
+    /// Percent.
+    SetVolume(f64),
+    /// Percent points; the result stays within 0-100.
+    AdjustVolume(f64),
+    /// `None` toggles.
+    Mute(Option<bool>),
+}
+
+/// `source:` or `sink:` prefix, defaulting to the sink.
+fn audio_device(value: &str) -> (bool, &str) {
+    match value.split_once(':') {
+        Some(("source", rest)) => (true, rest.trim()),
+        Some(("sink", rest)) => (false, rest.trim()),
+        _ => (false, value),
+    }
+}
+
+/// `40`, `+5`, `-5`, optionally prefixed by `source:`.
+fn parse_volume_action(value: &str) -> Option<InternalAction> {
+    let (source, v) = audio_device(value);
+    let n: f64 = v.parse().ok().filter(|n: &f64| n.is_finite())?;
+    let command = if v.starts_with(['+', '-']) {
+        AudioCommand::AdjustVolume(n)
+    } else {
+        AudioCommand::SetVolume(n)
+    };
+    Some(InternalAction::Audio(source, command))
+}
+
+/// `toggle`, `on` or `off`, optionally prefixed by `source:`.
+fn parse_mute_action(value: &str) -> Option<InternalAction> {
+    let (source, v) = audio_device(value);
+    let mute = match v {
+        "toggle" => None,
+        "on" => Some(true),
+        "off" => Some(false),
+        _ => return None,
+    };
+    Some(InternalAction::Audio(source, AudioCommand::Mute(mute)))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MprisCommand {
+    PlayPause,
+    Play,
+    Pause,
+    Stop,
+    Next,
+    Previous,
+    /// Relative, µs.
+    Seek(i64),
+}
+
+/// `play-pause`, `next`, `seek +5`, ... optionally prefixed by a player
+/// name and `:`, e.g. `spotify:next`.
+fn parse_mpris(value: &str) -> Option<InternalAction> {
+    let (player, command) = match value.split_once(':') {
+        Some((p, c)) if !p.trim().is_empty() => (Some(p.trim().to_string()), c.trim()),
+        Some(_) => return None,
+        None => (None, value),
+    };
+    let command = match command {
+        "play-pause" => MprisCommand::PlayPause,
+        "play" => MprisCommand::Play,
+        "pause" => MprisCommand::Pause,
+        "stop" => MprisCommand::Stop,
+        "next" => MprisCommand::Next,
+        "previous" => MprisCommand::Previous,
+        _ => {
+            let secs: f64 = command.strip_prefix("seek ")?.trim().parse().ok()?;
+            MprisCommand::Seek((secs * 1_000_000.0) as i64)
+        }
+    };
+    Some(InternalAction::Mpris(player, command))
+}
+
+pub const INTERNAL_ACTIONS: &[&str] = &[
+    "update",
+    "open",
+    "close",
+    "toggle",
+    "refresh",
+    "dnd",
+    "dismiss",
+    "mpris",
+    "volume",
+    "mute",
+    "workspace",
+    "brightness",
+    "network",
+    "bluetooth",
+    "power",
+    "inhibit",
+    "dbus-call",
+];
+
+pub fn parse_internal_action(prop: &str, value: &str) -> Option<InternalAction> {
+    let value = value.trim();
+    let target = || (!value.is_empty()).then(|| value.to_string());
+    match prop {
+        "update" => {
+            let (name, v) = value.split_once('=')?;
+            let name = name.trim();
+            (!name.is_empty()).then(|| InternalAction::Update {
+                name: name.to_string(),
+                value: v.trim().to_string(),
+            })
+        }
+        "open" => target().map(InternalAction::Open),
+        "close" => target().map(InternalAction::Close),
+        "toggle" => target().map(InternalAction::Toggle),
+        "refresh" => target().map(InternalAction::Refresh),
+        "dnd" => value
+            .parse::<u8>()
+            .ok()
+            .filter(|v| *v <= 2)
+            .map(InternalAction::Dnd),
+        "dismiss" if value == "all" => Some(InternalAction::Dismiss(None)),
+        "dismiss" => value
+            .parse()
+            .ok()
+            .map(|id| InternalAction::Dismiss(Some(id))),
+        "mpris" => parse_mpris(value),
+        "volume" => parse_volume_action(value),
+        "mute" => parse_mute_action(value),
+        "workspace" => target().map(InternalAction::Workspace),
+        "brightness" => parse_brightness(value),
+        "network" => parse_network(value),
+        "bluetooth" => parse_bluetooth(value),
+        "power" => parse_power(value),
+        "dbus-call" => parse_dbus_call(value),
+        "inhibit" => match value {
+            "toggle" => Some(InternalAction::Inhibit(None)),
+            "on" => Some(InternalAction::Inhibit(Some(true))),
+            "off" => Some(InternalAction::Inhibit(Some(false))),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -234,10 +787,309 @@ mod tests {
         assert_eq!(parse_transition(" slideleft "), Some(Transition::SlideLeft));
     }
 
+    #[test]
+    fn content_fit_and_image_paths() {
+        use super::{local_image_path, parse_content_fit};
+        use std::path::PathBuf;
+        assert_eq!(parse_content_fit("cover"), Some(iced::ContentFit::Cover));
+        assert_eq!(
+            parse_content_fit("scale-down"),
+            Some(iced::ContentFit::ScaleDown)
+        );
+        assert_eq!(parse_content_fit("stretch"), None);
+        assert_eq!(
+            local_image_path("file:///home/me/My%20Music/cover.jpg"),
+            Some(PathBuf::from("/home/me/My Music/cover.jpg"))
+        );
+        assert_eq!(
+            local_image_path("/tmp/a.png"),
+            Some(PathBuf::from("/tmp/a.png"))
+        );
+        assert_eq!(local_image_path("file:///50%"), Some(PathBuf::from("/50%")));
+        assert_eq!(local_image_path("https://i.scdn.co/image/ab67"), None);
+        assert_eq!(local_image_path(""), None);
+    }
+
     #[test]
     fn transition_rejects_garbage() {
         use super::parse_transition;
         assert_eq!(parse_transition("slidleft"), None);
         assert_eq!(parse_transition(""), None);
     }
+
+    #[test]
+    fn internal_actions_parse() {
+        use super::{InternalAction, parse_internal_action};
+        assert_eq!(
+            parse_internal_action("update", "songfolded=#true"),
+            Some(InternalAction::Update {
+                name: "songfolded".into(),
+                value: "#true".into()
+            })
+        );
+        assert_eq!(
+            parse_internal_action("update", "x="),
+            Some(InternalAction::Update {
+                name: "x".into(),
+                value: String::new()
+            })
+        );
+        assert_eq!(parse_internal_action("update", "=1"), None);
+        assert_eq!(parse_internal_action("update", "noequals"), None);
+        assert_eq!(
+            parse_internal_action("open", " powerbuttons "),
+            Some(InternalAction::Open("powerbuttons".into()))
+        );
+        assert_eq!(parse_internal_action("toggle", ""), None);
+        assert_eq!(
+            parse_internal_action("dnd", "2"),
+            Some(InternalAction::Dnd(2))
+        );
+        assert_eq!(parse_internal_action("dnd", "3"), None);
+        assert_eq!(
+            parse_internal_action("dismiss", "all"),
+            Some(InternalAction::Dismiss(None))
+        );
+        assert_eq!(
+            parse_internal_action("dismiss", "7"),
+            Some(InternalAction::Dismiss(Some(7)))
+        );
+        assert_eq!(parse_internal_action("bogus", "x"), None);
+    }
+
+    #[test]
+    fn audio_actions_parse() {
+        use super::{AudioCommand, InternalAction, parse_internal_action};
+        assert_eq!(
+            parse_internal_action("volume", "+5"),
+            Some(InternalAction::Audio(
+                false,
+                AudioCommand::AdjustVolume(5.0)
+            ))
+        );
+        assert_eq!(
+            parse_internal_action("volume", "source:-2.5"),
+            Some(InternalAction::Audio(
+                true,
+                AudioCommand::AdjustVolume(-2.5)
+            ))
+        );
+        assert_eq!(
+            parse_internal_action("volume", "sink:40"),
+            Some(InternalAction::Audio(false, AudioCommand::SetVolume(40.0)))
+        );
+        assert_eq!(
+            parse_internal_action("mute", "toggle"),
+            Some(InternalAction::Audio(false, AudioCommand::Mute(None)))
+        );
+        assert_eq!(
+            parse_internal_action("mute", "source:on"),
+            Some(InternalAction::Audio(true, AudioCommand::Mute(Some(true))))
+        );
+        assert_eq!(parse_internal_action("volume", "loud"), None);
+        assert_eq!(parse_internal_action("volume", "headphones:5"), None);
+        assert_eq!(parse_internal_action("mute", "yes"), None);
+        assert_eq!(
+            parse_internal_action("workspace", " 2 "),
+            Some(InternalAction::Workspace("2".into()))
+        );
+        assert_eq!(parse_internal_action("workspace", ""), None);
+    }
+
+    #[test]
+    fn brightness_actions_parse() {
+        use super::{BrightnessChange, InternalAction, parse_internal_action};
+        assert_eq!(
+            parse_internal_action("brightness", "50"),
+            Some(InternalAction::Brightness(
+                None,
+                BrightnessChange::Set(50.0)
+            ))
+        );
+        assert_eq!(
+            parse_internal_action("brightness", "intel_backlight: -5"),
+            Some(InternalAction::Brightness(
+                Some("intel_backlight".into()),
+                BrightnessChange::Adjust(-5.0)
+            ))
+        );
+        assert_eq!(parse_internal_action("brightness", ":5"), None);
+        assert_eq!(parse_internal_action("brightness", "max"), None);
+    }
+
+    #[test]
+    fn network_actions_parse() {
+        use super::{InternalAction, NetworkCommand, parse_internal_action};
+        assert_eq!(
+            parse_internal_action("network", "Wired connection 1"),
+            Some(InternalAction::Network(NetworkCommand::Up(
+                "Wired connection 1".into()
+            )))
+        );
+        assert_eq!(
+            parse_internal_action("network", "down: work"),
+            Some(InternalAction::Network(NetworkCommand::Down("work".into())))
+        );
+        assert_eq!(parse_internal_action("network", "up:"), None);
+        assert_eq!(parse_internal_action("network", ""), None);
+    }
+
+    #[test]
+    fn bluetooth_actions_parse() {
+        use super::{BluetoothCommand, InternalAction, parse_internal_action};
+        let bt = |c| Some(InternalAction::Bluetooth(c));
+        assert_eq!(
+            parse_internal_action("bluetooth", "power"),
+            bt(BluetoothCommand::Power(None))
+        );
+        assert_eq!(
+            parse_internal_action("bluetooth", "power:off"),
+            bt(BluetoothCommand::Power(Some(false)))
+        );
+        assert_eq!(
+            parse_internal_action("bluetooth", "connect: AA:BB:CC:DD:EE:FF"),
+            bt(BluetoothCommand::Connect("AA:BB:CC:DD:EE:FF".into()))
+        );
+        assert_eq!(
+            parse_internal_action("bluetooth", "disconnect:wh_1000xm4"),
+            bt(BluetoothCommand::Disconnect("wh_1000xm4".into()))
+        );
+        assert_eq!(parse_internal_action("bluetooth", "connect:"), None);
+        assert_eq!(parse_internal_action("bluetooth", "power:maybe"), None);
+        assert_eq!(parse_internal_action("bluetooth", "scan"), None);
+    }
+
+    #[test]
+    fn dbus_call_actions_parse() {
+        use super::{DbusArg, DbusBus, InternalAction, parse_internal_action};
+        let Some(InternalAction::DbusCall(call)) = parse_internal_action(
+            "dbus-call",
+            "session org.mpris.MediaPlayer2.spotify /org/mpris/MediaPlayer2 \
+             org.mpris.MediaPlayer2.Player Seek x -10000000",
+        ) else {
+            panic!("seek call should parse");
+        };
+        assert_eq!(call.bus, DbusBus::Session);
+        assert_eq!(call.interface, "org.mpris.MediaPlayer2.Player");
+        assert_eq!(call.method, "Seek");
+        assert_eq!(call.args, [DbusArg::I64(-10_000_000)]);
+        let Some(InternalAction::DbusCall(call)) = parse_internal_action(
+            "dbus-call",
+            "system org.freedesktop.login1 /org/freedesktop/login1 \
+             org.freedesktop.login1.Manager Suspend",
+        ) else {
+            panic!("call without arguments should parse");
+        };
+        assert!(call.args.is_empty());
+        let call = |v| parse_internal_action("dbus-call", v);
+        assert_eq!(call("system a.b /a a.b M su x"), None, "one argument short");
+        assert_eq!(call("system a.b /a a.b M u -1"), None, "unsigned");
+        assert_eq!(call("system a.b /a a.b M o relative"), None);
+        assert_eq!(call("user a.b /a a.b M"), None, "unknown bus");
+        assert_eq!(call("system a.b a a.b M"), None, "path");
+        assert_eq!(call("system a.b /a a.b"), None, "no method");
+    }
+
+    #[test]
+    fn power_actions_parse() {
+        use super::{InternalAction, PowerCommand, parse_internal_action};
+        assert_eq!(
+            parse_internal_action("power", "suspend"),
+            Some(InternalAction::Power(PowerCommand::Suspend))
+        );
+        assert_eq!(
+            parse_internal_action("power", " poweroff "),
+            Some(InternalAction::Power(PowerCommand::PowerOff))
+        );
+        assert_eq!(parse_internal_action("power", "shutdown"), None);
+        assert_eq!(
+            parse_internal_action("inhibit", "toggle"),
+            Some(InternalAction::Inhibit(None))
+        );
+        assert_eq!(
+            parse_internal_action("inhibit", "off"),
+            Some(InternalAction::Inhibit(Some(false)))
+        );
+        assert_eq!(parse_internal_action("inhibit", ""), None);
+    }
+
+    #[test]
+    fn mpris_actions_parse() {
+        use super::{InternalAction, MprisCommand, parse_internal_action};
+        assert_eq!(
+            parse_internal_action("mpris", "play-pause"),
+            Some(InternalAction::Mpris(None, MprisCommand::PlayPause))
+        );
+        assert_eq!(
+            parse_internal_action("mpris", "spotify:next"),
+            Some(InternalAction::Mpris(
+                Some("spotify".into()),
+                MprisCommand::Next
+            ))
+        );
+        assert_eq!(
+            parse_internal_action("mpris", "seek -2.5"),
+            Some(InternalAction::Mpris(None, MprisCommand::Seek(-2_500_000)))
+        );
+        assert_eq!(
+            parse_internal_action("mpris", "seek +10"),
+            Some(InternalAction::Mpris(None, MprisCommand::Seek(10_000_000)))
+        );
+        assert_eq!(parse_internal_action("mpris", "seek"), None);
+        assert_eq!(parse_internal_action("mpris", ":next"), None);
+        assert_eq!(parse_internal_action("mpris", "rewind"), None);
+    }
+
+    #[test]
+    fn action_qualifiers_parse() {
+        use super::{PointerTrigger, parse_action_qualifier};
+        use iced::keyboard::key::Named;
+        use iced::keyboard::{Key, Modifiers};
+        assert_eq!(
+            parse_action_qualifier("middle"),
+            Some((PointerTrigger::Middle, Modifiers::empty()))
+        );
+        assert_eq!(
+            parse_action_qualifier("shift"),
+            Some((PointerTrigger::Left, Modifiers::SHIFT))
+        );
+        assert_eq!(
+            parse_action_qualifier("scrollup:ctrl:alt"),
+            Some((PointerTrigger::ScrollUp, Modifiers::CTRL | Modifiers::ALT))
+        );
+        assert_eq!(
+            parse_action_qualifier("super:double"),
+            Some((PointerTrigger::Double, Modifiers::LOGO))
+        );
+        assert_eq!(parse_action_qualifier("middle:double"), None);
+        assert_eq!(parse_action_qualifier("shift:shift"), None);
+        assert_eq!(parse_action_qualifier("hyper"), None);
+        assert_eq!(parse_action_qualifier(""), None);
+    }
+
+    #[test]
+    fn key_chords_parse_and_match() {
+        use super::parse_key_chord;
+        use iced::keyboard::key::Named;
+        use iced::keyboard::{Key, Modifiers};
+        let q = parse_key_chord("ctrl+q").unwrap();
+        assert_eq!(q.modifiers, Modifiers::CTRL);
+        assert!(q.matches(&Key::Character("q".into()), Modifiers::CTRL));
+        assert!(!q.matches(&Key::Character("q".into()), Modifiers::empty()));
+        let shift_a = parse_key_chord("Shift+A").unwrap();
+        assert!(shift_a.matches(&Key::Character("A".into()), Modifiers::SHIFT));
+        let esc = parse_key_chord("Escape").unwrap();
+        assert_eq!(esc.key, Key::Named(Named::Escape));
+        assert!(esc.matches(&Key::Named(Named::Escape), Modifiers::empty()));
+        assert_eq!(
+            parse_key_chord("super+f5").map(|c| c.key),
+            Some(Key::Named(Named::F5))
+        );
+        assert!(parse_key_chord("ctrl").is_none());
+        assert!(parse_key_chord("a+b").is_none());
+        assert!(parse_key_chord("ctrl+bogus").is_none());
+        assert!(q.fires_over_input());
+        assert!(esc.fires_over_input());
+        assert!(!shift_a.fires_over_input());
+    }
 }
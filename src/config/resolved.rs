@@ -1,6 +1,6 @@
 use crate::config::types::Span;
 use iced::alignment::{Horizontal, Vertical};
-use iced::widget::{button, container};
+use iced::widget::{button, container, text_input};
 use iced::{Background, Border, Color, Font, Padding, Shadow, border::Radius};
 use iced_layershell::reexport::{Anchor, Layer, OutputOption};
 use indexmap::IndexMap;
@@ -14,6 +14,7 @@ pub struct ResolvedConfig {
     pub smart_polls: Vec<(String, Option<std::time::Duration>)>,
     pub icon_theme: Option<String>,
     pub watches: Vec<ResolvedWatch>,
+    pub keybinds: Vec<ResolvedKeybind>,
 }
 
 #[derive(Debug, Clone)]
@@ -40,7 +41,10 @@ pub enum ResolvedElement {
     Row(ResolvedRow),
     Column(ResolvedColumn),
     Text(ResolvedText),
+    Input(Box<ResolvedInput>),
+    Image(ResolvedImage),
     Apptray(Box<ResolvedApptraySettings>),
+    Taskbar(Box<ResolvedTaskbarSettings>),
 }
 
 #[derive(Debug, Clone)]
@@ -68,17 +72,53 @@ pub struct ResolvedRevealer {
 #[derive(Debug, Clone)]
 pub struct ResolvedEvent {
     pub evtype: crate::config::primitives::EventType,
-    pub action: Option<String>,
+    pub action: Option<ResolvedAction>,
     pub child: Box<ResolvedElement>,
     pub span: Span,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedAction {
+    pub command: Option<String>,
+    pub internal: Vec<crate::config::primitives::InternalAction>,
+    pub stdout: Option<String>,
+    pub status: Option<String>,
+    pub on_error: Option<String>,
+}
+
+impl ResolvedAction {
+    pub fn shell(command: impl Into<String>) -> Self {
+        ResolvedAction {
+            command: Some(command.into()),
+            internal: Vec::new(),
+            stdout: None,
+            status: None,
+            on_error: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedBinding {
+    pub trigger: crate::config::primitives::PointerTrigger,
+    pub modifiers: iced::keyboard::Modifiers,
+    pub action: ResolvedAction,
+}
+
+#[derive(Debug, Clone)]
+pub struct ResolvedKeybind {
+    pub id: String,
+    pub chord: crate::config::primitives::KeyChord,
+    pub widget: Option<String>,
+    pub action: ResolvedAction,
+}
+
 #[derive(Debug, Clone)]
 pub struct ResolvedWatch {
     pub id: String,
     pub evtype: crate::config::primitives::EventType,
     pub var: String,
-    pub action: String,
+    pub action: ResolvedAction,
     pub duration: Option<std::time::Duration>,
 }
 
@@ -87,7 +127,8 @@ pub struct ResolvedButton {
     pub w: Option<iced::Length>,
     pub h: Option<iced::Length>,
     pub padding: Option<Padding>,
-    pub action: Option<String>,
+    pub action: Option<ResolvedAction>,
+    pub bindings: Vec<ResolvedBinding>,
     pub clip: Option<bool>,
     pub style: Option<button::Style>,
     pub style_hover: Option<button::Style>,
@@ -133,6 +174,35 @@ pub struct ResolvedText {
     pub span: Span,
 }
 
+#[derive(Debug, Clone)]
+pub struct ResolvedInput {
+    pub id: String,
+    pub var: String,
+    pub value: String,
+    pub placeholder: Option<String>,
+    pub password: Option<bool>,
+    pub w: Option<iced::Length>,
+    pub padding: Option<Padding>,
+    pub size: Option<f32>,
+    pub font: Option<Font>,
+    pub style: Option<text_input::Style>,
+    pub style_hover: Option<text_input::Style>,
+    pub style_focus: Option<text_input::Style>,
+    pub on_submit: Option<ResolvedAction>,
+    pub on_change: Option<ResolvedAction>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct ResolvedImage {
+    /// `None` when neither `src` nor `fallback` led to a file.
+    pub path: Option<std::path::PathBuf>,
+    pub w: Option<iced::Length>,
+    pub h: Option<iced::Length>,
+    pub fit: Option<iced::ContentFit>,
+    pub span: Span,
+}
+
 // Struct stays acts as universal layer between final Style and cfg
 #[derive(Debug, Clone, Default)]
 pub struct PreResolvedStyle {
@@ -163,6 +233,18 @@ impl PreResolvedStyle {
             snap: self.snap.unwrap_or_default(),
         }
     }
+
+    pub fn to_input(&self) -> text_input::Style {
+        let text = self.text.unwrap_or(Color::BLACK);
+        text_input::Style {
+            background: Background::Color(self.bg.unwrap_or(Color::TRANSPARENT)),
+            border: self.border.unwrap_or_default(),
+            icon: text,
+            placeholder: text.scale_alpha(0.5),
+            value: text,
+            selection: text.scale_alpha(0.3),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -353,6 +435,36 @@ impl Default for ResolvedApptraySettings {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct ResolvedTaskbarSettings {
+    pub icon_size: f32,
+    pub spacing: f32,
+    pub padding: Option<Padding>,
+    pub bg: Option<Color>,
+    pub border: Option<Border>,
+    pub active_bg: Option<Color>,
+    pub vertical: bool,
+    /// Only windows on the output the widget is on.
+    pub own_output: bool,
+    pub icon_theme: String,
+}
+
+impl Default for ResolvedTaskbarSettings {
+    fn default() -> Self {
+        ResolvedTaskbarSettings {
+            icon_size: 22.0,
+            spacing: 4.0,
+            padding: None,
+            bg: None,
+            border: None,
+            active_bg: None,
+            vertical: false,
+            own_output: false,
+            icon_theme: String::new(),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ResolvedNotificationSettings {
     pub width: f32,
@@ -172,6 +172,19 @@ pub fn coerce_col_align(v: Value, field: &str, span: &Span) -> Result<Horizontal
     }
 }
 
+pub fn coerce_content_fit(
+    v: Value,
+    field: &str,
+    span: &Span,
+) -> Result<iced::ContentFit, ConfigError> {
+    match v {
+        Value::Str(s) => {
+            primitives::parse_content_fit(&s).ok_or_else(|| type_err(field, "a valid fit", span))
+        }
+        _ => Err(type_err(field, "a fit string", span)),
+    }
+}
+
 pub fn coerce_string(v: Value, _field: &str, _span: &Span) -> Result<String, ConfigError> {
     Ok(match v {
         Value::Int(i) => i.to_string(),
@@ -193,6 +206,18 @@ pub fn coerce_transition(
     }
 }
 
+pub fn coerce_key_chord(
+    v: Value,
+    field: &str,
+    span: &Span,
+) -> Result<crate::config::primitives::KeyChord, ConfigError> {
+    match v {
+        Value::Str(s) => primitives::parse_key_chord(&s)
+            .ok_or_else(|| type_err(field, "a key like \"ctrl+q\"", span)),
+        _ => Err(type_err(field, "a key string", span)),
+    }
+}
+
 pub fn coerce_duration(
     v: Value,
     field: &str,
@@ -1,13 +1,15 @@
 use crate::config::math::{self, value::Value};
+use crate::config::primitives::{self, InternalAction, parse_internal_action};
 use crate::config::resolved::{
-    PreResolvedStyle, ResolvedButton, ResolvedColumn, ResolvedContainer, ResolvedElement,
-    ResolvedEvent, ResolvedRevealer, ResolvedRow, ResolvedText, ResolvedWidget,
+    PreResolvedStyle, ResolvedAction, ResolvedBinding, ResolvedButton, ResolvedColumn,
+    ResolvedContainer, ResolvedElement, ResolvedEvent, ResolvedImage, ResolvedInput,
+    ResolvedRevealer, ResolvedRow, ResolvedText, ResolvedWidget,
 };
 use crate::config::resolver::coerce;
 use crate::config::resolver::vars::FlatEnv;
 use crate::config::types::{
-    Button, Column, Container, Event, FieldValue, ParsedConfig, Revealer, Row, Span, Style, TextEl,
-    Widget,
+    ActionDecl, Button, Column, Container, Event, FieldValue, Image, Input, ParsedConfig, Revealer,
+    Row, Span, Style, TextEl, VarValue, Widget,
 };
 use crate::config::{ConfigError, ConfigErrorKind, Severity};
 use std::collections::HashSet;
@@ -61,6 +63,100 @@ pub(crate) fn resolve_field<T: Clone>(
     }
 }
 
+pub(crate) fn resolve_action(action: &Option<ActionDecl>, ctx: &mut Ctx) -> Option<ResolvedAction> {
+    let a = action.as_ref()?;
+    let command = match &a.command {
+        Some(_) => Some(resolve_field(
+            &a.command,
+            "action",
+            &a.span,
+            coerce::coerce_string,
+            ctx,
+        )?),
+        None => None,
+    };
+    let mut internal = Vec::new();
+    for (prop, value) in &a.internal {
+        let value = resolve_field(
+            &Some(value.clone()),
+            prop,
+            &a.span,
+            coerce::coerce_string,
+            ctx,
+        )?;
+        let Some(ia) = parse_internal_action(prop, &value) else {
+            ctx.errs.push(ConfigError {
+                kind: ConfigErrorKind::InvalidFieldType,
+                span: a.span.clone(),
+                message: format!("invalid `{prop}=` value \"{value}\" on `action`"),
+                severity: Severity::Error,
+            });
+            return None;
+        };
+        check_internal_target(&ia, &a.span, ctx);
+        internal.push(ia);
+    }
+    let on_error = resolve_field(&a.on_error, "on_error", &a.span, coerce::coerce_string, ctx);
+    let mut capture_var = |var: &Option<String>, prop: &str| -> Option<String> {
+        let var = var.as_ref()?;
+        if !ctx.config.vars.contains_key(var) {
+            ctx.errs.push(ConfigError {
+                kind: ConfigErrorKind::UnresolvedReference,
+                span: a.span.clone(),
+                message: format!("action `{prop}=` references unknown variable \"{var}\""),
+                severity: Severity::Error,
+            });
+            return None;
+        }
+        ctx.used.insert(var.clone());
+        Some(var.clone())
+    };
+    let stdout = capture_var(&a.stdout, "stdout");
+    let status = capture_var(&a.status, "status");
+    Some(ResolvedAction {
+        command,
+        internal,
+        stdout,
+        status,
+        on_error,
+    })
+}
+
+fn check_internal_target(ia: &InternalAction, span: &Span, ctx: &mut Ctx) {
+    let missing = match ia {
+        InternalAction::Update { name, .. } => {
+            ctx.used.insert(name.clone());
+            (name != "dnd" && !ctx.config.vars.contains_key(name))
+                .then(|| format!("unknown variable \"{name}\""))
+        }
+        InternalAction::Open(w) | InternalAction::Close(w) | InternalAction::Toggle(w) => {
+            (!ctx.config.widgets.contains_key(w)).then(|| format!("unknown widget \"{w}\""))
+        }
+        InternalAction::Refresh(name) => (!ctx.config.pulls.contains_key(name)
+            && !crate::config::smart::is_namespace(name))
+        .then(|| format!("unknown pull or smart namespace \"{name}\"")),
+        InternalAction::Dnd(_)
+        | InternalAction::Dismiss(_)
+        | InternalAction::Mpris(..)
+        | InternalAction::Audio(..)
+        | InternalAction::Workspace(_)
+        | InternalAction::Brightness(..)
+        | InternalAction::Network(_)
+        | InternalAction::Bluetooth(_)
+        | InternalAction::Power(_)
+        | InternalAction::Inhibit(_)
+        | InternalAction::DbusCall(_) => None,
+    };
+    if let Some(message) = missing {
+        ctx.errs.push(ConfigError {
+            kind: ConfigErrorKind::UnresolvedReference,
+            span: span.clone(),
+            message: format!("action references {message}"),
+            severity: Severity::Error,
+        });
+    }
+}
+
 pub(crate) fn resolve_widget(_name: &str, w: &Widget, ctx: &mut Ctx) -> ResolvedWidget {
     let mut visited = HashSet::new();
     let child = match &w.child {
@@ -127,6 +223,11 @@ pub(crate) fn resolve_ref(
             resolve_apptray_settings(ctx),
         )));
     }
+    if reference == "taskbar" {
+        return Some(ResolvedElement::Taskbar(Box::new(
+            resolve_taskbar_settings(ctx),
+        )));
+    }
 
     let owned = ctx
         .config
@@ -168,6 +269,18 @@ pub(crate) fn resolve_ref(
                 .columns
                 .get(reference)
                 .map(|c| OwnedEl::Column(c.clone()))
+        })
+        .or_else(|| {
+            ctx.config
+                .inputs
+                .get(reference)
+                .map(|i| OwnedEl::Input(reference.to_string(), i.clone()))
+        })
+        .or_else(|| {
+            ctx.config
+                .images
+                .get(reference)
+                .map(|i| OwnedEl::Image(i.clone()))
         });
     if let Some(el) = owned {
         ctx.used.insert(reference.to_string());
@@ -221,6 +334,8 @@ enum OwnedEl {
     Row(Row),
     Column(Column),
     Text(TextEl),
+    Input(String, Input),
+    Image(Image),
 }
 
 fn single_element_owned(frag: &ParsedConfig) -> Option<OwnedEl> {
@@ -230,7 +345,9 @@ fn single_element_owned(frag: &ParsedConfig) -> Option<OwnedEl> {
         + frag.buttons.len()
         + frag.rows.len()
         + frag.columns.len()
-        + frag.texts.len();
+        + frag.texts.len()
+        + frag.inputs.len()
+        + frag.images.len();
     if count != 1 {
         return None;
     }
@@ -255,6 +372,12 @@ fn single_element_owned(frag: &ParsedConfig) -> Option<OwnedEl> {
     if let Some((_, t)) = frag.texts.iter().next() {
         return Some(OwnedEl::Text(t.clone()));
     }
+    if let Some((id, i)) = frag.inputs.iter().next() {
+        return Some(OwnedEl::Input(id.clone(), i.clone()));
+    }
+    if let Some((_, i)) = frag.images.iter().next() {
+        return Some(OwnedEl::Image(i.clone()));
+    }
     None
 }
 
@@ -279,6 +402,10 @@ fn resolve_fragment_element(
         OwnedEl::Row(r) => Some(ResolvedElement::Row(resolve_row(&r, ctx, visited))),
         OwnedEl::Column(c) => Some(ResolvedElement::Column(resolve_column(&c, ctx, visited))),
         OwnedEl::Text(t) => Some(ResolvedElement::Text(resolve_text(&t, ctx))),
+        OwnedEl::Input(id, i) => {
+            resolve_input(id, &i, ctx).map(|i| ResolvedElement::Input(Box::new(i)))
+        }
+        OwnedEl::Image(i) => Some(ResolvedElement::Image(resolve_image(&i, ctx))),
     }
 }
 
@@ -352,11 +479,7 @@ fn resolve_revealer(
 }
 
 fn resolve_event(e: &Event, ctx: &mut Ctx, visited: &mut HashSet<String>) -> Option<ResolvedEvent> {
-    use crate::config::primitives::EventType;
-    if matches!(
-        e.evtype,
-        EventType::WatchOn | EventType::WatchOff | EventType::Timeout
-    ) {
+    if !e.evtype.is_pointer() {
         ctx.errs.push(ConfigError {
             kind: ConfigErrorKind::InvalidFieldType,
             span: e.span.clone(),
@@ -368,7 +491,7 @@ fn resolve_event(e: &Event, ctx: &mut Ctx, visited: &mut HashSet<String>) -> Opt
     let child = resolve_child(&e.child, &e.span, ctx, visited)?;
     Some(ResolvedEvent {
         evtype: e.evtype,
-        action: resolve_field(&e.action, "action", &e.span, coerce::coerce_string, ctx),
+        action: resolve_action(&e.action, ctx),
         child,
         span: e.span.clone(),
     })
@@ -384,7 +507,18 @@ fn resolve_button(
         w: resolve_field(&b.w, "w", &b.span, coerce::coerce_length, ctx),
         h: resolve_field(&b.h, "h", &b.span, coerce::coerce_length, ctx),
         padding: resolve_field(&b.padding, "padding", &b.span, coerce::coerce_padding, ctx),
-        action: resolve_field(&b.action, "action", &b.span, coerce::coerce_string, ctx),
+        action: resolve_action(&b.action, ctx),
+        bindings: b
+            .bindings
+            .iter()
+            .filter_map(|bound| {
+                Some(ResolvedBinding {
+                    trigger: bound.trigger,
+                    modifiers: bound.modifiers,
+                    action: resolve_action(&Some(bound.action.clone()), ctx)?,
+                })
+            })
+            .collect(),
         clip: resolve_field(&b.clip, "clip", &b.span, coerce::coerce_bool, ctx),
         style: resolve_style_ref(&b.style, &b.span, ctx).map(|p| p.to_button()),
         style_hover: resolve_style_ref(&b.style_hover, &b.span, ctx).map(|p| p.to_button()),
@@ -650,6 +784,40 @@ pub(crate) fn resolve_apptray_settings(ctx: &mut Ctx) -> ResolvedApptraySettings
     out
 }
 
+use crate::config::resolved::ResolvedTaskbarSettings;
+
+pub(crate) fn resolve_taskbar_settings(ctx: &mut Ctx) -> ResolvedTaskbarSettings {
+    // the taskbar draws from `iwwc.toplevels`, so reading it here gets the
+    // namespace watched
+    use crate::config::math::value::VarStore;
+    ctx.env.lookup("iwwc.toplevels.count");
+    let mut out = ResolvedTaskbarSettings {
+        icon_theme: crate::iconlookup::effective_theme(ctx.config.icon_theme.as_deref()),
+        ..Default::default()
+    };
+    let Some(t) = ctx.config.taskbar.clone() else {
+        return out;
+    };
+    let span = t.span.clone();
+    if let Some(v) = resolve_field(&t.icon_size, "icon_size", &span, coerce::coerce_f32, ctx) {
+        out.icon_size = v;
+    }
+    if let Some(v) = resolve_field(&t.spacing, "spacing", &span, coerce::coerce_f32, ctx) {
+        out.spacing = v;
+    }
+    out.padding = resolve_field(&t.padding, "padding", &span, coerce::coerce_padding, ctx);
+    out.bg = resolve_field(&t.bg, "bg", &span, coerce::coerce_color, ctx);
+    out.border = resolve_border_ref(&t.border, &span, ctx);
+    out.active_bg = resolve_field(&t.active_bg, "active_bg", &span, coerce::coerce_color, ctx);
+    if let Some(v) = resolve_field(&t.vertical, "vertical", &span, coerce::coerce_bool, ctx) {
+        out.vertical = v;
+    }
+    if let Some(v) = resolve_field(&t.own_output, "own_output", &span, coerce::coerce_bool, ctx) {
+        out.own_output = v;
+    }
+    out
+}
+
 pub(crate) fn resolve_apptray_menu(ctx: &mut Ctx) -> ResolvedMenu {
     match (
         ctx.config.apptraymenu.clone(),
@@ -758,12 +926,94 @@ pub(crate) fn resolve_text(t: &TextEl, ctx: &mut Ctx) -> ResolvedText {
     }
 }
 
+/// `src` wins if it names an existing local file; otherwise `fallback` is
+/// tried as a path and then as an icon name in the configured theme.
+fn resolve_image(i: &Image, ctx: &mut Ctx) -> ResolvedImage {
+    let w = resolve_field(&i.w, "w", &i.span, coerce::coerce_length, ctx);
+    let h = resolve_field(&i.h, "h", &i.span, coerce::coerce_length, ctx);
+    let src = resolve_field(&i.src, "src", &i.span, coerce::coerce_string, ctx);
+    let fallback = resolve_field(&i.fallback, "fallback", &i.span, coerce::coerce_string, ctx);
+    let local = |s: &str| primitives::local_image_path(s).filter(|p| p.is_file());
+    let path = src.as_deref().and_then(local).or_else(|| {
+        let name = fallback.as_deref().filter(|f| !f.is_empty())?;
+        local(name).or_else(|| {
+            let size = [h, w]
+                .into_iter()
+                .find_map(|l| match l {
+                    Some(iced::Length::Fixed(px)) => Some(px.round() as u16),
+                    _ => None,
+                })
+                .unwrap_or(64);
+            let theme = crate::iconlookup::effective_theme(ctx.config.icon_theme.as_deref());
+            crate::iconlookup::lookup_named(name, size, &theme)
+        })
+    });
+    ResolvedImage {
+        path,
+        w,
+        h,
+        fit: resolve_field(&i.fit, "fit", &i.span, coerce::coerce_content_fit, ctx),
+        span: i.span.clone(),
+    }
+}
+
+fn resolve_input(id: String, i: &Input, ctx: &mut Ctx) -> Option<ResolvedInput> {
+    let var = i.var.clone()?;
+    // typed text is stored verbatim, so only a string var can hold it
+    let value = match ctx.config.vars.get(&var).map(|d| &d.value) {
+        Some(VarValue::Str(s)) => s.clone(),
+        Some(_) => {
+            ctx.errs.push(ConfigError {
+                kind: ConfigErrorKind::InvalidFieldType,
+                span: i.span.clone(),
+                message: format!("input `var` \"{var}\" must hold a string"),
+                severity: Severity::Error,
+            });
+            return None;
+        }
+        None => {
+            ctx.errs.push(ConfigError {
+                kind: ConfigErrorKind::UnresolvedReference,
+                span: i.span.clone(),
+                message: format!("input `var` references unknown variable \"{var}\""),
+                severity: Severity::Error,
+            });
+            return None;
+        }
+    };
+    ctx.used.insert(var.clone());
+    Some(ResolvedInput {
+        id,
+        var,
+        value,
+        placeholder: resolve_field(
+            &i.placeholder,
+            "placeholder",
+            &i.span,
+            coerce::coerce_string,
+            ctx,
+        ),
+        password: resolve_field(&i.password, "password", &i.span, coerce::coerce_bool, ctx),
+        w: resolve_field(&i.w, "w", &i.span, coerce::coerce_length, ctx),
+        padding: resolve_field(&i.padding, "padding", &i.span, coerce::coerce_padding, ctx),
+        size: resolve_field(&i.size, "size", &i.span, coerce::coerce_f32, ctx),
+        font: resolve_font_ref(&i.font, &i.span, ctx),
+        style: resolve_style_ref(&i.style, &i.span, ctx).map(|p| p.to_input()),
+        style_hover: resolve_style_ref(&i.style_hover, &i.span, ctx).map(|p| p.to_input()),
+        style_focus: resolve_style_ref(&i.style_focus, &i.span, ctx).map(|p| p.to_input()),
+        on_submit: resolve_action(&i.on_submit, ctx),
+        on_change: resolve_action(&i.on_change, ctx),
+        span: i.span.clone(),
+    })
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::config::Severity;
     use crate::config::parse_str;
-    use crate::config::resolved::ResolvedElement;
+    use crate::config::primitives::InternalAction;
+    use crate::config::resolved::{ResolvedAction, ResolvedElement};
     use crate::config::resolver::resolve;
+    use crate::config::{ConfigErrorKind, Severity};
 
     fn resolve_kdl(
         kdl: &str,
@@ -878,11 +1128,187 @@ mod tests {
         );
         let rc = rc.unwrap();
         match rc.widgets.get("bar").unwrap().child.as_deref() {
-            Some(ResolvedElement::Button(b)) => assert_eq!(b.action.as_deref(), Some("echo hi")),
+            Some(ResolvedElement::Button(b)) => assert_eq!(
+                b.action.as_ref().and_then(|a| a.command.as_deref()),
+                Some("echo hi")
+            ),
+            other => panic!("expected button, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn action_capture_vars_resolve() {
+        let (rc, errs) = resolve_kdl(
+            "var out=\"\"\nvar code=0\nwidget bar { child btn }\nbutton btn { child t1; action \"vpn up\" stdout=out status=code on_error=\"notify-send vpn\" }\ntext t1",
+        );
+        assert!(
+            errs.iter().all(|e| e.severity != Severity::Error),
+            "errs: {:?}",
+            errs
+        );
+        let rc = rc.unwrap();
+        match rc.widgets.get("bar").unwrap().child.as_deref() {
+            Some(ResolvedElement::Button(b)) => assert_eq!(
+                b.action,
+                Some(ResolvedAction {
+                    command: Some("vpn up".into()),
+                    internal: Vec::new(),
+                    stdout: Some("out".into()),
+                    status: Some("code".into()),
+                    on_error: Some("notify-send vpn".into()),
+                })
+            ),
+            other => panic!("expected button, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn internal_actions_resolve_with_exprs() {
+        let (rc, errs) = resolve_kdl(
+            "var folded=#false\nvar target=\"panel\"\nwidget bar { child btn }\nwidget panel { child t1 }\nbutton btn { child t1; action update=\"folded=toggle\" toggle=\"${target}\" dismiss=\"all\" }\ntext t1",
+        );
+        assert!(
+            errs.iter().all(|e| e.severity != Severity::Error),
+            "errs: {:?}",
+            errs
+        );
+        let rc = rc.unwrap();
+        match rc.widgets.get("bar").unwrap().child.as_deref() {
+            Some(ResolvedElement::Button(b)) => {
+                let a = b.action.as_ref().unwrap();
+                assert_eq!(a.command, None);
+                assert_eq!(
+                    a.internal,
+                    vec![
+                        InternalAction::Update {
+                            name: "folded".into(),
+                            value: "toggle".into()
+                        },
+                        InternalAction::Toggle("panel".into()),
+                        InternalAction::Dismiss(None),
+                    ]
+                );
+            }
             other => panic!("expected button, got {:?}", other),
         }
     }
 
+    #[test]
+    fn internal_action_unknown_targets_error() {
+        for action in [
+            "update=\"nope=1\"",
+            "open=\"nowidget\"",
+            "refresh=\"nopull\"",
+        ] {
+            let kdl = format!(
+                "widget bar {{ child btn }}\nbutton btn {{ child t1; action {action} }}\ntext t1"
+            );
+            let (rc, errs) = resolve_kdl(&kdl);
+            assert!(rc.is_none(), "{action} should fail");
+            assert!(
+                errs.iter()
+                    .any(|e| e.kind == ConfigErrorKind::UnresolvedReference),
+                "{action}: {errs:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn action_capture_unknown_var_errors() {
+        let (rc, errs) = resolve_kdl(
+            "widget bar { child btn }\nbutton btn { child t1; action \"true\" status=nope }\ntext t1",
+        );
+        assert!(rc.is_none());
+        assert!(
+            errs.iter()
+                .any(|e| e.kind == ConfigErrorKind::UnresolvedReference
+                    && e.message.contains("nope"))
+        );
+    }
+
+    #[test]
+    fn input_takes_value_and_actions() {
+        let (rc, errs) = resolve_kdl(
+            "var q=\"7\"\nvar target=\"menu\"\nwidget bar { child i1 }\nwidget menu { child t1 }\ninput i1 { var q; placeholder \"Run\"; on_submit \"echo $IWWC_INPUT\" close=\"${target}\" }\ntext t1",
+        );
+        assert!(
+            errs.iter().all(|e| e.severity != Severity::Error),
+            "errs: {:?}",
+            errs
+        );
+        let rc = rc.unwrap();
+        match rc.widgets.get("bar").unwrap().child.as_deref() {
+            Some(ResolvedElement::Input(i)) => {
+                assert_eq!(i.id, "i1");
+                assert_eq!(i.value, "7");
+                assert_eq!(i.placeholder.as_deref(), Some("Run"));
+                let submit = i.on_submit.as_ref().unwrap();
+                assert_eq!(submit.internal, vec![InternalAction::Close("menu".into())]);
+                assert!(i.on_change.is_none());
+            }
+            other => panic!("expected input, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn image_src_then_fallback() {
+        let dir = tempfile::tempdir().unwrap();
+        let art = dir.path().join("cover art.png");
+        let icon = dir.path().join("note.svg");
+        std::fs::write(&art, b"").unwrap();
+        std::fs::write(&icon, b"").unwrap();
+        let kdl = |src: &str| {
+            format!(
+                "var art=\"{src}\"\nwidget bar {{ child img }}\nimage img {{ src \"${{art}}\"; fallback \"{}\"; h 32; fit cover }}",
+                icon.display()
+            )
+        };
+        let image = |kdl: &str| {
+            let (rc, errs) = resolve_kdl(kdl);
+            assert!(
+                errs.iter().all(|e| e.severity != Severity::Error),
+                "errs: {:?}",
+                errs
+            );
+            match rc.unwrap().widgets.get("bar").unwrap().child.as_deref() {
+                Some(ResolvedElement::Image(i)) => i.clone(),
+                other => panic!("expected image, got {:?}", other),
+            }
+        };
+
+        let url = format!("file://{}", art.display()).replace(' ', "%20");
+        let i = image(&kdl(&url));
+        assert_eq!(i.path.as_deref(), Some(art.as_path()));
+        assert_eq!(i.h, Some(iced::Length::Fixed(32.0)));
+        assert_eq!(i.fit, Some(iced::ContentFit::Cover));
+        // no track, a remote url and a deleted file all fall back
+        for src in ["", "https://example.com/a.jpg", "/nonexistent/a.png"] {
+            assert_eq!(image(&kdl(src)).path.as_deref(), Some(icon.as_path()));
+        }
+    }
+
+    #[test]
+    fn input_var_must_be_string() {
+        let (rc, errs) = resolve_kdl("var n=5\nwidget bar { child i1 }\ninput i1 { var n }");
+        assert!(rc.is_none());
+        assert!(
+            errs.iter()
+                .any(|e| e.kind == ConfigErrorKind::InvalidFieldType
+                    && e.message == "input `var` \"n\" must hold a string")
+        );
+    }
+
+    #[test]
+    fn input_unknown_var_errors() {
+        let (rc, errs) = resolve_kdl("widget bar { child i1 }\ninput i1 { var nope }");
+        assert!(rc.is_none());
+        assert!(
+            errs.iter()
+                .any(|e| e.kind == ConfigErrorKind::UnresolvedReference
+                    && e.message == "input `var` references unknown variable \"nope\"")
+        );
+    }
+
     #[test]
     fn row_with_multiple_children() {
         let (rc, errs) = resolve_kdl(
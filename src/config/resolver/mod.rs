@@ -41,16 +41,21 @@ pub fn resolve(config: &ParsedConfig) -> (Option<ResolvedConfig>, Vec<ConfigErro
 
     let mut watches: Vec<crate::config::resolved::ResolvedWatch> = Vec::new();
     for (id, e) in &config.events {
-        use crate::config::primitives::EventType;
-        if matches!(
-            e.evtype,
-            EventType::OnHover | EventType::OnHoverExit | EventType::RightClick
-        ) {
+        if e.evtype.is_pointer() {
             continue;
         }
         used.insert(id.clone());
         let Some(var) = e.var.clone() else { continue };
-        match config.vars.get(&var).map(|d| &d.value) {
+        let value = match config.vars.get(&var) {
+            Some(d) => Some(&d.value),
+            // smart bools such as `iwwc.idle`; looking one up also polls
+            // its namespace
+            None if var.starts_with("iwwc.") => {
+                crate::config::math::value::VarStore::lookup(&env, &var)
+            }
+            None => None,
+        };
+        match value {
             Some(crate::config::types::VarValue::Bool(_)) => {}
             Some(_) => {
                 errs.push(ConfigError {
@@ -78,13 +83,7 @@ pub fn resolve(config: &ParsedConfig) -> (Option<ResolvedConfig>, Vec<ConfigErro
             errs: &mut errs,
             used: &mut used,
         };
-        let action = elements::resolve_field(
-            &e.action,
-            "action",
-            &e.span,
-            coerce::coerce_string,
-            &mut ctx,
-        );
+        let action = elements::resolve_action(&e.action, &mut ctx);
         let duration = elements::resolve_field(
             &e.duration,
             "duration",
@@ -102,7 +101,40 @@ pub fn resolve(config: &ParsedConfig) -> (Option<ResolvedConfig>, Vec<ConfigErro
         });
     }
 
-    let smart_polls = env.smart_polls();
+    let mut keybinds = Vec::new();
+    for (id, k) in &config.keybinds {
+        if let Some(w) = &k.widget
+            && !config.widgets.contains_key(w)
+        {
+            errs.push(ConfigError {
+                kind: crate::config::ConfigErrorKind::UnresolvedReference,
+                span: k.span.clone(),
+                message: format!("keybind \"{id}\": unknown widget \"{w}\""),
+                severity: Severity::Error,
+            });
+            continue;
+        }
+        let mut ctx = elements::Ctx {
+            config,
+            env: &env,
+            errs: &mut errs,
+            used: &mut used,
+        };
+        let chord =
+            elements::resolve_field(&k.key, "key", &k.span, coerce::coerce_key_chord, &mut ctx);
+        let action = elements::resolve_action(&k.action, &mut ctx);
+        let (Some(chord), Some(action)) = (chord, action) else {
+            continue;
+        };
+        keybinds.push(crate::config::resolved::ResolvedKeybind {
+            id: id.clone(),
+            chord,
+            widget: k.widget.clone(),
+            action,
+        });
+    }
+
+    let smart_polls = env.smart_polls(&config.smart);
 
     let mut all_ids: Vec<(&str, &crate::config::types::Span, bool)> = Vec::new();
     for (id, d) in &config.vars {
@@ -116,7 +148,8 @@ pub fn resolve(config: &ParsedConfig) -> (Option<ResolvedConfig>, Vec<ConfigErro
         };
     }
     collect_element_ids!(
-        containers, revealers, events, buttons, rows, columns, texts, styles, borders, shadows
+        containers, revealers, events, buttons, rows, columns, texts, inputs, images, styles,
+        borders, shadows
     );
 
     for (id, span, is_var) in all_ids {
@@ -154,6 +187,7 @@ pub fn resolve(config: &ParsedConfig) -> (Option<ResolvedConfig>, Vec<ConfigErro
                 smart_polls,
                 icon_theme,
                 watches,
+                keybinds,
             }),
             errs,
         )
@@ -458,6 +492,30 @@ mod tests {
         }
     }
 
+    #[test]
+    fn taskbar_reference_watches_toplevels() {
+        let (cfg, _) = parse_str(
+            "widget bar { child taskbar }\ntaskbar { icon_size 18; own_output #true }",
+            "<t>",
+        );
+        let (rc, errs) = resolve(&cfg.unwrap());
+        assert!(
+            errs.iter().all(|e| e.severity != Severity::Error),
+            "{:?}",
+            errs
+        );
+        let rc = rc.unwrap();
+        match rc.widgets.get("bar").unwrap().child.as_deref() {
+            Some(crate::config::resolved::ResolvedElement::Taskbar(s)) => {
+                assert_eq!(s.icon_size, 18.0);
+                assert!(s.own_output);
+                assert!(!s.vertical);
+            }
+            other => panic!("expected taskbar, got {:?}", other),
+        }
+        assert!(rc.smart_polls.iter().any(|(ns, _)| ns == "iwwc.toplevels"));
+    }
+
     #[test]
     fn smart_ram_resolves_and_is_polled() {
         let (cfg, _) = parse_str(
@@ -474,6 +532,27 @@ mod tests {
         assert!(rc.smart_polls.iter().any(|(ns, _)| ns == "iwwc.ram"));
     }
 
+    #[test]
+    fn mpris_ticks_only_for_position() {
+        let interval = |text: &str| {
+            let (cfg, _) = parse_str(
+                &format!("widget bar {{ child t1 }}\ntext t1 \"{text}\""),
+                "<t>",
+            );
+            let (rc, _) = resolve(&cfg.unwrap());
+            rc.unwrap()
+                .smart_polls
+                .into_iter()
+                .find(|(ns, _)| ns == "iwwc.mpris")
+                .map(|(_, d)| d)
+        };
+        assert_eq!(interval("${iwwc.mpris.title}"), Some(None));
+        assert_eq!(
+            interval("${iwwc.mpris.title} ${iwwc.mpris.position}"),
+            Some(Some(std::time::Duration::from_secs(1)))
+        );
+    }
+
     #[test]
     fn bare_namespace_is_unresolved() {
         let (cfg, _) = parse_str("widget bar { child t1 }\ntext t1 \"${iwwc.ram}\"", "<t>");
@@ -607,6 +686,37 @@ mod tests {
             && e.message.contains("\"rev\"")));
     }
 
+    #[test]
+    fn keybinds_resolve_with_widget_scope() {
+        let (rc, errs) = resolve_kdl(
+            "widget menu { keyboard #true; child t1 }\ntext t1\nkeybind quit { key \"ctrl+q\"; widget menu; action close=\"menu\" }\nkeybind any { key \"F5\"; action \"true\" }",
+        );
+        assert!(
+            errs.iter().all(|e| e.severity != Severity::Error),
+            "errs: {:?}",
+            errs
+        );
+        let rc = rc.unwrap();
+        assert_eq!(rc.keybinds.len(), 2);
+        let quit = &rc.keybinds[0];
+        assert_eq!(quit.id, "quit");
+        assert_eq!(quit.widget.as_deref(), Some("menu"));
+        assert_eq!(quit.chord.modifiers, iced::keyboard::Modifiers::CTRL);
+        assert_eq!(rc.keybinds[1].widget, None);
+    }
+
+    #[test]
+    fn keybind_unknown_widget_errors() {
+        let (rc, errs) = resolve_kdl(
+            "widget bar { child t1 }\ntext t1\nkeybind k { key \"q\"; widget nope; action \"true\" }",
+        );
+        assert!(rc.is_none());
+        assert!(
+            errs.iter()
+                .any(|e| e.kind == ConfigErrorKind::UnresolvedReference)
+        );
+    }
+
     #[test]
     fn watch_events_resolve_into_watches() {
         let (cfg, perrs) = crate::config::parse_str(
@@ -627,7 +737,7 @@ mod tests {
         assert_eq!(rc.watches.len(), 2);
         let w1 = rc.watches.iter().find(|w| w.id == "w1").expect("w1");
         assert_eq!(w1.var, "flag");
-        assert_eq!(w1.action, "echo on");
+        assert_eq!(w1.action.command.as_deref(), Some("echo on"));
         assert_eq!(w1.duration, None);
         let t1 = rc.watches.iter().find(|w| w.id == "t1").expect("t1");
         assert_eq!(t1.duration, Some(std::time::Duration::from_secs(2)));
@@ -652,6 +762,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn watch_on_smart_bool() {
+        let (cfg, _perrs) = crate::config::parse_str(
+            "event away { type watchon; var iwwc.idle; action \"true\" }",
+            "<test>",
+        );
+        let (rc, errs) = resolve(&cfg.expect("parse ok"));
+        let rc = rc.unwrap_or_else(|| panic!("{errs:?}"));
+        assert_eq!(rc.watches[0].var, "iwwc.idle");
+        assert!(rc.smart_polls.iter().any(|(ns, _)| ns == "iwwc.idle"));
+
+        let (cfg, _perrs) = crate::config::parse_str(
+            "event w1 { type watchon; var iwwc.uptime; action \"true\" }",
+            "<test>",
+        );
+        let (rc, errs) = resolve(&cfg.expect("parse ok"));
+        assert!(rc.is_none());
+        assert!(
+            errs.iter()
+                .any(|e| e.message.contains("is not a bool variable")),
+            "{errs:?}"
+        );
+    }
+
     #[test]
     fn watch_on_unknown_var_errors() {
         let (cfg, _perrs) = crate::config::parse_str(
@@ -696,7 +830,10 @@ mod tests {
         let bar = rc.widgets.get("bar").expect("bar");
         match bar.child.as_deref() {
             Some(crate::config::resolved::ResolvedElement::Event(e)) => {
-                assert_eq!(e.action.as_deref(), Some("true"));
+                assert_eq!(
+                    e.action.as_ref().and_then(|a| a.command.as_deref()),
+                    Some("true")
+                );
             }
             other => panic!("expected event child, got {other:?}"),
         }
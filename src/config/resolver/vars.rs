@@ -18,14 +18,26 @@ impl FlatEnv {
         self.map.get(name)
     }
 
-    pub fn smart_polls(&self) -> Vec<(String, Option<std::time::Duration>)> {
+    pub fn smart_polls(
+        &self,
+        decls: &indexmap::IndexMap<String, crate::config::types::SmartDecl>,
+    ) -> Vec<(String, Option<std::time::Duration>)> {
         let mut seen = HashSet::new();
         let mut out = Vec::new();
-        for key in self.accessed_smart.borrow().iter() {
+        let accessed = self.accessed_smart.borrow();
+        let position = accessed
+            .iter()
+            .any(|k| crate::config::smart::mpris::is_position(k));
+        for key in accessed.iter() {
             if let Some(ns) = crate::config::smart::namespace_of(key)
                 && seen.insert(ns)
             {
-                out.push((ns.to_string(), crate::config::smart::poll_interval(ns)));
+                // without a position in the config, players are event-driven
+                let interval = match ns {
+                    "iwwc.mpris" if !position => None,
+                    _ => crate::config::smart::interval(ns, decls),
+                };
+                out.push((ns.to_string(), interval));
             }
         }
         out
@@ -51,7 +63,7 @@ pub(crate) fn resolve_vars(
         smart_keys: HashSet::new(),
         accessed_smart: RefCell::new(HashSet::new()),
     };
-    for (name, value) in crate::config::smart::values() {
+    for (name, value) in crate::config::smart::values(&config.smart) {
         env.smart_keys.insert(name.clone());
         if crate::config::smart::is_unset(&name, &value) && config.vars.contains_key(&name) {
             continue;
@@ -1,18 +1,221 @@
-use crate::config::types::VarValue;
+pub mod audio;
+pub mod backlight;
+mod battery;
+pub mod bluetooth;
+mod disk;
+mod gpu;
+pub mod keyboard;
+pub mod mpris;
+mod net;
+pub mod network;
+mod temps;
+pub mod toplevels;
+pub mod upower;
+pub mod workspaces;
+
+use crate::config::types::{SmartDecl, VarValue};
+use indexmap::IndexMap;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Mutex, OnceLock};
 use std::time::Duration;
 use sysinfo::{CpuRefreshKind, System};
 
 static SYS: OnceLock<Mutex<System>> = OnceLock::new();
-static ACTIVESONG: Mutex<Option<String>> = Mutex::new(None);
+static NET: OnceLock<Mutex<net::Sampler>> = OnceLock::new();
+static DISK: OnceLock<Mutex<disk::Sampler>> = OnceLock::new();
+static MPRIS: OnceLock<Mutex<mpris::Players>> = OnceLock::new();
+static AUDIO: Mutex<Option<audio::Audio>> = Mutex::new(None);
+static WORKSPACES: Mutex<Option<workspaces::Workspaces>> = Mutex::new(None);
+static KEYBOARD: Mutex<Option<keyboard::Keyboard>> = Mutex::new(None);
+static TOPLEVELS: Mutex<Option<toplevels::Toplevels>> = Mutex::new(None);
+static NETWORK: Mutex<Option<network::Network>> = Mutex::new(None);
+static BLUETOOTH: Mutex<Option<bluetooth::Bluetooth>> = Mutex::new(None);
+static UPOWER: Mutex<Option<upower::UPower>> = Mutex::new(None);
+static INHIBITED: AtomicBool = AtomicBool::new(false);
+static IDLE: AtomicBool = AtomicBool::new(false);
+
+const NAMESPACES: &[&str] = &[
+    "iwwc.activesong",
+    "iwwc.mpris",
+    "iwwc.audio",
+    "iwwc.workspaces",
+    "iwwc.keyboard",
+    "iwwc.toplevels",
+    "iwwc.network",
+    "iwwc.bluetooth",
+    "iwwc.upower",
+    "iwwc.inhibited",
+    "iwwc.idle",
+    "iwwc.ram",
+    "iwwc.swap",
+    "iwwc.cpu",
+    "iwwc.load",
+    "iwwc.uptime",
+    "iwwc.processes",
+    "iwwc.battery",
+    "iwwc.backlight",
+    "iwwc.temps",
+    "iwwc.gpu",
+    "iwwc.net",
+    "iwwc.disk",
+];
+
+/// Namespaces that are a single value rather than a tree.
+const LEAVES: &[&str] = &[
+    "iwwc.activesong",
+    "iwwc.inhibited",
+    "iwwc.idle",
+    "iwwc.uptime",
+    "iwwc.processes",
+];
+
+/// Namespaces computed from in-memory state on every read instead of
+/// being sampled into the cache.
+const LIVE: &[&str] = &[
+    "iwwc.activesong",
+    "iwwc.mpris",
+    "iwwc.audio",
+    "iwwc.workspaces",
+    "iwwc.keyboard",
+    "iwwc.toplevels",
+    "iwwc.network",
+    "iwwc.bluetooth",
+    "iwwc.upower",
+    "iwwc.inhibited",
+    "iwwc.idle",
+];
 
-pub fn set_activesong(title: Option<String>) -> bool {
-    let mut cur = ACTIVESONG.lock().unwrap();
-    if *cur == title {
-        return false;
+/// Applies a change to the MPRIS state; true when anything changed.
+pub fn update_mpris(f: impl FnOnce(&mut mpris::Players)) -> bool {
+    let mut players = MPRIS.get_or_init(Default::default).lock().unwrap();
+    let before = players.clone();
+    f(&mut players);
+    *players != before
+}
+
+/// Bus name suffix of `player`, or of the active player for `None`.
+pub fn mpris_player(player: Option<&str>) -> Option<String> {
+    let players = MPRIS.get_or_init(Default::default).lock().unwrap();
+    match player {
+        Some(name) => players.lookup(name),
+        None => players.active(),
     }
-    *cur = title;
-    true
+    .map(|p| p.name.clone())
+}
+
+/// Whether a player is playing, so that `iwwc.mpris.position` moves.
+pub fn mpris_playing() -> bool {
+    MPRIS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap()
+        .playing()
+}
+
+/// Applies a change to the audio state; true when anything changed.
+pub fn update_audio(f: impl FnOnce(&mut audio::Audio)) -> bool {
+    let mut state = AUDIO.lock().unwrap();
+    let state = state.get_or_insert_with(Default::default);
+    let before = state.clone();
+    f(state);
+    *state != before
+}
+
+pub fn audio() -> audio::Audio {
+    AUDIO.lock().unwrap().clone().unwrap_or_default()
+}
+
+/// Applies a change to the workspace state; true when anything changed.
+pub fn update_workspaces(f: impl FnOnce(&mut workspaces::Workspaces)) -> bool {
+    let mut state = WORKSPACES.lock().unwrap();
+    let state = state.get_or_insert_with(Default::default);
+    let before = state.values();
+    f(state);
+    state.values() != before
+}
+
+/// Protocol id of the workspace a `workspace=` action names.
+pub fn workspace_handle(name: &str) -> Option<u32> {
+    WORKSPACES.lock().unwrap().as_ref()?.lookup(name)
+}
+
+/// Applies a change to the keyboard state; true when anything changed.
+pub fn update_keyboard(f: impl FnOnce(&mut keyboard::Keyboard)) -> bool {
+    let mut state = KEYBOARD.lock().unwrap();
+    let state = state.get_or_insert_with(Default::default);
+    let before = state.clone();
+    f(state);
+    *state != before
+}
+
+/// Applies a change to the window list; true when anything changed.
+pub fn update_toplevels(f: impl FnOnce(&mut toplevels::Toplevels)) -> bool {
+    let mut state = TOPLEVELS.lock().unwrap();
+    let state = state.get_or_insert_with(Default::default);
+    let before = state.values();
+    f(state);
+    state.values() != before
+}
+
+/// Open windows for the taskbar, in the order they appeared.
+pub fn toplevels() -> Vec<toplevels::Window> {
+    TOPLEVELS
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|t| t.windows())
+        .unwrap_or_default()
+}
+
+/// Replaces the NetworkManager state; true when anything changed.
+pub fn update_network(f: impl FnOnce(&mut network::Network)) -> bool {
+    let mut state = NETWORK.lock().unwrap();
+    let state = state.get_or_insert_with(Default::default);
+    let before = state.clone();
+    f(state);
+    *state != before
+}
+
+/// Replaces the BlueZ state; true when anything changed.
+pub fn update_bluetooth(f: impl FnOnce(&mut bluetooth::Bluetooth)) -> bool {
+    let mut state = BLUETOOTH.lock().unwrap();
+    let state = state.get_or_insert_with(Default::default);
+    let before = state.clone();
+    f(state);
+    *state != before
+}
+
+/// Applies a change to the UPower state; true when anything changed.
+pub fn update_upower(f: impl FnOnce(&mut upower::UPower)) -> bool {
+    let mut state = UPOWER.lock().unwrap();
+    let state = state.get_or_insert_with(Default::default);
+    let before = state.clone();
+    f(state);
+    *state != before
+}
+
+/// Records whether iwwc holds a logind inhibitor; true when it changed.
+pub fn set_inhibited(on: bool) -> bool {
+    INHIBITED.swap(on, Ordering::Relaxed) != on
+}
+
+/// Records whether the seat is idle; true when it changed.
+pub fn set_idle(on: bool) -> bool {
+    IDLE.swap(on, Ordering::Relaxed) != on
+}
+
+pub fn idle() -> bool {
+    IDLE.load(Ordering::Relaxed)
+}
+
+/// `smart iwwc.idle timeout=`, five minutes by default.
+pub fn idle_timeout(decls: &IndexMap<String, SmartDecl>) -> Duration {
+    decls
+        .get("iwwc.idle")
+        .and_then(|d| d.timeout)
+        .unwrap_or(Duration::from_secs(300))
 }
 
 pub fn is_unset(key: &str, value: &VarValue) -> bool {
@@ -25,37 +228,210 @@ pub fn test_lock() -> std::sync::MutexGuard<'static, ()> {
     LOCK.lock().unwrap_or_else(|e| e.into_inner())
 }
 
-pub fn values() -> Vec<(String, VarValue)> {
-    let mut out = Vec::new();
-    out.push((
-        "iwwc.activesong".to_string(),
-        VarValue::Str(ACTIVESONG.lock().unwrap().clone().unwrap_or_default()),
-    ));
-    if let Ok(text) = std::fs::read_to_string("/proc/meminfo")
-        && let Some((total, used)) = read_meminfo(&text)
-    {
-        out.push(("iwwc.ram.total".to_string(), VarValue::Int(total)));
-        out.push(("iwwc.ram.used".to_string(), VarValue::Int(used)));
+/// Last sample of every polled namespace. Namespaces are sampled on their
+/// own timer through [`refresh`]; [`values`] only fills in ones never read.
+#[derive(Default)]
+struct Cache {
+    by_ns: HashMap<&'static str, Vec<(String, VarValue)>>,
+}
+
+impl Cache {
+    fn refresh(
+        &mut self,
+        namespaces: &[&'static str],
+        mut sample: impl FnMut(&'static str) -> Vec<(String, VarValue)>,
+    ) {
+        for &ns in namespaces {
+            self.by_ns.insert(ns, sample(ns));
+        }
     }
-    let mut sys = SYS
-        .get_or_init(|| Mutex::new(System::new()))
-        .lock()
-        .unwrap();
-    sys.refresh_cpu_specifics(CpuRefreshKind::nothing().with_cpu_usage().with_frequency());
-    for (i, cpu) in sys.cpus().iter().enumerate() {
-        out.push((
-            format!("iwwc.cpu.{i}.usage"),
-            VarValue::Float(round2(cpu.cpu_usage())),
-        ));
-        out.push((
-            format!("iwwc.cpu.{i}.frequency"),
-            VarValue::Int(cpu.frequency() as i128),
-        ));
+
+    fn values(
+        &mut self,
+        mut sample: impl FnMut(&'static str) -> Vec<(String, VarValue)>,
+    ) -> Vec<(String, VarValue)> {
+        let mut out = Vec::new();
+        for &ns in NAMESPACES.iter().filter(|ns| !LIVE.contains(ns)) {
+            out.extend(self.by_ns.entry(ns).or_insert_with(|| sample(ns)).clone());
+        }
+        out
     }
+}
+
+static CACHE: Mutex<Option<Cache>> = Mutex::new(None);
+
+pub fn values(decls: &IndexMap<String, SmartDecl>) -> Vec<(String, VarValue)> {
+    let players = MPRIS.get_or_init(Default::default).lock().unwrap();
+    let mut out = vec![(
+        "iwwc.activesong".to_string(),
+        VarValue::Str(
+            players
+                .active()
+                .map(|p| p.title.clone())
+                .unwrap_or_default(),
+        ),
+    )];
+    out.extend(players.values(std::time::Instant::now()));
+    drop(players);
+    out.extend(audio().values());
+    out.extend(
+        WORKSPACES
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|ws| ws.values())
+            .unwrap_or_default(),
+    );
+    out.extend(
+        KEYBOARD
+            .lock()
+            .unwrap()
+            .clone()
+            .unwrap_or_default()
+            .values(),
+    );
+    out.extend(
+        TOPLEVELS
+            .lock()
+            .unwrap()
+            .clone()
+            .unwrap_or_default()
+            .values(),
+    );
+    out.extend(NETWORK.lock().unwrap().clone().unwrap_or_default().values());
+    out.extend(
+        BLUETOOTH
+            .lock()
+            .unwrap()
+            .clone()
+            .unwrap_or_default()
+            .values(),
+    );
+    out.extend(UPOWER.lock().unwrap().clone().unwrap_or_default().values());
     out.push((
-        "iwwc.cpu.avg.usage".to_string(),
-        VarValue::Float(round2(sys.global_cpu_usage())),
+        "iwwc.inhibited".to_string(),
+        VarValue::Bool(INHIBITED.load(Ordering::Relaxed)),
     ));
+    out.push(("iwwc.idle".to_string(), VarValue::Bool(idle())));
+    let mut cache = CACHE.lock().unwrap();
+    out.extend(
+        cache
+            .get_or_insert_with(Default::default)
+            .values(|ns| sample(ns, decls)),
+    );
+    out
+}
+
+/// Resamples the given namespaces; `iwwc` stands for all of them.
+pub fn refresh(names: &[String], decls: &IndexMap<String, SmartDecl>) {
+    let namespaces: Vec<&'static str> = NAMESPACES
+        .iter()
+        .copied()
+        .filter(|ns| !LIVE.contains(ns))
+        .filter(|ns| names.iter().any(|n| n == "iwwc" || n == ns))
+        .collect();
+    let mut cache = CACHE.lock().unwrap();
+    cache
+        .get_or_insert_with(Default::default)
+        .refresh(&namespaces, |ns| sample(ns, decls));
+}
+
+fn sample(namespace: &str, decls: &IndexMap<String, SmartDecl>) -> Vec<(String, VarValue)> {
+    let mut out = Vec::new();
+    match namespace {
+        "iwwc.ram" | "iwwc.swap" => {
+            if let Ok(text) = std::fs::read_to_string("/proc/meminfo")
+                && let Some(mem) = read_meminfo(&text)
+            {
+                if namespace == "iwwc.ram" {
+                    out.push(("iwwc.ram.total".to_string(), VarValue::Int(mem.total)));
+                    out.push(("iwwc.ram.used".to_string(), VarValue::Int(mem.used)));
+                    out.push(("iwwc.ram.cached".to_string(), VarValue::Int(mem.cached)));
+                    out.push(("iwwc.ram.buffers".to_string(), VarValue::Int(mem.buffers)));
+                } else {
+                    out.push(("iwwc.swap.total".to_string(), VarValue::Int(mem.swap_total)));
+                    out.push(("iwwc.swap.used".to_string(), VarValue::Int(mem.swap_used)));
+                }
+            }
+        }
+        "iwwc.load" => {
+            if let Ok(text) = std::fs::read_to_string("/proc/loadavg")
+                && let Some(load) = read_loadavg(&text)
+            {
+                for (span, v) in ["1", "5", "15"].into_iter().zip(load) {
+                    out.push((format!("iwwc.load.{span}"), VarValue::Float(v)));
+                }
+            }
+        }
+        "iwwc.uptime" => {
+            if let Ok(text) = std::fs::read_to_string("/proc/uptime")
+                && let Some(secs) = read_uptime(&text)
+            {
+                out.push(("iwwc.uptime".to_string(), VarValue::Int(secs)));
+            }
+        }
+        "iwwc.processes" => out.push((
+            "iwwc.processes".to_string(),
+            VarValue::Int(count_processes(Path::new("/proc"))),
+        )),
+        "iwwc.cpu" => {
+            let mut sys = SYS
+                .get_or_init(|| Mutex::new(System::new()))
+                .lock()
+                .unwrap();
+            sys.refresh_cpu_specifics(CpuRefreshKind::nothing().with_cpu_usage().with_frequency());
+            for (i, cpu) in sys.cpus().iter().enumerate() {
+                out.push((
+                    format!("iwwc.cpu.{i}.usage"),
+                    VarValue::Float(round2(cpu.cpu_usage())),
+                ));
+                out.push((
+                    format!("iwwc.cpu.{i}.frequency"),
+                    VarValue::Int(cpu.frequency() as i128),
+                ));
+            }
+            out.push((
+                "iwwc.cpu.avg.usage".to_string(),
+                VarValue::Float(round2(sys.global_cpu_usage())),
+            ));
+            let freqs: Vec<u64> = sys.cpus().iter().map(|c| c.frequency()).collect();
+            if let (Some(min), Some(max)) = (freqs.iter().min(), freqs.iter().max()) {
+                let avg = freqs.iter().sum::<u64>() / freqs.len() as u64;
+                for (agg, v) in [("min", *min), ("max", *max), ("avg", avg)] {
+                    out.push((
+                        format!("iwwc.cpu.{agg}.frequency"),
+                        VarValue::Int(v as i128),
+                    ));
+                }
+            }
+        }
+        "iwwc.battery" => out.extend(battery::values(Path::new(battery::ROOT))),
+        "iwwc.backlight" => out.extend(backlight::values(Path::new(backlight::ROOT))),
+        "iwwc.temps" => {
+            out.extend(temps::values(Path::new(temps::ROOT)));
+            out.extend(temps::per_core(
+                Path::new(temps::ROOT),
+                Path::new(temps::CPU_ROOT),
+            ));
+        }
+        "iwwc.gpu" => out.extend(gpu::values(Path::new(gpu::ROOT))),
+        "iwwc.net" => out.extend(
+            NET.get_or_init(Default::default)
+                .lock()
+                .unwrap()
+                .values(Path::new(net::ROOT), std::time::Instant::now()),
+        ),
+        "iwwc.disk" => {
+            let filter = decls.get("iwwc.disk").and_then(|d| d.mounts.as_deref());
+            out.extend(DISK.get_or_init(Default::default).lock().unwrap().values(
+                &disk::mounts(),
+                &std::fs::read_to_string(disk::DISKSTATS).unwrap_or_default(),
+                filter,
+                std::time::Instant::now(),
+            ));
+        }
+        _ => {}
+    }
     out
 }
 
@@ -77,43 +453,155 @@ pub fn children(values: &[(String, VarValue)], prefix: &str) -> Vec<String> {
     out
 }
 
+/// A sysfs or procfs file without its trailing newline.
+pub(crate) fn read_trimmed(path: &Path) -> Option<String> {
+    std::fs::read_to_string(path)
+        .ok()
+        .map(|s| s.trim().to_string())
+}
+
+/// Turns a device or mount name into a single variable path segment.
+pub(crate) fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
 pub fn namespace_of(name: &str) -> Option<&'static str> {
-    if name == "iwwc.activesong" {
-        return Some("iwwc.activesong");
+    if let Some(leaf) = LEAVES.iter().copied().find(|l| *l == name) {
+        return Some(leaf);
     }
-    ["iwwc.ram", "iwwc.cpu"].into_iter().find(|ns| {
+    // per-core temperatures walk hwmon, so they come with `iwwc.temps`
+    if name
+        .strip_prefix("iwwc.cpu.")
+        .and_then(|rest| rest.strip_suffix(".temp"))
+        .is_some_and(|n| n.parse::<u32>().is_ok())
+    {
+        return Some("iwwc.temps");
+    }
+    NAMESPACES.iter().copied().find(|ns| {
         name.strip_prefix(ns)
             .is_some_and(|rest| rest.starts_with('.'))
     })
 }
 
+pub fn is_namespace(name: &str) -> bool {
+    name == "iwwc" || NAMESPACES.contains(&name)
+}
+
+/// How often a namespace is resampled: `smart <namespace> interval=` when
+/// set, else the built-in default. `None` for event-driven namespaces.
+pub fn interval(namespace: &str, decls: &IndexMap<String, SmartDecl>) -> Option<Duration> {
+    let default = poll_interval(namespace)?;
+    Some(
+        decls
+            .get(namespace)
+            .and_then(|d| d.interval)
+            .unwrap_or(default),
+    )
+}
+
 pub fn poll_interval(namespace: &str) -> Option<Duration> {
     match namespace {
-        "iwwc.ram" | "iwwc.cpu" => Some(Duration::from_secs(1)),
+        "iwwc.ram" | "iwwc.swap" | "iwwc.cpu" | "iwwc.uptime" => Some(Duration::from_secs(1)),
+        "iwwc.processes" => Some(Duration::from_secs(2)),
+        "iwwc.load" => Some(Duration::from_secs(5)),
+        "iwwc.battery" => Some(Duration::from_secs(10)),
+        "iwwc.temps" => Some(Duration::from_secs(5)),
+        "iwwc.gpu" | "iwwc.net" => Some(Duration::from_secs(2)),
+        // only the playback position moves without an event; ticks only
+        // while it is referenced and a player is playing
+        "iwwc.mpris" => Some(Duration::from_secs(1)),
+        "iwwc.disk" => Some(Duration::from_secs(30)),
         _ => None,
     }
 }
 
-fn read_meminfo(text: &str) -> Option<(i128, i128)> {
-    let mut total_kb: Option<i128> = None;
-    let mut avail_kb: Option<i128> = None;
-    for line in text.lines() {
-        if let Some(rest) = line.strip_prefix("MemTotal:") {
-            total_kb = parse_kb(rest);
-        } else if let Some(rest) = line.strip_prefix("MemAvailable:") {
-            avail_kb = parse_kb(rest);
-        }
-    }
-    let total = total_kb?;
-    let avail = avail_kb?;
-    let used = (total - avail).max(0);
-    Some((total * 1024, used * 1024))
+/// Bytes, from `/proc/meminfo`.
+#[derive(Debug, PartialEq)]
+struct Meminfo {
+    total: i128,
+    used: i128,
+    cached: i128,
+    buffers: i128,
+    swap_total: i128,
+    swap_used: i128,
+}
+
+fn read_meminfo(text: &str) -> Option<Meminfo> {
+    let field = |name: &str| -> Option<i128> {
+        text.lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+            .and_then(parse_kb)
+            .map(|kb| kb * 1024)
+    };
+    let total = field("MemTotal")?;
+    let avail = field("MemAvailable")?;
+    let swap_total = field("SwapTotal").unwrap_or(0);
+    Some(Meminfo {
+        total,
+        used: (total - avail).max(0),
+        cached: field("Cached").unwrap_or(0),
+        buffers: field("Buffers").unwrap_or(0),
+        swap_total,
+        swap_used: (swap_total - field("SwapFree").unwrap_or(swap_total)).max(0),
+    })
 }
 
 fn parse_kb(s: &str) -> Option<i128> {
     s.split_whitespace().next()?.parse::<i128>().ok()
 }
 
+/// The 1, 5 and 15 minute averages.
+fn read_loadavg(text: &str) -> Option<[f64; 3]> {
+    let mut fields = text.split_whitespace().map(|f| f.parse::<f64>().ok());
+    Some([fields.next()??, fields.next()??, fields.next()??])
+}
+
+/// Whole seconds since boot.
+fn read_uptime(text: &str) -> Option<i128> {
+    let secs: f64 = text.split_whitespace().next()?.parse().ok()?;
+    Some(secs as i128)
+}
+
+fn count_processes(proc: &Path) -> i128 {
+    std::fs::read_dir(proc)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|e| {
+            e.file_name()
+                .to_string_lossy()
+                .bytes()
+                .all(|b| b.is_ascii_digit())
+        })
+        .count() as i128
+}
+
+/// Fixtures shared by the namespace tests.
+#[cfg(test)]
+pub(crate) mod testutil {
+    use crate::config::types::VarValue;
+    use std::path::Path;
+
+    /// Writes `root/rel` the way sysfs shows it, with a trailing newline.
+    pub fn write(root: &Path, rel: &str, value: &str) {
+        let path = root.join(rel);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, format!("{value}\n")).unwrap();
+    }
+
+    pub fn get(vals: &[(String, VarValue)], key: &str) -> Option<VarValue> {
+        vals.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -121,7 +609,41 @@ mod tests {
     #[test]
     fn meminfo_parsed_to_bytes() {
         let sample = "MemTotal:       8000 kB\nMemFree: 1000 kB\nMemAvailable:   2000 kB\n";
-        assert_eq!(read_meminfo(sample), Some((8000 * 1024, 6000 * 1024)));
+        let mem = read_meminfo(sample).unwrap();
+        assert_eq!((mem.total, mem.used), (8000 * 1024, 6000 * 1024));
+        assert_eq!((mem.swap_total, mem.swap_used), (0, 0));
+    }
+
+    #[test]
+    fn meminfo_swap_and_cache() {
+        let sample = "MemTotal: 8000 kB\nMemAvailable: 2000 kB\nBuffers: 100 kB\n\
+                      Cached: 3000 kB\nSwapCached: 5 kB\nSwapTotal: 4000 kB\nSwapFree: 3000 kB\n";
+        assert_eq!(
+            read_meminfo(sample),
+            Some(Meminfo {
+                total: 8000 * 1024,
+                used: 6000 * 1024,
+                cached: 3000 * 1024,
+                buffers: 100 * 1024,
+                swap_total: 4000 * 1024,
+                swap_used: 1000 * 1024,
+            })
+        );
+    }
+
+    #[test]
+    fn loadavg_uptime_processes() {
+        assert_eq!(
+            read_loadavg("0.52 0.71 1.05 2/1234 56789\n"),
+            Some([0.52, 0.71, 1.05])
+        );
+        assert_eq!(read_loadavg("0.52\n"), None);
+        assert_eq!(read_uptime("12345.67 45678.90\n"), Some(12345));
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["1", "42", "self", "net"] {
+            std::fs::create_dir(dir.path().join(name)).unwrap();
+        }
+        assert_eq!(count_processes(dir.path()), 2);
     }
 
     #[test]
@@ -135,8 +657,64 @@ mod tests {
         assert_eq!(namespace_of("iwwc.ram"), None);
         assert_eq!(namespace_of("x"), None);
         assert_eq!(poll_interval("iwwc.ram"), Some(Duration::from_secs(1)));
-        assert_eq!(poll_interval("iwwc.disk"), None);
+        assert_eq!(poll_interval("iwwc.disk"), Some(Duration::from_secs(30)));
+        assert_eq!(poll_interval("iwwc.nope"), None);
         assert_eq!(namespace_of("iwwc.activesong"), Some("iwwc.activesong"));
         assert_eq!(poll_interval("iwwc.activesong"), None);
+        assert!(is_namespace("iwwc.cpu"));
+        assert!(!is_namespace("iwwc.cpu.0"));
+        assert_eq!(
+            namespace_of("iwwc.battery.bat0.status"),
+            Some("iwwc.battery")
+        );
+        assert_eq!(poll_interval("iwwc.battery"), Some(Duration::from_secs(10)));
+        assert_eq!(namespace_of("iwwc.uptime"), Some("iwwc.uptime"));
+        assert_eq!(namespace_of("iwwc.load.5"), Some("iwwc.load"));
+        assert_eq!(namespace_of("iwwc.cpu.3.temp"), Some("iwwc.temps"));
+        assert_eq!(namespace_of("iwwc.cpu.avg.usage"), Some("iwwc.cpu"));
+        assert!(is_namespace("iwwc.swap"));
+    }
+
+    #[test]
+    fn cache_samples_only_refreshed_namespaces() {
+        let mut cache = Cache::default();
+        let sampled = std::cell::RefCell::new(Vec::new());
+        let sample = |ns: &'static str| {
+            sampled.borrow_mut().push(ns);
+            let n = sampled.borrow().len() as i128;
+            vec![(format!("{ns}.x"), VarValue::Int(n))]
+        };
+        let get = |vals: &[(String, VarValue)], key: &str| {
+            vals.iter().find(|(k, _)| k == key).unwrap().1.clone()
+        };
+        let first = cache.values(&sample);
+        assert_eq!(first.len(), NAMESPACES.len() - LIVE.len());
+        let n = sampled.borrow().len();
+        cache.values(&sample);
+        assert_eq!(
+            sampled.borrow().len(),
+            n,
+            "second read is served from the cache"
+        );
+        cache.refresh(&["iwwc.cpu"], &sample);
+        assert_eq!(sampled.borrow()[n..], ["iwwc.cpu"]);
+        let after = cache.values(&sample);
+        assert_eq!(get(&after, "iwwc.cpu.x"), VarValue::Int(n as i128 + 1));
+        assert_eq!(get(&after, "iwwc.ram.x"), get(&first, "iwwc.ram.x"));
+    }
+
+    #[test]
+    fn configured_interval_overrides_default() {
+        let (cfg, _) = crate::config::parse_str(r#"smart iwwc.cpu interval="3s""#, "<t>");
+        let decls = cfg.unwrap().smart;
+        assert_eq!(interval("iwwc.cpu", &decls), Some(Duration::from_secs(3)));
+        assert_eq!(interval("iwwc.ram", &decls), Some(Duration::from_secs(1)));
+        assert_eq!(interval("iwwc.activesong", &decls), None);
+    }
+
+    #[test]
+    fn sanitize_makes_one_segment() {
+        assert_eq!(sanitize("BAT0"), "bat0");
+        assert_eq!(sanitize("ps-controller:01"), "ps_controller_01");
     }
 }
@@ -0,0 +1,138 @@
+//! `iwwc.audio.*`: default sink and source of the PulseAudio-compatible
+//! server (PulseAudio or pipewire-pulse). `crate::audio` keeps [`Audio`]
+//! current from server events; nothing here is polled.
+
+use crate::config::types::VarValue;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Device {
+    /// Server name of the default device, e.g. `alsa_output.pci-0000_00_1f.3.analog-stereo`.
+    pub name: String,
+    /// Percent, averaged over channels.
+    pub volume: f64,
+    pub muted: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Audio {
+    /// The server answered the last query.
+    pub connected: bool,
+    pub sink: Device,
+    pub source: Device,
+}
+
+impl Audio {
+    pub fn device_mut(&mut self, source: bool) -> &mut Device {
+        if source {
+            &mut self.source
+        } else {
+            &mut self.sink
+        }
+    }
+
+    pub fn values(&self) -> Vec<(String, VarValue)> {
+        let mut out = vec![(
+            "iwwc.audio.connected".to_string(),
+            VarValue::Bool(self.connected),
+        )];
+        for (kind, d) in [("sink", &self.sink), ("source", &self.source)] {
+            let key = |field: &str| format!("iwwc.audio.{kind}.{field}");
+            out.push((key("name"), VarValue::Str(d.name.clone())));
+            out.push((key("volume"), VarValue::Int(d.volume.round() as i128)));
+            out.push((key("muted"), VarValue::Bool(d.muted)));
+        }
+        out
+    }
+}
+
+/// Average of the per-channel percentages in `pactl get-sink-volume`
+/// output: `Volume: front-left: 26214 /  40% / -23.88 dB,   front-right: ...`.
+pub fn parse_volume(text: &str) -> Option<f64> {
+    let percents: Vec<f64> = text
+        .split_whitespace()
+        .filter_map(|w| w.strip_suffix('%')?.parse().ok())
+        .collect();
+    (!percents.is_empty()).then(|| percents.iter().sum::<f64>() / percents.len() as f64)
+}
+
+/// `Mute: yes` / `Mute: no`.
+pub fn parse_mute(text: &str) -> Option<bool> {
+    match text.trim().strip_prefix("Mute:")?.trim() {
+        "yes" => Some(true),
+        "no" => Some(false),
+        _ => None,
+    }
+}
+
+/// Whether a `pactl subscribe` line can change the defaults or their
+/// volume: `Event 'change' on sink #47`. `server` covers a new default.
+pub fn is_relevant_event(line: &str) -> bool {
+    let Some(rest) = line.trim().strip_prefix("Event '") else {
+        return false;
+    };
+    let facility = rest
+        .split_once("' on ")
+        .and_then(|(_, f)| f.split_whitespace().next());
+    matches!(facility, Some("sink" | "source" | "server"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_pactl_output() {
+        let stereo = "Volume: front-left: 26214 /  40% / -23.88 dB,   front-right: 32768 /  50% / -18.06 dB\n        balance 0.10\n";
+        assert_eq!(parse_volume(stereo), Some(45.0));
+        assert_eq!(
+            parse_volume("Volume: mono: 65536 / 100% / 0.00 dB\n"),
+            Some(100.0)
+        );
+        assert_eq!(parse_volume("No such entity"), None);
+        assert_eq!(parse_mute("Mute: yes\n"), Some(true));
+        assert_eq!(parse_mute("Mute: no"), Some(false));
+        assert_eq!(parse_mute("Failure: No such entity"), None);
+    }
+
+    #[test]
+    fn relevant_events() {
+        assert!(is_relevant_event("Event 'change' on sink #47"));
+        assert!(is_relevant_event("Event 'new' on source #3"));
+        assert!(is_relevant_event("Event 'change' on server #-1"));
+        assert!(!is_relevant_event("Event 'change' on sink-input #120"));
+        assert!(!is_relevant_event("Event 'remove' on client #88"));
+        assert!(!is_relevant_event("garbage"));
+    }
+
+    #[test]
+    fn values_for_both_devices() {
+        let audio = Audio {
+            connected: true,
+            sink: Device {
+                name: "speakers".into(),
+                volume: 42.6,
+                muted: false,
+            },
+            source: Device {
+                name: "mic".into(),
+                volume: 80.0,
+                muted: true,
+            },
+        };
+        let vals = audio.values();
+        let get = |k: &str| vals.iter().find(|(key, _)| key == k).unwrap().1.clone();
+        assert_eq!(get("iwwc.audio.connected"), VarValue::Bool(true));
+        assert_eq!(get("iwwc.audio.sink.volume"), VarValue::Int(43));
+        assert_eq!(
+            get("iwwc.audio.sink.name"),
+            VarValue::Str("speakers".into())
+        );
+        assert_eq!(get("iwwc.audio.source.muted"), VarValue::Bool(true));
+        // keys exist before the server answered
+        assert_eq!(
+            Audio::default().values().len(),
+            vals.len(),
+            "same keys without a server"
+        );
+    }
+}
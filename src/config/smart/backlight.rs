@@ -0,0 +1,217 @@
+//! `iwwc.backlight.*`, read from `/sys/class/backlight`. `crate::backlight`
+//! resamples it when a `brightness` file is written.
+
+use crate::config::primitives::BrightnessChange;
+use crate::config::types::VarValue;
+use std::path::{Path, PathBuf};
+
+pub const ROOT: &str = "/sys/class/backlight";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Device {
+    /// Directory name, e.g. `intel_backlight`; what logind expects.
+    pub name: String,
+    pub dir: PathBuf,
+    pub brightness: u64,
+    pub max: u64,
+}
+
+impl Device {
+    pub fn percent(&self) -> i128 {
+        if self.max == 0 {
+            return 0;
+        }
+        (self.brightness as f64 / self.max as f64 * 100.0).round() as i128
+    }
+
+    /// Raw value for `percent`, within `0..=max`.
+    pub fn raw(&self, percent: f64) -> u64 {
+        ((percent / 100.0 * self.max as f64).round().max(0.0) as u64).min(self.max)
+    }
+
+    /// Raw value after `change`. A step moves at least one raw unit, and
+    /// never below 1 so scrolling down does not switch the panel off.
+    pub fn target(&self, change: BrightnessChange) -> u64 {
+        match change {
+            BrightnessChange::Set(percent) => self.raw(percent),
+            BrightnessChange::Adjust(step) => {
+                let exact = self.brightness as f64 / self.max.max(1) as f64 * 100.0;
+                let mut raw = self.raw(exact + step);
+                if raw == self.brightness && step > 0.0 {
+                    raw = (raw + 1).min(self.max);
+                } else if raw == self.brightness && step < 0.0 {
+                    raw = raw.saturating_sub(1);
+                }
+                raw.max(1).min(self.max)
+            }
+        }
+    }
+}
+
+fn read(dir: &Path, file: &str) -> Option<String> {
+    super::read_trimmed(&dir.join(file))
+}
+
+/// The kernel's preference when several interfaces drive one panel.
+fn rank(kind: Option<&str>) -> u8 {
+    match kind {
+        Some("firmware") => 0,
+        Some("platform") => 1,
+        Some("raw") => 2,
+        _ => 3,
+    }
+}
+
+/// All devices, preferred first.
+pub fn devices(root: &Path) -> Vec<Device> {
+    let mut out: Vec<(u8, Device)> = std::fs::read_dir(root)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let dir = entry.path();
+            let device = Device {
+                name: entry.file_name().to_string_lossy().into_owned(),
+                brightness: read(&dir, "brightness")?.parse().ok()?,
+                max: read(&dir, "max_brightness")?.parse().ok()?,
+                dir: dir.clone(),
+            };
+            Some((rank(read(&dir, "type").as_deref()), device))
+        })
+        .collect();
+    out.sort_by(|(ra, a), (rb, b)| ra.cmp(rb).then_with(|| a.name.cmp(&b.name)));
+    out.into_iter().map(|(_, d)| d).collect()
+}
+
+pub fn values(root: &Path) -> Vec<(String, VarValue)> {
+    let devices = devices(root);
+    let mut out = Vec::new();
+    let key = |k: &str| format!("iwwc.backlight.{k}");
+    for d in &devices {
+        let k = |field: &str| key(&format!("{}.{field}", super::sanitize(&d.name)));
+        out.push((k("brightness"), VarValue::Int(d.brightness as i128)));
+        out.push((k("max"), VarValue::Int(d.max as i128)));
+        out.push((k("percent"), VarValue::Int(d.percent())));
+    }
+    let main = devices.first();
+    out.push((key("present"), VarValue::Bool(main.is_some())));
+    out.push((
+        key("device"),
+        VarValue::Str(main.map(|d| d.name.clone()).unwrap_or_default()),
+    ));
+    out.push((
+        key("brightness"),
+        VarValue::Int(main.map_or(0, |d| d.brightness as i128)),
+    ));
+    out.push((key("max"), VarValue::Int(main.map_or(0, |d| d.max as i128))));
+    out.push((
+        key("percent"),
+        VarValue::Int(main.map_or(0, Device::percent)),
+    ));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::smart::testutil::{get, write};
+
+    fn backlight(root: &Path, name: &str, files: &[(&str, &str)]) {
+        for (f, v) in files {
+            write(root, &format!("{name}/{f}"), v);
+        }
+    }
+
+    #[test]
+    fn firmware_interface_preferred() {
+        let dir = tempfile::tempdir().unwrap();
+        backlight(
+            dir.path(),
+            "intel_backlight",
+            &[
+                ("type", "raw"),
+                ("brightness", "9600"),
+                ("max_brightness", "19200"),
+            ],
+        );
+        backlight(
+            dir.path(),
+            "acpi_video0",
+            &[
+                ("type", "firmware"),
+                ("brightness", "7"),
+                ("max_brightness", "10"),
+            ],
+        );
+        // unreadable devices are skipped
+        backlight(dir.path(), "broken", &[("type", "raw")]);
+        let vals = values(dir.path());
+        assert_eq!(
+            get(&vals, "iwwc.backlight.present"),
+            Some(VarValue::Bool(true))
+        );
+        assert_eq!(
+            get(&vals, "iwwc.backlight.device"),
+            Some(VarValue::Str("acpi_video0".into()))
+        );
+        assert_eq!(
+            get(&vals, "iwwc.backlight.percent"),
+            Some(VarValue::Int(70))
+        );
+        assert_eq!(
+            get(&vals, "iwwc.backlight.intel_backlight.percent"),
+            Some(VarValue::Int(50))
+        );
+        assert_eq!(
+            get(&vals, "iwwc.backlight.intel_backlight.max"),
+            Some(VarValue::Int(19200))
+        );
+        assert!(
+            !vals
+                .iter()
+                .any(|(k, _)| k.starts_with("iwwc.backlight.broken"))
+        );
+    }
+
+    #[test]
+    fn no_backlight() {
+        let dir = tempfile::tempdir().unwrap();
+        let vals = values(dir.path());
+        assert_eq!(
+            get(&vals, "iwwc.backlight.present"),
+            Some(VarValue::Bool(false))
+        );
+        assert_eq!(get(&vals, "iwwc.backlight.percent"), Some(VarValue::Int(0)));
+        assert_eq!(values(&dir.path().join("missing")).len(), vals.len());
+    }
+
+    #[test]
+    fn raw_from_percent() {
+        let d = Device {
+            name: "x".into(),
+            dir: PathBuf::new(),
+            brightness: 0,
+            max: 255,
+        };
+        assert_eq!(d.raw(50.0), 128);
+        assert_eq!(d.raw(120.0), 255);
+        assert_eq!(d.raw(-5.0), 0);
+    }
+
+    #[test]
+    fn brightness_steps() {
+        let d = |brightness, max| Device {
+            name: "x".into(),
+            dir: PathBuf::new(),
+            brightness,
+            max,
+        };
+        assert_eq!(d(9600, 19200).target(BrightnessChange::Adjust(5.0)), 10560);
+        assert_eq!(d(9600, 19200).target(BrightnessChange::Set(0.0)), 0);
+        // coarse devices still move one step
+        assert_eq!(d(3, 7).target(BrightnessChange::Adjust(5.0)), 4);
+        assert_eq!(d(3, 7).target(BrightnessChange::Adjust(-5.0)), 2);
+        assert_eq!(d(1, 7).target(BrightnessChange::Adjust(-50.0)), 1);
+        assert_eq!(d(7, 7).target(BrightnessChange::Adjust(5.0)), 7);
+    }
+}
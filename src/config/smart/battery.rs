@@ -0,0 +1,304 @@
+//! `iwwc.battery.*`, read from `/sys/class/power_supply`.
+
+use crate::config::types::VarValue;
+use std::path::Path;
+
+pub const ROOT: &str = "/sys/class/power_supply";
+
+#[derive(Debug)]
+struct Battery {
+    name: String,
+    capacity: Option<i128>,
+    status: String,
+    /// Peripheral batteries (mice, headsets) report `scope` Device and stay
+    /// out of the aggregate.
+    system: bool,
+    /// µW
+    power: f64,
+    /// µWh
+    energy_now: Option<f64>,
+    energy_full: Option<f64>,
+}
+
+impl Battery {
+    fn time_to_empty(&self) -> i128 {
+        match self.energy_now {
+            Some(now) if self.status == "discharging" => seconds(now, self.power),
+            _ => 0,
+        }
+    }
+
+    fn time_to_full(&self) -> i128 {
+        match (self.energy_now, self.energy_full) {
+            (Some(now), Some(full)) if self.status == "charging" => {
+                seconds((full - now).max(0.0), self.power)
+            }
+            _ => 0,
+        }
+    }
+}
+
+fn seconds(energy: f64, power: f64) -> i128 {
+    if power <= 0.0 {
+        return 0;
+    }
+    (energy / power * 3600.0).round() as i128
+}
+
+fn read(dir: &Path, file: &str) -> Option<String> {
+    super::read_trimmed(&dir.join(file))
+}
+
+fn read_f64(dir: &Path, file: &str) -> Option<f64> {
+    read(dir, file)?.parse().ok()
+}
+
+fn read_battery(dir: &Path, name: &str) -> Battery {
+    let voltage = read_f64(dir, "voltage_now");
+    let design_voltage = read_f64(dir, "voltage_min_design").or(voltage);
+    // Drivers report either energy_* (µWh) or charge_* (µAh); the latter is
+    // converted with the design voltage.
+    let energy = |kind: &str| {
+        read_f64(dir, &format!("energy_{kind}"))
+            .or_else(|| Some(read_f64(dir, &format!("charge_{kind}"))? * design_voltage? / 1e6))
+    };
+    let power = read_f64(dir, "power_now")
+        .or_else(|| Some(read_f64(dir, "current_now")? * voltage? / 1e6))
+        .map(f64::abs)
+        .unwrap_or(0.0);
+    Battery {
+        name: super::sanitize(name),
+        capacity: read(dir, "capacity").and_then(|c| c.parse().ok()),
+        status: read(dir, "status")
+            .map(|s| s.to_lowercase().replace(' ', "_"))
+            .unwrap_or_else(|| "unknown".into()),
+        system: read(dir, "scope").is_none_or(|s| s != "Device"),
+        power,
+        energy_now: energy("now"),
+        energy_full: energy("full"),
+    }
+}
+
+pub fn values(root: &Path) -> Vec<(String, VarValue)> {
+    let mut batteries = Vec::new();
+    let mut ac = None;
+    let mut entries: Vec<_> = std::fs::read_dir(root)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .collect();
+    entries.sort_by_key(|e| e.file_name());
+    for entry in entries {
+        let dir = entry.path();
+        let name = entry.file_name().to_string_lossy().into_owned();
+        match read(&dir, "type").as_deref() {
+            Some("Battery") => batteries.push(read_battery(&dir, &name)),
+            Some("Mains" | "USB") => {
+                let online = read(&dir, "online").is_some_and(|o| o == "1");
+                ac = Some(ac.unwrap_or(false) || online);
+            }
+            _ => {}
+        }
+    }
+
+    let mut out = Vec::new();
+    let key = |k: &str| format!("iwwc.battery.{k}");
+    for b in &batteries {
+        let k = |field: &str| key(&format!("{}.{field}", b.name));
+        if let Some(c) = b.capacity {
+            out.push((k("capacity"), VarValue::Int(c)));
+        }
+        out.push((k("status"), VarValue::Str(b.status.clone())));
+        out.push((k("power"), VarValue::Float(watts(b.power))));
+        out.push((k("time_to_empty"), VarValue::Int(b.time_to_empty())));
+        out.push((k("time_to_full"), VarValue::Int(b.time_to_full())));
+    }
+
+    let system: Vec<&Battery> = batteries.iter().filter(|b| b.system).collect();
+    let total = aggregate(&system);
+    out.push((key("present"), VarValue::Bool(!system.is_empty())));
+    out.push((key("capacity"), VarValue::Int(total.capacity.unwrap_or(0))));
+    out.push((key("status"), VarValue::Str(total.status.clone())));
+    out.push((key("power"), VarValue::Float(watts(total.power))));
+    out.push((key("time_to_empty"), VarValue::Int(total.time_to_empty())));
+    out.push((key("time_to_full"), VarValue::Int(total.time_to_full())));
+    // Without any battery the machine can only be running on mains.
+    out.push((key("ac"), VarValue::Bool(ac.unwrap_or(system.is_empty()))));
+    out
+}
+
+/// Folds all system batteries into one, weighting capacity by energy when
+/// every battery reports it.
+fn aggregate(batteries: &[&Battery]) -> Battery {
+    let sum = |f: fn(&Battery) -> Option<f64>| -> Option<f64> {
+        batteries.iter().map(|&b| f(b)).sum::<Option<f64>>()
+    };
+    let energy_now = sum(|b| b.energy_now);
+    let energy_full = sum(|b| b.energy_full);
+    let capacity = match (energy_now, energy_full) {
+        (Some(now), Some(full)) if full > 0.0 => Some((now / full * 100.0).round() as i128),
+        _ => {
+            let known: Vec<i128> = batteries.iter().filter_map(|b| b.capacity).collect();
+            (!known.is_empty()).then(|| known.iter().sum::<i128>() / known.len() as i128)
+        }
+    };
+    let any = |s: &str| batteries.iter().any(|b| b.status == s);
+    let status = if batteries.is_empty() {
+        "unknown"
+    } else if any("charging") {
+        "charging"
+    } else if any("discharging") {
+        "discharging"
+    } else if batteries.iter().all(|b| b.status == "full") {
+        "full"
+    } else {
+        batteries[0].status.as_str()
+    };
+    Battery {
+        name: String::new(),
+        capacity,
+        status: status.to_string(),
+        system: true,
+        power: batteries.iter().map(|b| b.power).sum(),
+        energy_now,
+        energy_full,
+    }
+}
+
+fn watts(micro: f64) -> f64 {
+    (micro / 1e4).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::smart::testutil::{get, write};
+
+    fn supply(root: &Path, name: &str, files: &[(&str, &str)]) {
+        for (f, v) in files {
+            write(root, &format!("{name}/{f}"), v);
+        }
+    }
+
+    #[test]
+    fn energy_battery_discharging() {
+        let dir = tempfile::tempdir().unwrap();
+        supply(
+            dir.path(),
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("status", "Discharging"),
+                ("capacity", "50"),
+                ("energy_now", "25000000"),
+                ("energy_full", "50000000"),
+                ("power_now", "12500000"),
+            ],
+        );
+        supply(dir.path(), "AC", &[("type", "Mains"), ("online", "0")]);
+        let vals = values(dir.path());
+        assert_eq!(
+            get(&vals, "iwwc.battery.bat0.capacity"),
+            Some(VarValue::Int(50))
+        );
+        assert_eq!(
+            get(&vals, "iwwc.battery.bat0.status"),
+            Some(VarValue::Str("discharging".into()))
+        );
+        assert_eq!(
+            get(&vals, "iwwc.battery.power"),
+            Some(VarValue::Float(12.5))
+        );
+        assert_eq!(
+            get(&vals, "iwwc.battery.time_to_empty"),
+            Some(VarValue::Int(2 * 3600))
+        );
+        assert_eq!(
+            get(&vals, "iwwc.battery.time_to_full"),
+            Some(VarValue::Int(0))
+        );
+        assert_eq!(get(&vals, "iwwc.battery.ac"), Some(VarValue::Bool(false)));
+        assert_eq!(
+            get(&vals, "iwwc.battery.present"),
+            Some(VarValue::Bool(true))
+        );
+    }
+
+    #[test]
+    fn charge_battery_converted_and_aggregated() {
+        let dir = tempfile::tempdir().unwrap();
+        supply(
+            dir.path(),
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("status", "Charging"),
+                ("capacity", "25"),
+                ("charge_now", "1000000"),
+                ("charge_full", "4000000"),
+                ("voltage_min_design", "10000000"),
+                ("voltage_now", "10000000"),
+                ("current_now", "1000000"),
+            ],
+        );
+        supply(
+            dir.path(),
+            "BAT1",
+            &[
+                ("type", "Battery"),
+                ("status", "Full"),
+                ("capacity", "100"),
+                ("energy_now", "40000000"),
+                ("energy_full", "40000000"),
+                ("power_now", "0"),
+            ],
+        );
+        supply(
+            dir.path(),
+            "hidpp_battery_0",
+            &[
+                ("type", "Battery"),
+                ("scope", "Device"),
+                ("status", "Discharging"),
+                ("capacity", "5"),
+            ],
+        );
+        supply(dir.path(), "ADP1", &[("type", "Mains"), ("online", "1")]);
+        let vals = values(dir.path());
+        // 10Wh + 40Wh of 40Wh + 40Wh
+        assert_eq!(get(&vals, "iwwc.battery.capacity"), Some(VarValue::Int(63)));
+        assert_eq!(
+            get(&vals, "iwwc.battery.status"),
+            Some(VarValue::Str("charging".into()))
+        );
+        assert_eq!(
+            get(&vals, "iwwc.battery.bat0.power"),
+            Some(VarValue::Float(10.0))
+        );
+        assert_eq!(
+            get(&vals, "iwwc.battery.bat0.time_to_full"),
+            Some(VarValue::Int(3 * 3600))
+        );
+        assert_eq!(
+            get(&vals, "iwwc.battery.hidpp_battery_0.capacity"),
+            Some(VarValue::Int(5))
+        );
+        assert_eq!(get(&vals, "iwwc.battery.ac"), Some(VarValue::Bool(true)));
+    }
+
+    #[test]
+    fn no_battery_reports_mains() {
+        let dir = tempfile::tempdir().unwrap();
+        let vals = values(dir.path());
+        assert_eq!(
+            get(&vals, "iwwc.battery.present"),
+            Some(VarValue::Bool(false))
+        );
+        assert_eq!(get(&vals, "iwwc.battery.ac"), Some(VarValue::Bool(true)));
+        assert_eq!(get(&vals, "iwwc.battery.capacity"), Some(VarValue::Int(0)));
+        assert_eq!(
+            get(&vals, "iwwc.battery.status"),
+            Some(VarValue::Str("unknown".into()))
+        );
+    }
+}
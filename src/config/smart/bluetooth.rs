@@ -0,0 +1,208 @@
+//! `iwwc.bluetooth.*`: BlueZ adapters and devices. `crate::bluetooth` reads
+//! them from BlueZ's object manager and replaces [`Bluetooth`] on every change.
+
+use crate::config::types::VarValue;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Adapter {
+    /// D-Bus path, e.g. `/org/bluez/hci0`.
+    pub path: String,
+    pub alias: String,
+    pub powered: bool,
+    pub discovering: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Device {
+    pub path: String,
+    pub address: String,
+    /// Alias, which falls back to the remote name.
+    pub name: String,
+    /// freedesktop icon name, e.g. `audio-headset`.
+    pub icon: String,
+    pub paired: bool,
+    pub connected: bool,
+    /// Percent, from `org.bluez.Battery1`.
+    pub battery: Option<u8>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Bluetooth {
+    /// Ordered by path, so `hci0` comes first.
+    pub adapters: Vec<Adapter>,
+    /// Paired or connected devices, in path order; discovered strangers
+    /// are left out.
+    pub devices: Vec<Device>,
+}
+
+impl Bluetooth {
+    fn keys(&self) -> Vec<String> {
+        let mut seen = Vec::new();
+        self.devices
+            .iter()
+            .map(|d| {
+                let name = if d.name.is_empty() {
+                    &d.address
+                } else {
+                    &d.name
+                };
+                super::temps::unique(super::sanitize(name), &mut seen)
+            })
+            .collect()
+    }
+
+    /// Device a `bluetooth=` action names: its key in
+    /// `iwwc.bluetooth.devices`, its name or its address.
+    pub fn lookup(&self, name: &str) -> Option<&Device> {
+        let keys = self.keys();
+        self.devices
+            .iter()
+            .zip(&keys)
+            .find(|(_, k)| *k == name)
+            .or_else(|| {
+                self.devices
+                    .iter()
+                    .zip(&keys)
+                    .find(|(d, _)| d.name == name || d.address.eq_ignore_ascii_case(name))
+            })
+            .map(|(d, _)| d)
+    }
+
+    pub fn values(&self) -> Vec<(String, VarValue)> {
+        let key = |k: &str| format!("iwwc.bluetooth.{k}");
+        let str = |s: &str| VarValue::Str(s.to_string());
+        let adapter = self.adapters.first();
+        let keys = self.keys();
+        let connected: Vec<&str> = self
+            .devices
+            .iter()
+            .zip(&keys)
+            .filter(|(d, _)| d.connected)
+            .map(|(_, k)| k.as_str())
+            .collect();
+        let battery = self
+            .devices
+            .iter()
+            .filter(|d| d.connected)
+            .filter_map(|d| d.battery)
+            .min();
+        let mut out = vec![
+            (key("available"), VarValue::Bool(adapter.is_some())),
+            (key("adapter"), str(adapter.map_or("", |a| &a.alias))),
+            (
+                key("powered"),
+                VarValue::Bool(adapter.is_some_and(|a| a.powered)),
+            ),
+            (
+                key("discovering"),
+                VarValue::Bool(adapter.is_some_and(|a| a.discovering)),
+            ),
+            (key("devices"), str(&keys.join(" "))),
+            (key("connected"), str(&connected.join(" "))),
+            (key("count"), VarValue::Int(connected.len() as i128)),
+            (
+                key("battery"),
+                VarValue::Int(battery.map_or(-1, i128::from)),
+            ),
+        ];
+        for (d, k) in self.devices.iter().zip(&keys) {
+            let k = |field: &str| key(&format!("device.{k}.{field}"));
+            out.push((k("name"), str(&d.name)));
+            out.push((k("address"), str(&d.address)));
+            out.push((k("icon"), str(&d.icon)));
+            out.push((k("paired"), VarValue::Bool(d.paired)));
+            out.push((k("connected"), VarValue::Bool(d.connected)));
+            out.push((
+                k("battery"),
+                VarValue::Int(d.battery.map_or(-1, i128::from)),
+            ));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get(vals: &[(String, VarValue)], key: &str) -> VarValue {
+        vals.iter()
+            .find(|(k, _)| k == key)
+            .unwrap_or_else(|| panic!("missing {key}"))
+            .1
+            .clone()
+    }
+
+    fn device(name: &str, address: &str, connected: bool, battery: Option<u8>) -> Device {
+        Device {
+            path: format!("/org/bluez/hci0/dev_{}", address.replace(':', "_")),
+            address: address.into(),
+            name: name.into(),
+            icon: "audio-headset".into(),
+            paired: true,
+            connected,
+            battery,
+        }
+    }
+
+    #[test]
+    fn connected_devices_and_lowest_battery() {
+        let bt = Bluetooth {
+            adapters: vec![Adapter {
+                path: "/org/bluez/hci0".into(),
+                alias: "laptop".into(),
+                powered: true,
+                discovering: false,
+            }],
+            devices: vec![
+                device("WH-1000XM4", "AA:BB:CC:DD:EE:01", true, Some(60)),
+                device("MX Master 3", "AA:BB:CC:DD:EE:02", true, Some(35)),
+                device("", "AA:BB:CC:DD:EE:03", false, None),
+            ],
+        };
+        let vals = bt.values();
+        assert_eq!(get(&vals, "iwwc.bluetooth.powered"), VarValue::Bool(true));
+        assert_eq!(
+            get(&vals, "iwwc.bluetooth.devices"),
+            VarValue::Str("wh_1000xm4 mx_master_3 aa_bb_cc_dd_ee_03".into())
+        );
+        assert_eq!(
+            get(&vals, "iwwc.bluetooth.connected"),
+            VarValue::Str("wh_1000xm4 mx_master_3".into())
+        );
+        assert_eq!(get(&vals, "iwwc.bluetooth.count"), VarValue::Int(2));
+        assert_eq!(get(&vals, "iwwc.bluetooth.battery"), VarValue::Int(35));
+        assert_eq!(
+            get(&vals, "iwwc.bluetooth.device.wh_1000xm4.battery"),
+            VarValue::Int(60)
+        );
+        assert_eq!(
+            get(&vals, "iwwc.bluetooth.device.aa_bb_cc_dd_ee_03.battery"),
+            VarValue::Int(-1)
+        );
+        assert_eq!(
+            bt.lookup("mx_master_3").unwrap().address,
+            "AA:BB:CC:DD:EE:02"
+        );
+        assert_eq!(
+            bt.lookup("WH-1000XM4").unwrap().address,
+            "AA:BB:CC:DD:EE:01"
+        );
+        assert_eq!(
+            bt.lookup("aa:bb:cc:dd:ee:03").unwrap().address,
+            "AA:BB:CC:DD:EE:03"
+        );
+        assert!(bt.lookup("keyboard").is_none());
+    }
+
+    #[test]
+    fn no_adapter() {
+        let vals = Bluetooth::default().values();
+        assert_eq!(
+            get(&vals, "iwwc.bluetooth.available"),
+            VarValue::Bool(false)
+        );
+        assert_eq!(get(&vals, "iwwc.bluetooth.powered"), VarValue::Bool(false));
+        assert_eq!(get(&vals, "iwwc.bluetooth.battery"), VarValue::Int(-1));
+    }
+}
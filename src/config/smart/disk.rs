@@ -0,0 +1,281 @@
+//! `iwwc.disk.*`: space of mounted filesystems and throughput from
+//! `/proc/diskstats`. Rates are deltas against the previous sample.
+
+use crate::config::types::VarValue;
+use std::collections::HashMap;
+use std::time::Instant;
+
+pub const DISKSTATS: &str = "/proc/diskstats";
+
+/// Filesystems that hold no user data; skipped unless listed in `mounts=`.
+const PSEUDO: &[&str] = &[
+    "autofs",
+    "bpf",
+    "cgroup",
+    "cgroup2",
+    "configfs",
+    "debugfs",
+    "devpts",
+    "devtmpfs",
+    "efivarfs",
+    "fusectl",
+    "hugetlbfs",
+    "mqueue",
+    "nsfs",
+    "overlay",
+    "proc",
+    "pstore",
+    "ramfs",
+    "securityfs",
+    "squashfs",
+    "sysfs",
+    "tmpfs",
+    "tracefs",
+];
+
+#[derive(Debug, Clone)]
+pub struct Mount {
+    pub point: String,
+    pub fs: String,
+    /// Kernel block device name as it appears in `/proc/diskstats`.
+    pub device: String,
+    pub total: u64,
+    pub available: u64,
+}
+
+/// Byte counters of the previous poll, per block device.
+#[derive(Default)]
+pub struct Sampler {
+    last: Option<(Instant, HashMap<String, (u64, u64)>)>,
+}
+
+pub fn mounts() -> Vec<Mount> {
+    sysinfo::Disks::new_with_refreshed_list()
+        .iter()
+        .map(|d| {
+            // `/dev/mapper/root` is a symlink to `/dev/dm-0`, which is the
+            // name diskstats uses.
+            let dev = std::path::PathBuf::from(d.name());
+            let dev = std::fs::canonicalize(&dev).unwrap_or(dev);
+            Mount {
+                point: d.mount_point().to_string_lossy().into_owned(),
+                fs: d.file_system().to_string_lossy().into_owned(),
+                device: dev
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                total: d.total_space(),
+                available: d.available_space(),
+            }
+        })
+        .collect()
+}
+
+fn keep(m: &Mount, filter: Option<&[String]>) -> bool {
+    match filter {
+        Some(points) => points.iter().any(|p| *p == m.point),
+        None => m.total > 0 && !PSEUDO.contains(&m.fs.as_str()),
+    }
+}
+
+/// `/` becomes `root`, `/home/me` becomes `home_me`. The keys sit under
+/// `iwwc.disk.mount` so a mount named `read` cannot shadow the totals.
+fn mount_key(point: &str) -> String {
+    match point.trim_matches('/') {
+        "" => "root".to_string(),
+        rest => super::sanitize(rest),
+    }
+}
+
+/// `(device, bytes read, bytes written)`; diskstats counts 512-byte sectors.
+fn parse_diskstats(text: &str) -> Vec<(String, u64, u64)> {
+    text.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let read: u64 = fields.get(5)?.parse().ok()?;
+            let written: u64 = fields.get(9)?.parse().ok()?;
+            Some((fields[2].to_string(), read * 512, written * 512))
+        })
+        .collect()
+}
+
+impl Sampler {
+    pub fn values(
+        &mut self,
+        mounts: &[Mount],
+        diskstats: &str,
+        filter: Option<&[String]>,
+        now: Instant,
+    ) -> Vec<(String, VarValue)> {
+        let counters: HashMap<String, (u64, u64)> = parse_diskstats(diskstats)
+            .into_iter()
+            .map(|(dev, r, w)| (dev, (r, w)))
+            .collect();
+        let elapsed = self
+            .last
+            .as_ref()
+            .map(|(at, _)| now.duration_since(*at).as_secs_f64());
+        let rate = |dev: &str| -> (i128, i128) {
+            let prev = self.last.as_ref().and_then(|(_, m)| m.get(dev));
+            match (counters.get(dev), prev, elapsed) {
+                (Some(&(r, w)), Some(&(pr, pw)), Some(secs)) if secs > 0.0 => (
+                    (r.saturating_sub(pr) as f64 / secs).round() as i128,
+                    (w.saturating_sub(pw) as f64 / secs).round() as i128,
+                ),
+                _ => (0, 0),
+            }
+        };
+
+        let mut out = Vec::new();
+        let mut counted: Vec<&str> = Vec::new();
+        let (mut sum_read, mut sum_write) = (0, 0);
+        for m in mounts.iter().filter(|m| keep(m, filter)) {
+            let (read, write) = rate(&m.device);
+            // Bind mounts and btrfs subvolumes share a device.
+            if !counted.contains(&m.device.as_str()) {
+                counted.push(&m.device);
+                sum_read += read;
+                sum_write += write;
+            }
+            let used = m.total.saturating_sub(m.available);
+            let percent = if m.total > 0 {
+                (used as f64 / m.total as f64 * 10000.0).round() / 100.0
+            } else {
+                0.0
+            };
+            let name = mount_key(&m.point);
+            let key = |field: &str| format!("iwwc.disk.mount.{name}.{field}");
+            out.push((key("total"), VarValue::Int(m.total as i128)));
+            out.push((key("used"), VarValue::Int(used as i128)));
+            out.push((key("available"), VarValue::Int(m.available as i128)));
+            out.push((key("percent"), VarValue::Float(percent)));
+            out.push((key("read"), VarValue::Int(read)));
+            out.push((key("write"), VarValue::Int(write)));
+        }
+        out.push(("iwwc.disk.read".to_string(), VarValue::Int(sum_read)));
+        out.push(("iwwc.disk.write".to_string(), VarValue::Int(sum_write)));
+
+        self.last = Some((now, counters));
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::smart::testutil::get;
+    use std::time::Duration;
+
+    fn mount(point: &str, fs: &str, device: &str, total: u64, available: u64) -> Mount {
+        Mount {
+            point: point.into(),
+            fs: fs.into(),
+            device: device.into(),
+            total,
+            available,
+        }
+    }
+
+    const STATS_A: &str = "\
+ 259       0 nvme0n1 100 0 2000 0 50 0 1000 0 0 0 0
+ 259       2 nvme0n1p2 90 0 1000 0 40 0 800 0 0 0 0
+ 259       3 nvme0n1p3 10 0 100 0 10 0 200 0 0 0 0
+";
+    const STATS_B: &str = "\
+ 259       0 nvme0n1 100 0 6000 0 50 0 3000 0 0 0 0
+ 259       2 nvme0n1p2 90 0 3000 0 40 0 1800 0 0 0 0
+ 259       3 nvme0n1p3 10 0 2100 0 10 0 200 0 0 0 0
+";
+
+    fn sample_mounts() -> Vec<Mount> {
+        vec![
+            mount("/", "btrfs", "nvme0n1p2", 1000, 250),
+            mount("/home", "btrfs", "nvme0n1p2", 1000, 250),
+            mount("/mnt/Data Disk", "ext4", "nvme0n1p3", 400, 400),
+            mount("/run", "tmpfs", "tmpfs", 100, 90),
+            mount("/proc", "proc", "proc", 0, 0),
+        ]
+    }
+
+    #[test]
+    fn space_per_mount_and_pseudo_skipped() {
+        let vals = Sampler::default().values(&sample_mounts(), STATS_A, None, Instant::now());
+        assert_eq!(
+            get(&vals, "iwwc.disk.mount.root.total"),
+            Some(VarValue::Int(1000))
+        );
+        assert_eq!(
+            get(&vals, "iwwc.disk.mount.root.used"),
+            Some(VarValue::Int(750))
+        );
+        assert_eq!(
+            get(&vals, "iwwc.disk.mount.root.available"),
+            Some(VarValue::Int(250))
+        );
+        assert_eq!(
+            get(&vals, "iwwc.disk.mount.root.percent"),
+            Some(VarValue::Float(75.0))
+        );
+        assert_eq!(
+            get(&vals, "iwwc.disk.mount.mnt_data_disk.percent"),
+            Some(VarValue::Float(0.0))
+        );
+        assert_eq!(get(&vals, "iwwc.disk.mount.run.total"), None);
+        assert_eq!(get(&vals, "iwwc.disk.mount.proc.total"), None);
+        assert_eq!(
+            get(&vals, "iwwc.disk.mount.root.read"),
+            Some(VarValue::Int(0))
+        );
+    }
+
+    #[test]
+    fn filter_lists_exact_mountpoints() {
+        let filter = vec!["/home".to_string(), "/run".to_string()];
+        let vals =
+            Sampler::default().values(&sample_mounts(), STATS_A, Some(&filter), Instant::now());
+        assert_eq!(get(&vals, "iwwc.disk.mount.root.total"), None);
+        assert_eq!(
+            get(&vals, "iwwc.disk.mount.home.total"),
+            Some(VarValue::Int(1000))
+        );
+        assert_eq!(
+            get(&vals, "iwwc.disk.mount.run.used"),
+            Some(VarValue::Int(10))
+        );
+    }
+
+    #[test]
+    fn throughput_from_diskstats_delta() {
+        let mut sampler = Sampler::default();
+        let t0 = Instant::now();
+        sampler.values(&sample_mounts(), STATS_A, None, t0);
+        let vals = sampler.values(&sample_mounts(), STATS_B, None, t0 + Duration::from_secs(2));
+        // 2000 sectors read, 1000 written over 2s
+        assert_eq!(
+            get(&vals, "iwwc.disk.mount.root.read"),
+            Some(VarValue::Int(512_000))
+        );
+        assert_eq!(
+            get(&vals, "iwwc.disk.mount.home.write"),
+            Some(VarValue::Int(256_000))
+        );
+        assert_eq!(
+            get(&vals, "iwwc.disk.mount.mnt_data_disk.read"),
+            Some(VarValue::Int(512_000))
+        );
+        // nvme0n1p2 counted once despite two mounts
+        assert_eq!(get(&vals, "iwwc.disk.read"), Some(VarValue::Int(1_024_000)));
+        assert_eq!(get(&vals, "iwwc.disk.write"), Some(VarValue::Int(256_000)));
+    }
+
+    #[test]
+    fn mount_keys_do_not_shadow_totals() {
+        let mounts = vec![mount("/read", "ext4", "nvme0n1p3", 400, 100)];
+        let vals = Sampler::default().values(&mounts, STATS_A, None, Instant::now());
+        assert_eq!(
+            get(&vals, "iwwc.disk.mount.read.total"),
+            Some(VarValue::Int(400))
+        );
+        assert_eq!(get(&vals, "iwwc.disk.read"), Some(VarValue::Int(0)));
+    }
+}
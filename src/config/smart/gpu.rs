@@ -0,0 +1,153 @@
+//! `iwwc.gpu.*`, read from `/sys/class/drm/card*`. Load and VRAM come from
+//! amdgpu, frequency from i915; temperatures from the card's hwmon.
+
+use super::read_trimmed as read;
+use crate::config::types::VarValue;
+use std::path::Path;
+
+pub const ROOT: &str = "/sys/class/drm";
+
+fn read_int(path: &Path) -> Option<i128> {
+    read(path)?.parse().ok()
+}
+
+fn vendor(id: &str) -> &'static str {
+    match id {
+        "0x1002" => "amd",
+        "0x8086" => "intel",
+        "0x10de" => "nvidia",
+        _ => "unknown",
+    }
+}
+
+/// `card0`, `card1`, ... but not connectors like `card0-DP-1`.
+fn cards(root: &Path) -> Vec<(u32, std::path::PathBuf)> {
+    let mut out: Vec<(u32, std::path::PathBuf)> = std::fs::read_dir(root)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().into_owned();
+            Some((name.strip_prefix("card")?.parse().ok()?, e.path()))
+        })
+        .collect();
+    out.sort_by_key(|(n, _)| *n);
+    out
+}
+
+fn temps(device: &Path) -> Vec<(String, f64)> {
+    let mut out = Vec::new();
+    let Some(hwmon) = std::fs::read_dir(device.join("hwmon"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|e| e.path())
+        .min()
+    else {
+        return out;
+    };
+    for i in 1.. {
+        let Some(c) = super::temps::read_celsius(&hwmon.join(format!("temp{i}_input"))) else {
+            break;
+        };
+        let label = read(&hwmon.join(format!("temp{i}_label")))
+            .map(|l| super::sanitize(&l))
+            .unwrap_or_else(|| format!("temp{i}"));
+        out.push((label, c));
+    }
+    out
+}
+
+pub fn values(root: &Path) -> Vec<(String, VarValue)> {
+    let mut out = Vec::new();
+    let mut index = 0;
+    for (_, card) in cards(root) {
+        let device = card.join("device");
+        let Some(id) = read(&device.join("vendor")) else {
+            continue;
+        };
+        let key = |field: &str| format!("iwwc.gpu.{index}.{field}");
+        out.push((key("vendor"), VarValue::Str(vendor(&id).into())));
+        let ints = [
+            ("load", device.join("gpu_busy_percent")),
+            ("vram.used", device.join("mem_info_vram_used")),
+            ("vram.total", device.join("mem_info_vram_total")),
+            ("freq", card.join("gt_cur_freq_mhz")),
+            ("freq_max", card.join("gt_max_freq_mhz")),
+        ];
+        for (field, path) in ints {
+            if let Some(v) = read_int(&path) {
+                out.push((key(field), VarValue::Int(v)));
+            }
+        }
+        let temps = temps(&device);
+        if let Some((_, first)) = temps.first() {
+            out.push((key("temp"), VarValue::Float(*first)));
+        }
+        for (label, c) in temps {
+            out.push((key(&format!("temps.{label}")), VarValue::Float(c)));
+        }
+        index += 1;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::smart::testutil::{get, write};
+
+    #[test]
+    fn amdgpu_load_vram_and_temps() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(root, "card1/device/vendor", "0x1002");
+        write(root, "card1/device/gpu_busy_percent", "37");
+        write(root, "card1/device/mem_info_vram_used", "1073741824");
+        write(root, "card1/device/mem_info_vram_total", "8589934592");
+        write(root, "card1/device/hwmon/hwmon4/temp1_input", "45000");
+        write(root, "card1/device/hwmon/hwmon4/temp1_label", "edge");
+        write(root, "card1/device/hwmon/hwmon4/temp2_input", "51000");
+        write(root, "card1/device/hwmon/hwmon4/temp2_label", "junction");
+        write(root, "card1-DP-1/status", "connected");
+        let vals = values(root);
+        assert_eq!(
+            get(&vals, "iwwc.gpu.0.vendor"),
+            Some(VarValue::Str("amd".into()))
+        );
+        assert_eq!(get(&vals, "iwwc.gpu.0.load"), Some(VarValue::Int(37)));
+        assert_eq!(
+            get(&vals, "iwwc.gpu.0.vram.used"),
+            Some(VarValue::Int(1 << 30))
+        );
+        assert_eq!(
+            get(&vals, "iwwc.gpu.0.vram.total"),
+            Some(VarValue::Int(8 << 30))
+        );
+        assert_eq!(get(&vals, "iwwc.gpu.0.temp"), Some(VarValue::Float(45.0)));
+        assert_eq!(
+            get(&vals, "iwwc.gpu.0.temps.junction"),
+            Some(VarValue::Float(51.0))
+        );
+        assert_eq!(get(&vals, "iwwc.gpu.1.vendor"), None);
+    }
+
+    #[test]
+    fn intel_frequency_and_gpu_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(root, "card0/device/vendor", "0x8086");
+        write(root, "card0/gt_cur_freq_mhz", "350");
+        write(root, "card0/gt_max_freq_mhz", "1300");
+        write(root, "card2/device/vendor", "0x10de");
+        let vals = values(root);
+        assert_eq!(get(&vals, "iwwc.gpu.0.freq"), Some(VarValue::Int(350)));
+        assert_eq!(get(&vals, "iwwc.gpu.0.freq_max"), Some(VarValue::Int(1300)));
+        assert_eq!(get(&vals, "iwwc.gpu.0.load"), None);
+        assert_eq!(get(&vals, "iwwc.gpu.0.temp"), None);
+        assert_eq!(
+            get(&vals, "iwwc.gpu.1.vendor"),
+            Some(VarValue::Str("nvidia".into()))
+        );
+    }
+}
@@ -0,0 +1,251 @@
+//! `iwwc.keyboard.*`: layouts of the seat's XKB keymap and the active one.
+//! `crate::keyboard` feeds [`Keyboard`] the `wl_keyboard` keymap and
+//! modifier events.
+
+use crate::config::types::VarValue;
+
+/// XKB's `Lock` real modifier.
+const CAPS_MASK: u32 = 1 << 1;
+const REAL_MODS: [&str; 8] = [
+    "Shift", "Lock", "Control", "Mod1", "Mod2", "Mod3", "Mod4", "Mod5",
+];
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Layout {
+    /// `name[GroupN]`, e.g. `English (US)`.
+    pub name: String,
+    /// Symbols file, e.g. `us`.
+    pub short: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Keymap {
+    /// In group order.
+    pub layouts: Vec<Layout>,
+    /// Real modifier `<NMLK>` is mapped to, usually `Mod2`.
+    pub num_mask: u32,
+}
+
+/// The quoted string after `key`, e.g. `"pc+us+de:2"` after `xkb_symbols`.
+fn quoted_after<'a>(text: &'a str, key: &str) -> Option<&'a str> {
+    let rest = &text[text.find(key)? + key.len()..];
+    let rest = &rest[rest.find('"')? + 1..];
+    Some(&rest[..rest.find('"')?])
+}
+
+impl Keymap {
+    /// Reads the keymap text (`XKB_KEYMAP_FORMAT_TEXT_V1`) as compositors
+    /// send it, fully resolved.
+    pub fn parse(text: &str) -> Keymap {
+        let Some(start) = text.find("xkb_symbols") else {
+            return Keymap::default();
+        };
+        let symbols = &text[start..];
+
+        // `pc+us+de:2+inet(evdev)`: group 1 is the first layout after `pc`,
+        // the others carry their group number
+        let mut shorts: Vec<String> = Vec::new();
+        for (i, part) in quoted_after(symbols, "xkb_symbols")
+            .unwrap_or_default()
+            .split('+')
+            .enumerate()
+        {
+            let (file, group) = match part.split_once(':') {
+                Some((f, g)) => (f, g.parse::<usize>().ok()),
+                None if i <= 1 && part != "pc" && shorts.is_empty() => (part, Some(1)),
+                None => continue,
+            };
+            let Some(group) = group.filter(|g| *g >= 1) else {
+                continue;
+            };
+            let file = file.split('(').next().unwrap_or(file);
+            if shorts.len() < group {
+                shorts.resize(group, String::new());
+            }
+            shorts[group - 1] = file.to_string();
+        }
+
+        let mut names: Vec<String> = Vec::new();
+        let mut rest = symbols;
+        while let Some(at) = rest.find("name[") {
+            rest = &rest[at + 5..];
+            let Some(end) = rest.find(']') else { break };
+            let group = rest[..end]
+                .trim()
+                .to_ascii_lowercase()
+                .strip_prefix("group")
+                .and_then(|g| g.parse::<usize>().ok());
+            let Some(name) = quoted_after(rest, "=") else {
+                break;
+            };
+            if let Some(group) = group.filter(|g| *g >= 1) {
+                if names.len() < group {
+                    names.resize(group, String::new());
+                }
+                names[group - 1] = name.to_string();
+            }
+        }
+
+        let count = names.len().max(shorts.len());
+        let layouts = (0..count)
+            .map(|i| {
+                let name = names.get(i).cloned().unwrap_or_default();
+                let short = match shorts.get(i) {
+                    Some(s) if !s.is_empty() => s.clone(),
+                    _ => name.chars().take(2).collect::<String>().to_lowercase(),
+                };
+                Layout { name, short }
+            })
+            .collect();
+
+        Keymap {
+            layouts,
+            num_mask: Self::num_mask(symbols),
+        }
+    }
+
+    /// `modifier_map Mod2 { <NMLK> };`
+    fn num_mask(symbols: &str) -> u32 {
+        let mut rest = symbols;
+        while let Some(at) = rest.find("modifier_map") {
+            rest = &rest[at + "modifier_map".len()..];
+            let Some(end) = rest.find('}') else { break };
+            let entry = &rest[..end];
+            if entry.contains("<NMLK>") || entry.contains("Num_Lock") {
+                let name = entry.split_whitespace().next().unwrap_or_default();
+                if let Some(i) = REAL_MODS.iter().position(|m| m.eq_ignore_ascii_case(name)) {
+                    return 1 << i;
+                }
+            }
+        }
+        1 << 4
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Keyboard {
+    pub keymap: Keymap,
+    /// Locked modifiers as sent in `wl_keyboard.modifiers`.
+    pub locked: u32,
+    pub group: u32,
+}
+
+impl Keyboard {
+    pub fn values(&self) -> Vec<(String, VarValue)> {
+        let key = |k: &str| format!("iwwc.keyboard.{k}");
+        let layout = self.keymap.layouts.get(self.group as usize);
+        let shorts: Vec<&str> = self
+            .keymap
+            .layouts
+            .iter()
+            .map(|l| l.short.as_str())
+            .collect();
+        vec![
+            (
+                key("layout"),
+                VarValue::Str(layout.map(|l| l.name.clone()).unwrap_or_default()),
+            ),
+            (
+                key("layout_short"),
+                VarValue::Str(layout.map(|l| l.short.clone()).unwrap_or_default()),
+            ),
+            (key("layouts"), VarValue::Str(shorts.join(" "))),
+            (key("group"), VarValue::Int(self.group.into())),
+            (
+                key("caps_lock"),
+                VarValue::Bool(self.locked & CAPS_MASK != 0),
+            ),
+            (
+                key("num_lock"),
+                VarValue::Bool(self.locked & self.keymap.num_mask != 0),
+            ),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEYMAP: &str = r#"xkb_keymap {
+xkb_keycodes "evdev+aliases(qwerty)" {
+	minimum = 8;
+	<NMLK> = 77;
+};
+xkb_types "complete" {
+	virtual_modifiers NumLock,Alt;
+};
+xkb_symbols "pc+us+de:2+inet(evdev)+group(alt_shift_toggle)" {
+	name[Group1]="English (US)";
+	name[Group2]="German";
+	key <AE01> { [ 1, exclam ], [ 1, exclam ] };
+	modifier_map Lock { <CAPS> };
+	modifier_map Mod2 { <NMLK> };
+};
+};
+"#;
+
+    fn get(vals: &[(String, VarValue)], key: &str) -> VarValue {
+        vals.iter()
+            .find(|(k, _)| k == key)
+            .unwrap_or_else(|| panic!("missing {key}"))
+            .1
+            .clone()
+    }
+
+    #[test]
+    fn layouts_from_keymap() {
+        let keymap = Keymap::parse(KEYMAP);
+        assert_eq!(
+            keymap.layouts,
+            [
+                Layout {
+                    name: "English (US)".into(),
+                    short: "us".into()
+                },
+                Layout {
+                    name: "German".into(),
+                    short: "de".into()
+                },
+            ]
+        );
+        assert_eq!(keymap.num_mask, 1 << 4);
+
+        let kb = Keyboard {
+            keymap,
+            locked: CAPS_MASK,
+            group: 1,
+        };
+        let vals = kb.values();
+        assert_eq!(
+            get(&vals, "iwwc.keyboard.layout"),
+            VarValue::Str("German".into())
+        );
+        assert_eq!(
+            get(&vals, "iwwc.keyboard.layout_short"),
+            VarValue::Str("de".into())
+        );
+        assert_eq!(
+            get(&vals, "iwwc.keyboard.layouts"),
+            VarValue::Str("us de".into())
+        );
+        assert_eq!(get(&vals, "iwwc.keyboard.caps_lock"), VarValue::Bool(true));
+        assert_eq!(get(&vals, "iwwc.keyboard.num_lock"), VarValue::Bool(false));
+    }
+
+    #[test]
+    fn variants_and_missing_names() {
+        let keymap =
+            Keymap::parse(r#"xkb_symbols "pc+us(intl)+ru:2" { modifier_map Mod3 { <NMLK> }; };"#);
+        let shorts: Vec<&str> = keymap.layouts.iter().map(|l| l.short.as_str()).collect();
+        assert_eq!(shorts, ["us", "ru"]);
+        assert_eq!(keymap.num_mask, 1 << 5);
+        // no keymap yet
+        let vals = Keyboard::default().values();
+        assert_eq!(
+            get(&vals, "iwwc.keyboard.layout"),
+            VarValue::Str(String::new())
+        );
+        assert_eq!(get(&vals, "iwwc.keyboard.num_lock"), VarValue::Bool(false));
+    }
+}
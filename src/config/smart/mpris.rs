@@ -0,0 +1,298 @@
+//! `iwwc.mpris.*`: state of every MPRIS player on the session bus. The
+//! D-Bus side lives in `crate::mpris` and feeds [`Players`]; values are
+//! computed from it on every read so the position keeps moving.
+
+use crate::config::types::VarValue;
+use std::time::Instant;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Player {
+    /// Bus name without `org.mpris.MediaPlayer2.`, e.g. `spotify`.
+    pub name: String,
+    /// `playing`, `paused` or `stopped`.
+    pub status: String,
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    pub art_url: String,
+    /// Local file for `art_url`: the path itself for `file://`, else the
+    /// downloaded copy. Empty without art.
+    pub art: String,
+    /// µs
+    pub length: i64,
+    /// µs, as of `position_at`
+    pub position: i64,
+    pub position_at: Instant,
+    pub rate: f64,
+    pub volume: f64,
+    pub shuffle: bool,
+    /// `none`, `track` or `playlist`.
+    pub loop_status: String,
+}
+
+impl Player {
+    pub fn new(name: &str) -> Player {
+        Player {
+            name: name.to_string(),
+            status: "stopped".into(),
+            title: String::new(),
+            artist: String::new(),
+            album: String::new(),
+            art_url: String::new(),
+            art: String::new(),
+            length: 0,
+            position: 0,
+            position_at: Instant::now(),
+            rate: 1.0,
+            volume: 0.0,
+            shuffle: false,
+            loop_status: "none".into(),
+        }
+    }
+
+    /// Players only report position on request and on seeks; in between it
+    /// is extrapolated from the playback rate.
+    fn position_at(&self, now: Instant) -> i64 {
+        let mut pos = self.position;
+        if self.status == "playing" {
+            let elapsed = now.saturating_duration_since(self.position_at);
+            pos += (elapsed.as_micros() as f64 * self.rate) as i64;
+        }
+        if self.length > 0 {
+            pos = pos.min(self.length);
+        }
+        pos.max(0)
+    }
+}
+
+/// All known players in appearance order, plus the one controls go to.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Players {
+    list: Vec<Player>,
+    active: Option<String>,
+}
+
+impl Players {
+    /// Inserts or replaces a player. One that starts playing becomes active,
+    /// like playerctld does.
+    pub fn update(&mut self, player: Player) {
+        let was_playing = self
+            .get(&player.name)
+            .is_some_and(|p| p.status == "playing");
+        if player.status == "playing" && !was_playing {
+            self.active = Some(player.name.clone());
+        }
+        match self.list.iter_mut().find(|p| p.name == player.name) {
+            Some(p) => *p = player,
+            None => self.list.push(player),
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.list.retain(|p| p.name != name);
+        if self.active.as_deref() == Some(name) {
+            self.active = None;
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Player> {
+        self.list.iter().find(|p| p.name == name)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Player> {
+        self.list.iter_mut().find(|p| p.name == name)
+    }
+
+    /// By bus name suffix or by the sanitized name used in variables.
+    pub fn lookup(&self, name: &str) -> Option<&Player> {
+        self.get(name)
+            .or_else(|| self.list.iter().find(|p| super::sanitize(&p.name) == name))
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.list.iter().map(|p| p.name.as_str())
+    }
+
+    /// The last player to start playing; without one, the first playing
+    /// player, else the first player.
+    /// Whether some player's position is moving.
+    pub fn playing(&self) -> bool {
+        self.list.iter().any(|p| p.status == "playing")
+    }
+
+    pub fn active(&self) -> Option<&Player> {
+        self.active
+            .as_deref()
+            .and_then(|n| self.get(n))
+            .or_else(|| self.list.iter().find(|p| p.status == "playing"))
+            .or_else(|| self.list.first())
+    }
+
+    pub fn values(&self, now: Instant) -> Vec<(String, VarValue)> {
+        let mut out = Vec::new();
+        let names: Vec<String> = self.list.iter().map(|p| super::sanitize(&p.name)).collect();
+        out.push((
+            "iwwc.mpris.players".to_string(),
+            VarValue::Str(names.join(" ")),
+        ));
+        let active = self.active();
+        out.push((
+            "iwwc.mpris.active".to_string(),
+            VarValue::Str(active.map(|p| super::sanitize(&p.name)).unwrap_or_default()),
+        ));
+        // The unprefixed keys exist even with no player so configs resolve.
+        let idle = Player::new("");
+        push_player(&mut out, "iwwc.mpris", active.unwrap_or(&idle), now);
+        for (p, name) in self.list.iter().zip(&names) {
+            push_player(&mut out, &format!("iwwc.mpris.{name}"), p, now);
+        }
+        out
+    }
+}
+
+/// A playback position key, the only value that moves without an event.
+pub fn is_position(key: &str) -> bool {
+    key.strip_prefix("iwwc.mpris.")
+        .is_some_and(|rest| rest == "position" || rest.ends_with(".position"))
+}
+
+fn push_player(out: &mut Vec<(String, VarValue)>, prefix: &str, p: &Player, now: Instant) {
+    let key = |field: &str| format!("{prefix}.{field}");
+    let str = |s: &str| VarValue::Str(s.to_string());
+    out.push((key("status"), str(&p.status)));
+    out.push((key("title"), str(&p.title)));
+    out.push((key("artist"), str(&p.artist)));
+    out.push((key("album"), str(&p.album)));
+    out.push((key("art_url"), str(&p.art_url)));
+    out.push((key("art"), str(&p.art)));
+    out.push((key("length"), VarValue::Int((p.length / 1_000_000) as i128)));
+    out.push((
+        key("position"),
+        VarValue::Int((p.position_at(now) / 1_000_000) as i128),
+    ));
+    out.push((key("volume"), VarValue::Float((p.volume * 100.0).round())));
+    out.push((key("shuffle"), VarValue::Bool(p.shuffle)));
+    out.push((key("loop"), str(&p.loop_status)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn player(name: &str, status: &str) -> Player {
+        Player {
+            status: status.into(),
+            title: format!("{name} song"),
+            ..Player::new(name)
+        }
+    }
+
+    fn get(vals: &[(String, VarValue)], key: &str) -> VarValue {
+        vals.iter()
+            .find(|(k, _)| k == key)
+            .unwrap_or_else(|| panic!("missing {key}"))
+            .1
+            .clone()
+    }
+
+    #[test]
+    fn last_player_to_start_is_active() {
+        let mut ps = Players::default();
+        ps.update(player("spotify", "playing"));
+        ps.update(player("firefox.instance_1_42", "paused"));
+        assert_eq!(ps.active().unwrap().name, "spotify");
+        ps.update(player("firefox.instance_1_42", "playing"));
+        assert_eq!(ps.active().unwrap().name, "firefox.instance_1_42");
+        // spotify pausing and resuming takes it back
+        ps.update(player("spotify", "paused"));
+        assert_eq!(ps.active().unwrap().name, "firefox.instance_1_42");
+        ps.update(player("spotify", "playing"));
+        assert_eq!(ps.active().unwrap().name, "spotify");
+        assert_eq!(
+            ps.lookup("firefox_instance_1_42").unwrap().name,
+            "firefox.instance_1_42"
+        );
+        ps.remove("spotify");
+        assert_eq!(ps.active().unwrap().name, "firefox.instance_1_42");
+        let vals = ps.values(Instant::now());
+        assert_eq!(
+            get(&vals, "iwwc.mpris.players"),
+            VarValue::Str("firefox_instance_1_42".into())
+        );
+        assert_eq!(
+            get(&vals, "iwwc.mpris.active"),
+            VarValue::Str("firefox_instance_1_42".into())
+        );
+    }
+
+    #[test]
+    fn values_for_active_and_each_player() {
+        let mut ps = Players::default();
+        let t0 = Instant::now();
+        ps.update(Player {
+            artist: "A, B".into(),
+            length: 200_000_000,
+            position: 10_000_000,
+            position_at: t0,
+            volume: 0.55,
+            shuffle: true,
+            loop_status: "playlist".into(),
+            ..player("mpd", "playing")
+        });
+        ps.update(player("vlc", "stopped"));
+        let vals = ps.values(t0 + Duration::from_millis(5500));
+        assert_eq!(
+            get(&vals, "iwwc.mpris.title"),
+            VarValue::Str("mpd song".into())
+        );
+        assert_eq!(
+            get(&vals, "iwwc.mpris.artist"),
+            VarValue::Str("A, B".into())
+        );
+        assert_eq!(get(&vals, "iwwc.mpris.length"), VarValue::Int(200));
+        assert_eq!(get(&vals, "iwwc.mpris.position"), VarValue::Int(15));
+        assert_eq!(get(&vals, "iwwc.mpris.volume"), VarValue::Float(55.0));
+        assert_eq!(get(&vals, "iwwc.mpris.shuffle"), VarValue::Bool(true));
+        assert_eq!(
+            get(&vals, "iwwc.mpris.loop"),
+            VarValue::Str("playlist".into())
+        );
+        assert_eq!(
+            get(&vals, "iwwc.mpris.vlc.status"),
+            VarValue::Str("stopped".into())
+        );
+        assert_eq!(
+            get(&vals, "iwwc.mpris.players"),
+            VarValue::Str("mpd vlc".into())
+        );
+    }
+
+    #[test]
+    fn position_frozen_when_paused_and_clamped() {
+        let t0 = Instant::now();
+        let mut p = Player {
+            length: 3_000_000,
+            position: 2_000_000,
+            position_at: t0,
+            ..player("x", "paused")
+        };
+        assert_eq!(p.position_at(t0 + Duration::from_secs(5)), 2_000_000);
+        p.status = "playing".into();
+        assert_eq!(p.position_at(t0 + Duration::from_secs(5)), 3_000_000);
+    }
+
+    #[test]
+    fn no_player_still_resolves() {
+        let vals = Players::default().values(Instant::now());
+        assert_eq!(
+            get(&vals, "iwwc.mpris.active"),
+            VarValue::Str(String::new())
+        );
+        assert_eq!(
+            get(&vals, "iwwc.mpris.status"),
+            VarValue::Str("stopped".into())
+        );
+        assert_eq!(get(&vals, "iwwc.mpris.title"), VarValue::Str(String::new()));
+    }
+}
@@ -0,0 +1,332 @@
+//! `iwwc.net.*`: interface state, addresses and throughput from `/proc/net`
+//! and `/sys/class/net`. Rates are deltas against the previous sample.
+
+use crate::config::types::VarValue;
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::path::Path;
+use std::time::Instant;
+
+pub const ROOT: &str = "/";
+
+/// Byte counters of the previous poll, per interface.
+#[derive(Default)]
+pub struct Sampler {
+    last: Option<(Instant, HashMap<String, (u64, u64)>)>,
+}
+
+fn read(root: &Path, rel: &str) -> String {
+    super::read_trimmed(&root.join(rel)).unwrap_or_default()
+}
+
+/// `(interface, rx bytes, tx bytes)` from `/proc/net/dev`.
+fn parse_dev(text: &str) -> Vec<(String, u64, u64)> {
+    text.lines()
+        .skip(2)
+        .filter_map(|line| {
+            let (name, rest) = line.split_once(':')?;
+            let fields: Vec<u64> = rest
+                .split_whitespace()
+                .map(|f| f.parse().ok())
+                .collect::<Option<_>>()?;
+            Some((name.trim().to_string(), *fields.first()?, *fields.get(8)?))
+        })
+        .collect()
+}
+
+/// procfs prints IPv4 words in host byte order.
+fn hex_ipv4(s: &str) -> Option<Ipv4Addr> {
+    Some(Ipv4Addr::from(
+        u32::from_str_radix(s, 16).ok()?.to_le_bytes(),
+    ))
+}
+
+/// `(interface, destination, mask)` rows of `/proc/net/route`.
+fn parse_route(text: &str) -> Vec<(String, Ipv4Addr, Ipv4Addr)> {
+    text.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let f: Vec<&str> = line.split_whitespace().collect();
+            Some((
+                f.first()?.to_string(),
+                hex_ipv4(f.get(1)?)?,
+                hex_ipv4(f.get(7)?)?,
+            ))
+        })
+        .collect()
+}
+
+fn default_route(route: &str, ipv6_route: &str) -> Option<String> {
+    let v4 = parse_route(route)
+        .into_iter()
+        .find(|(_, dest, mask)| dest.is_unspecified() && mask.is_unspecified())
+        .map(|(iface, _, _)| iface);
+    v4.or_else(|| {
+        ipv6_route.lines().find_map(|line| {
+            let f: Vec<&str> = line.split_whitespace().collect();
+            let iface = *f.last()?;
+            (f.len() == 10 && f[0].bytes().all(|b| b == b'0') && f[1] == "00" && iface != "lo")
+                .then(|| iface.to_string())
+        })
+    })
+}
+
+/// Local IPv4 addresses from `/proc/net/fib_trie`, assigned to interfaces
+/// by the connected route that contains them.
+fn ipv4_addrs(fib_trie: &str, route: &str) -> Vec<(String, Ipv4Addr)> {
+    let routes = parse_route(route);
+    let mut out: Vec<(String, Ipv4Addr)> = Vec::new();
+    let mut last: Option<Ipv4Addr> = None;
+    for line in fib_trie.lines() {
+        let t = line.trim_start();
+        if let Some(addr) = t.strip_prefix("|-- ") {
+            last = addr.parse().ok();
+        } else if t.starts_with("/32 host LOCAL")
+            && let Some(addr) = last
+        {
+            let iface = if addr.is_loopback() {
+                Some("lo".to_string())
+            } else {
+                routes
+                    .iter()
+                    .filter(|(_, _, mask)| !mask.is_unspecified())
+                    .find(|(_, dest, mask)| u32::from(addr) & u32::from(*mask) == u32::from(*dest))
+                    .map(|(iface, _, _)| iface.clone())
+            };
+            if let Some(iface) = iface
+                && !out.contains(&(iface.clone(), addr))
+            {
+                out.push((iface, addr));
+            }
+        }
+    }
+    out
+}
+
+/// `(interface, address, global scope)` from `/proc/net/if_inet6`.
+fn ipv6_addrs(text: &str) -> Vec<(String, Ipv6Addr, bool)> {
+    text.lines()
+        .filter_map(|line| {
+            let f: Vec<&str> = line.split_whitespace().collect();
+            let addr = u128::from_str_radix(f.first()?, 16).ok()?;
+            Some((
+                f.get(5)?.to_string(),
+                Ipv6Addr::from(addr),
+                *f.get(3)? == "00",
+            ))
+        })
+        .collect()
+}
+
+impl Sampler {
+    pub fn values(&mut self, root: &Path, now: Instant) -> Vec<(String, VarValue)> {
+        let counters = parse_dev(&read(root, "proc/net/dev"));
+        let route = read(root, "proc/net/route");
+        let default = default_route(&route, &read(root, "proc/net/ipv6_route"));
+        let v4 = ipv4_addrs(&read(root, "proc/net/fib_trie"), &route);
+        let v6 = ipv6_addrs(&read(root, "proc/net/if_inet6"));
+
+        let elapsed = self
+            .last
+            .as_ref()
+            .map(|(at, _)| now.duration_since(*at).as_secs_f64());
+        let rate = |iface: &str, rx: u64, tx: u64| -> (i128, i128) {
+            let prev = self.last.as_ref().and_then(|(_, m)| m.get(iface));
+            match (prev, elapsed) {
+                (Some(&(prx, ptx)), Some(secs)) if secs > 0.0 => (
+                    (rx.saturating_sub(prx) as f64 / secs).round() as i128,
+                    (tx.saturating_sub(ptx) as f64 / secs).round() as i128,
+                ),
+                _ => (0, 0),
+            }
+        };
+
+        let mut out = Vec::new();
+        let (mut sum_rx, mut sum_tx) = (0, 0);
+        for (iface, rx, tx) in &counters {
+            let (rx_rate, tx_rate) = rate(iface, *rx, *tx);
+            if iface != "lo" {
+                sum_rx += rx_rate;
+                sum_tx += tx_rate;
+            }
+            // under `iface` so an interface named `rx` cannot shadow the totals
+            let name = super::sanitize(iface);
+            let key = |field: &str| format!("iwwc.net.iface.{name}.{field}");
+            let state = super::read_trimmed(&root.join(format!("sys/class/net/{iface}/operstate")))
+                .unwrap_or_else(|| "unknown".into());
+            let ipv4 = v4
+                .iter()
+                .find(|(i, _)| i == iface)
+                .map(|(_, a)| a.to_string());
+            let ipv6 = v6
+                .iter()
+                .filter(|(i, _, _)| i == iface)
+                .max_by_key(|(_, _, global)| *global)
+                .map(|(_, a, _)| a.to_string());
+            out.push((key("state"), VarValue::Str(state)));
+            out.push((key("rx"), VarValue::Int(rx_rate)));
+            out.push((key("tx"), VarValue::Int(tx_rate)));
+            out.push((key("rx_total"), VarValue::Int(*rx as i128)));
+            out.push((key("tx_total"), VarValue::Int(*tx as i128)));
+            out.push((key("ipv4"), VarValue::Str(ipv4.unwrap_or_default())));
+            out.push((key("ipv6"), VarValue::Str(ipv6.unwrap_or_default())));
+        }
+        out.push(("iwwc.net.rx".to_string(), VarValue::Int(sum_rx)));
+        out.push(("iwwc.net.tx".to_string(), VarValue::Int(sum_tx)));
+        out.push((
+            "iwwc.net.default".to_string(),
+            VarValue::Str(default.map(|d| super::sanitize(&d)).unwrap_or_default()),
+        ));
+
+        self.last = Some((
+            now,
+            counters
+                .into_iter()
+                .map(|(iface, rx, tx)| (iface, (rx, tx)))
+                .collect(),
+        ));
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::smart::testutil::{get, write};
+    use std::time::Duration;
+
+    const DEV: &str = "Inter-|   Receive                                                |  Transmit
+ face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed
+    lo:    1000      10    0    0    0     0          0         0     1000      10    0    0    0     0       0          0
+wlp3s0: 5000000   4000    0    0    0     0          0         0   800000    900    0    0    0     0       0          0
+";
+
+    const ROUTE: &str =
+        "Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT
+wlp3s0\t00000000\t0101A8C0\t0003\t0\t0\t600\t00000000\t0\t0\t0
+wlp3s0\t0001A8C0\t00000000\t0001\t0\t0\t600\t00FFFFFF\t0\t0\t0
+";
+
+    const FIB_TRIE: &str = "Main:
+  +-- 0.0.0.0/0 3 0 5
+     |-- 0.0.0.0
+        /0 universe UNICAST
+     +-- 127.0.0.0/8 2 0 2
+        +-- 127.0.0.0/31 1 0 0
+           |-- 127.0.0.1
+              /32 host LOCAL
+     +-- 192.168.1.0/24 2 0 2
+        |-- 192.168.1.42
+           /32 host LOCAL
+        |-- 192.168.1.255
+           /32 link BROADCAST
+Local:
+  +-- 0.0.0.0/0 3 0 5
+     +-- 192.168.1.0/24 2 0 2
+        |-- 192.168.1.42
+           /32 host LOCAL
+";
+
+    const IF_INET6: &str = "00000000000000000000000000000001 01 80 10 80       lo
+fe800000000000000000000000000042 02 40 20 80   wlp3s0
+2a0100000000000000000000000000aa 02 40 00 00   wlp3s0
+";
+
+    fn fixture(dev: &str) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let files = [
+            ("proc/net/dev", dev),
+            ("proc/net/route", ROUTE),
+            ("proc/net/fib_trie", FIB_TRIE),
+            ("proc/net/if_inet6", IF_INET6),
+            ("sys/class/net/wlp3s0/operstate", "up"),
+            ("sys/class/net/lo/operstate", "unknown"),
+        ];
+        for (rel, text) in files {
+            write(dir.path(), rel, text.trim_end());
+        }
+        dir
+    }
+
+    #[test]
+    fn parses_dev_counters() {
+        assert_eq!(
+            parse_dev(DEV),
+            vec![
+                ("lo".to_string(), 1000, 1000),
+                ("wlp3s0".to_string(), 5000000, 800000)
+            ]
+        );
+    }
+
+    #[test]
+    fn default_route_prefers_ipv4() {
+        assert_eq!(default_route(ROUTE, ""), Some("wlp3s0".into()));
+        let v6 = "00000000000000000000000000000000 00 00000000000000000000000000000000 00 fe800000000000000000000000000001 00000400 00000001 00000000 00000003    wg0\n";
+        assert_eq!(default_route("", v6), Some("wg0".into()));
+        assert_eq!(default_route("", ""), None);
+    }
+
+    #[test]
+    fn addresses_mapped_to_interfaces() {
+        assert_eq!(
+            ipv4_addrs(FIB_TRIE, ROUTE),
+            vec![
+                ("lo".to_string(), Ipv4Addr::new(127, 0, 0, 1)),
+                ("wlp3s0".to_string(), Ipv4Addr::new(192, 168, 1, 42))
+            ]
+        );
+        let v6 = ipv6_addrs(IF_INET6);
+        assert_eq!(v6.len(), 3);
+        assert_eq!(v6[2].1, "2a01::aa".parse::<Ipv6Addr>().unwrap());
+        assert!(v6[2].2);
+    }
+
+    #[test]
+    fn rates_from_consecutive_samples() {
+        let t0 = Instant::now();
+        let mut sampler = Sampler::default();
+        let first = sampler.values(fixture(DEV).path(), t0);
+        assert_eq!(
+            get(&first, "iwwc.net.iface.wlp3s0.rx"),
+            Some(VarValue::Int(0))
+        );
+        assert_eq!(
+            get(&first, "iwwc.net.iface.wlp3s0.state"),
+            Some(VarValue::Str("up".into()))
+        );
+        assert_eq!(
+            get(&first, "iwwc.net.iface.wlp3s0.ipv4"),
+            Some(VarValue::Str("192.168.1.42".into()))
+        );
+        assert_eq!(
+            get(&first, "iwwc.net.iface.wlp3s0.ipv6"),
+            Some(VarValue::Str("2a01::aa".into()))
+        );
+        assert_eq!(
+            get(&first, "iwwc.net.default"),
+            Some(VarValue::Str("wlp3s0".into()))
+        );
+
+        let later = DEV
+            .replace("5000000", "5200000")
+            .replace("800000", "850000")
+            .replace("1000      10", "9000      10");
+        let second = sampler.values(fixture(&later).path(), t0 + Duration::from_secs(2));
+        assert_eq!(
+            get(&second, "iwwc.net.iface.wlp3s0.rx"),
+            Some(VarValue::Int(100000))
+        );
+        assert_eq!(
+            get(&second, "iwwc.net.iface.wlp3s0.tx"),
+            Some(VarValue::Int(25000))
+        );
+        assert_eq!(
+            get(&second, "iwwc.net.iface.lo.rx"),
+            Some(VarValue::Int(4000))
+        );
+        // loopback stays out of the totals
+        assert_eq!(get(&second, "iwwc.net.rx"), Some(VarValue::Int(100000)));
+        assert_eq!(get(&second, "iwwc.net.tx"), Some(VarValue::Int(25000)));
+    }
+}
@@ -0,0 +1,302 @@
+//! `iwwc.network.*`: NetworkManager's view of the connections. `crate::network`
+//! reads [`Network`] again whenever NetworkManager signals a change.
+
+use crate::config::types::VarValue;
+
+/// `NMState` values.
+const STATE_CONNECTED_LOCAL: u32 = 50;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ActiveConnection {
+    pub id: String,
+    /// As [`kind_name`] returns it.
+    pub kind: String,
+    /// Interface of its first device, empty for VPNs without one.
+    pub device: String,
+    pub vpn: bool,
+    /// D-Bus path of the active connection object.
+    pub path: String,
+    /// D-Bus path of the saved connection it was activated from.
+    pub settings: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SavedConnection {
+    pub id: String,
+    pub kind: String,
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AccessPoint {
+    pub ssid: String,
+    /// Percent.
+    pub strength: u8,
+    /// MHz.
+    pub frequency: u32,
+    pub secure: bool,
+    /// The one a wireless device is associated with.
+    pub active: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Network {
+    /// NetworkManager answered the last read.
+    pub running: bool,
+    /// `NMState`.
+    pub state: u32,
+    /// Path of the primary active connection, `/` without one.
+    pub primary: String,
+    pub active: Vec<ActiveConnection>,
+    pub saved: Vec<SavedConnection>,
+    /// Strongest first, one per SSID.
+    pub access_points: Vec<AccessPoint>,
+}
+
+/// Short name of a NetworkManager connection type.
+pub fn kind_name(kind: &str) -> String {
+    match kind {
+        "802-11-wireless" => "wifi",
+        "802-3-ethernet" => "ethernet",
+        other => other,
+    }
+    .to_string()
+}
+
+fn state_name(state: u32) -> &'static str {
+    match state {
+        10 => "asleep",
+        20 => "disconnected",
+        30 => "disconnecting",
+        40 => "connecting",
+        50 => "local",
+        60 => "site",
+        70 => "global",
+        _ => "unknown",
+    }
+}
+
+/// Access points seen several times (one SSID on many BSSIDs or bands)
+/// are folded into the associated one, else the strongest; hidden networks
+/// are dropped.
+pub fn merge_access_points(aps: Vec<AccessPoint>) -> Vec<AccessPoint> {
+    let mut out: Vec<AccessPoint> = Vec::new();
+    for ap in aps.into_iter().filter(|ap| !ap.ssid.is_empty()) {
+        match out.iter_mut().find(|o| o.ssid == ap.ssid) {
+            Some(o) if ap.active || (!o.active && ap.strength > o.strength) => *o = ap,
+            Some(_) => {}
+            None => out.push(ap),
+        }
+    }
+    out.sort_by(|a, b| {
+        b.strength
+            .cmp(&a.strength)
+            .then_with(|| a.ssid.cmp(&b.ssid))
+    });
+    out
+}
+
+impl Network {
+    fn primary(&self) -> Option<&ActiveConnection> {
+        self.active.iter().find(|a| a.path == self.primary)
+    }
+
+    fn keys<'a>(names: impl Iterator<Item = &'a str>) -> Vec<String> {
+        let mut seen = Vec::new();
+        names
+            .map(|n| super::temps::unique(super::sanitize(n), &mut seen))
+            .collect()
+    }
+
+    /// Saved connection a `network=` action names: its id or its key in
+    /// `iwwc.network.connections`.
+    pub fn lookup(&self, name: &str) -> Option<&SavedConnection> {
+        let keys = Self::keys(self.saved.iter().map(|s| s.id.as_str()));
+        self.saved.iter().find(|s| s.id == name).or_else(|| {
+            self.saved
+                .iter()
+                .zip(&keys)
+                .find(|(_, k)| *k == name)
+                .map(|(s, _)| s)
+        })
+    }
+
+    pub fn values(&self) -> Vec<(String, VarValue)> {
+        let key = |k: &str| format!("iwwc.network.{k}");
+        let str = |s: &str| VarValue::Str(s.to_string());
+        let primary = self.primary();
+        let wifi = self.access_points.iter().find(|ap| ap.active);
+        let vpns: Vec<&str> = self
+            .active
+            .iter()
+            .filter(|a| a.vpn)
+            .map(|a| a.id.as_str())
+            .collect();
+        let mut out = vec![
+            (key("running"), VarValue::Bool(self.running)),
+            (key("state"), str(state_name(self.state))),
+            (
+                key("connected"),
+                VarValue::Bool(self.state >= STATE_CONNECTED_LOCAL),
+            ),
+            (key("primary"), str(primary.map_or("", |p| &p.id))),
+            (key("type"), str(primary.map_or("", |p| &p.kind))),
+            (key("device"), str(primary.map_or("", |p| &p.device))),
+            (key("ssid"), str(wifi.map_or("", |ap| &ap.ssid))),
+            (
+                key("strength"),
+                VarValue::Int(wifi.map_or(0, |ap| ap.strength as i128)),
+            ),
+            (key("vpn"), VarValue::Bool(!vpns.is_empty())),
+            (key("vpn_name"), str(&vpns.join(", "))),
+        ];
+
+        let ap_keys = Self::keys(self.access_points.iter().map(|ap| ap.ssid.as_str()));
+        out.push((key("access_points"), str(&ap_keys.join(" "))));
+        for (ap, k) in self.access_points.iter().zip(&ap_keys) {
+            let k = |field: &str| key(&format!("ap.{k}.{field}"));
+            out.push((k("ssid"), str(&ap.ssid)));
+            out.push((k("strength"), VarValue::Int(ap.strength as i128)));
+            out.push((k("frequency"), VarValue::Int(ap.frequency as i128)));
+            out.push((k("secure"), VarValue::Bool(ap.secure)));
+            out.push((k("active"), VarValue::Bool(ap.active)));
+        }
+
+        let saved_keys = Self::keys(self.saved.iter().map(|s| s.id.as_str()));
+        out.push((key("connections"), str(&saved_keys.join(" "))));
+        for (s, k) in self.saved.iter().zip(&saved_keys) {
+            let k = |field: &str| key(&format!("connection.{k}.{field}"));
+            out.push((k("id"), str(&s.id)));
+            out.push((k("type"), str(&s.kind)));
+            out.push((
+                k("active"),
+                VarValue::Bool(self.active.iter().any(|a| a.settings == s.path)),
+            ));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get(vals: &[(String, VarValue)], key: &str) -> VarValue {
+        vals.iter()
+            .find(|(k, _)| k == key)
+            .unwrap_or_else(|| panic!("missing {key}"))
+            .1
+            .clone()
+    }
+
+    fn ap(ssid: &str, strength: u8, active: bool) -> AccessPoint {
+        AccessPoint {
+            ssid: ssid.into(),
+            strength,
+            frequency: 2412,
+            secure: true,
+            active,
+        }
+    }
+
+    #[test]
+    fn access_points_fold_by_ssid() {
+        let aps = merge_access_points(vec![
+            ap("home", 40, true),
+            ap("", 90, false),
+            ap("cafe", 30, false),
+            ap("home", 70, false),
+            ap("cafe", 55, false),
+        ]);
+        assert_eq!(aps, vec![ap("cafe", 55, false), ap("home", 40, true)]);
+    }
+
+    #[test]
+    fn values_for_wifi_and_vpn() {
+        let net = Network {
+            running: true,
+            state: 70,
+            primary: "/ac/1".into(),
+            active: vec![
+                ActiveConnection {
+                    id: "home".into(),
+                    kind: "wifi".into(),
+                    device: "wlan0".into(),
+                    vpn: false,
+                    path: "/ac/1".into(),
+                    settings: "/s/1".into(),
+                },
+                ActiveConnection {
+                    id: "work vpn".into(),
+                    kind: "wireguard".into(),
+                    device: "wg0".into(),
+                    vpn: true,
+                    path: "/ac/2".into(),
+                    settings: "/s/2".into(),
+                },
+            ],
+            saved: vec![
+                SavedConnection {
+                    id: "home".into(),
+                    kind: "wifi".into(),
+                    path: "/s/1".into(),
+                },
+                SavedConnection {
+                    id: "Wired connection 1".into(),
+                    kind: "ethernet".into(),
+                    path: "/s/3".into(),
+                },
+            ],
+            access_points: vec![ap("home", 70, true), ap("cafe", 55, false)],
+        };
+        let vals = net.values();
+        assert_eq!(
+            get(&vals, "iwwc.network.state"),
+            VarValue::Str("global".into())
+        );
+        assert_eq!(get(&vals, "iwwc.network.connected"), VarValue::Bool(true));
+        assert_eq!(
+            get(&vals, "iwwc.network.type"),
+            VarValue::Str("wifi".into())
+        );
+        assert_eq!(
+            get(&vals, "iwwc.network.ssid"),
+            VarValue::Str("home".into())
+        );
+        assert_eq!(get(&vals, "iwwc.network.strength"), VarValue::Int(70));
+        assert_eq!(get(&vals, "iwwc.network.vpn"), VarValue::Bool(true));
+        assert_eq!(
+            get(&vals, "iwwc.network.vpn_name"),
+            VarValue::Str("work vpn".into())
+        );
+        assert_eq!(
+            get(&vals, "iwwc.network.access_points"),
+            VarValue::Str("home cafe".into())
+        );
+        assert_eq!(
+            get(&vals, "iwwc.network.connections"),
+            VarValue::Str("home wired_connection_1".into())
+        );
+        assert_eq!(
+            get(&vals, "iwwc.network.connection.wired_connection_1.active"),
+            VarValue::Bool(false)
+        );
+        assert_eq!(
+            get(&vals, "iwwc.network.connection.home.active"),
+            VarValue::Bool(true)
+        );
+        assert_eq!(net.lookup("wired_connection_1").unwrap().path, "/s/3");
+        assert_eq!(net.lookup("Wired connection 1").unwrap().path, "/s/3");
+        assert!(net.lookup("cafe").is_none());
+        // keys exist before NetworkManager answered
+        let empty = Network::default().values();
+        assert_eq!(
+            get(&empty, "iwwc.network.state"),
+            VarValue::Str("unknown".into())
+        );
+        assert_eq!(
+            get(&empty, "iwwc.network.ssid"),
+            VarValue::Str(String::new())
+        );
+    }
+}
@@ -0,0 +1,293 @@
+//! `iwwc.temps.*`, read from `/sys/class/hwmon` and `/sys/class/thermal`.
+
+use super::read_trimmed as read;
+use crate::config::types::VarValue;
+use std::path::{Path, PathBuf};
+
+pub const ROOT: &str = "/sys/class";
+pub const CPU_ROOT: &str = "/sys/devices/system/cpu";
+
+#[derive(Debug)]
+struct Sensor {
+    /// `<chip>.<label>`, already sanitized.
+    name: String,
+    current: f64,
+    crit: Option<f64>,
+    max: Option<f64>,
+}
+
+/// sysfs reports millidegrees.
+pub(super) fn read_celsius(path: &Path) -> Option<f64> {
+    let milli: f64 = read(path)?.parse().ok()?;
+    Some((milli / 100.0).round() / 10.0)
+}
+
+fn sorted_dirs(dir: &Path, prefix: &str) -> Vec<PathBuf> {
+    let mut out: Vec<PathBuf> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|e| e.file_name().to_string_lossy().starts_with(prefix))
+        .map(|e| e.path())
+        .collect();
+    // hwmon10 after hwmon9
+    out.sort_by_key(|p| {
+        let name = p.file_name().unwrap_or_default().to_string_lossy();
+        let n: u32 = name[prefix.len()..].parse().unwrap_or(u32::MAX);
+        (n, name.into_owned())
+    });
+    out
+}
+
+/// Chips and zones that share a name get `_1`, `_2`, ... in enumeration
+/// order so every sensor keeps a distinct key.
+pub(super) fn unique(name: String, seen: &mut Vec<String>) -> String {
+    let count = seen.iter().filter(|s| **s == name).count();
+    seen.push(name.clone());
+    match count {
+        0 => name,
+        n => format!("{name}_{n}"),
+    }
+}
+
+fn hwmon(root: &Path, seen: &mut Vec<String>) -> Vec<Sensor> {
+    let mut out = Vec::new();
+    for dir in sorted_dirs(&root.join("hwmon"), "hwmon") {
+        let Some(chip) = read(&dir.join("name")) else {
+            continue;
+        };
+        let chip = unique(super::sanitize(&chip), seen);
+        let mut inputs: Vec<u32> = std::fs::read_dir(&dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|e| {
+                let f = e.file_name().to_string_lossy().into_owned();
+                f.strip_prefix("temp")?.strip_suffix("_input")?.parse().ok()
+            })
+            .collect();
+        inputs.sort_unstable();
+        for i in inputs {
+            let file = |suffix: &str| dir.join(format!("temp{i}_{suffix}"));
+            let Some(current) = read_celsius(&file("input")) else {
+                continue;
+            };
+            let label = read(&file("label"))
+                .map(|l| super::sanitize(&l))
+                .unwrap_or_else(|| format!("temp{i}"));
+            out.push(Sensor {
+                name: format!("{chip}.{label}"),
+                current,
+                crit: read_celsius(&file("crit")),
+                max: read_celsius(&file("max")),
+            });
+        }
+    }
+    out
+}
+
+fn thermal(root: &Path, seen: &mut Vec<String>) -> Vec<Sensor> {
+    let mut out = Vec::new();
+    for dir in sorted_dirs(&root.join("thermal"), "thermal_zone") {
+        let (Some(kind), Some(current)) =
+            (read(&dir.join("type")), read_celsius(&dir.join("temp")))
+        else {
+            continue;
+        };
+        let crit = (0..)
+            .map_while(|k| {
+                let kind = read(&dir.join(format!("trip_point_{k}_type")))?;
+                Some((kind, dir.join(format!("trip_point_{k}_temp"))))
+            })
+            .find(|(kind, _)| kind == "critical")
+            .and_then(|(_, temp)| read_celsius(&temp));
+        let zone = unique(format!("zone.{}", super::sanitize(&kind)), seen);
+        out.push(Sensor {
+            name: zone,
+            current,
+            crit,
+            max: None,
+        });
+    }
+    out
+}
+
+/// The sensor most bars mean by "cpu temperature": the Intel package, the
+/// AMD control temperature, or the x86 package thermal zone.
+fn cpu(sensors: &[Sensor]) -> Option<f64> {
+    [
+        "coretemp.package_id_0",
+        "k10temp.tctl",
+        "k10temp.tdie",
+        "zone.x86_pkg_temp",
+    ]
+    .iter()
+    .find_map(|name| sensors.iter().find(|s| s.name == *name))
+    .map(|s| s.current)
+}
+
+pub fn values(root: &Path) -> Vec<(String, VarValue)> {
+    let mut seen = Vec::new();
+    let mut sensors = hwmon(root, &mut seen);
+    sensors.extend(thermal(root, &mut seen));
+    let mut out = Vec::new();
+    for s in &sensors {
+        let key = |field: &str| format!("iwwc.temps.{}.{field}", s.name);
+        out.push((key("current"), VarValue::Float(s.current)));
+        if let Some(c) = s.crit {
+            out.push((key("crit"), VarValue::Float(c)));
+        }
+        if let Some(m) = s.max {
+            out.push((key("max"), VarValue::Float(m)));
+        }
+    }
+    if let Some(c) = cpu(&sensors) {
+        out.push(("iwwc.temps.cpu".to_string(), VarValue::Float(c)));
+    }
+    out
+}
+
+/// `iwwc.cpu.<n>.temp` for each logical cpu under `cpu_root`, from the
+/// coretemp sensor of its physical core. Hyperthreads share a value; AMD
+/// reports no per-core sensors, so nothing is published there. Sampled with
+/// `iwwc.temps`, not on the faster `iwwc.cpu` timer.
+pub fn per_core(root: &Path, cpu_root: &Path) -> Vec<(String, VarValue)> {
+    let sensors = hwmon(root, &mut Vec::new());
+    let mut cpus: Vec<u32> = std::fs::read_dir(cpu_root)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|e| e.file_name().to_str()?.strip_prefix("cpu")?.parse().ok())
+        .collect();
+    cpus.sort_unstable();
+    cpus.into_iter()
+        .filter_map(|i| {
+            let topology = cpu_root.join(format!("cpu{i}/topology"));
+            let core = read(&topology.join("core_id"))?;
+            // a second socket is a second coretemp chip, named `coretemp_1`
+            let chip = match read(&topology.join("physical_package_id")).as_deref() {
+                None | Some("0") => "coretemp".to_string(),
+                Some(pkg) => format!("coretemp_{pkg}"),
+            };
+            let name = format!("{chip}.core_{core}");
+            let s = sensors.iter().find(|s| s.name == name)?;
+            Some((format!("iwwc.cpu.{i}.temp"), VarValue::Float(s.current)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::smart::testutil::{get, write};
+
+    #[test]
+    fn hwmon_sensors_named_by_chip_and_label() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(root, "hwmon/hwmon2/name", "coretemp");
+        write(root, "hwmon/hwmon2/temp1_input", "52000");
+        write(root, "hwmon/hwmon2/temp1_label", "Package id 0");
+        write(root, "hwmon/hwmon2/temp1_crit", "100000");
+        write(root, "hwmon/hwmon2/temp1_max", "86000");
+        write(root, "hwmon/hwmon2/temp2_input", "48500");
+        write(root, "hwmon/hwmon2/temp2_label", "Core 0");
+        write(root, "hwmon/hwmon0/name", "nvme");
+        write(root, "hwmon/hwmon0/temp1_input", "38850");
+        write(root, "hwmon/hwmon10/name", "nvme");
+        write(root, "hwmon/hwmon10/temp1_input", "41000");
+        let vals = values(root);
+        assert_eq!(
+            get(&vals, "iwwc.temps.coretemp.package_id_0.current"),
+            Some(VarValue::Float(52.0))
+        );
+        assert_eq!(
+            get(&vals, "iwwc.temps.coretemp.package_id_0.crit"),
+            Some(VarValue::Float(100.0))
+        );
+        assert_eq!(
+            get(&vals, "iwwc.temps.coretemp.package_id_0.max"),
+            Some(VarValue::Float(86.0))
+        );
+        assert_eq!(
+            get(&vals, "iwwc.temps.coretemp.core_0.current"),
+            Some(VarValue::Float(48.5))
+        );
+        assert_eq!(get(&vals, "iwwc.temps.coretemp.core_0.crit"), None);
+        assert_eq!(
+            get(&vals, "iwwc.temps.nvme.temp1.current"),
+            Some(VarValue::Float(38.9))
+        );
+        assert_eq!(
+            get(&vals, "iwwc.temps.nvme_1.temp1.current"),
+            Some(VarValue::Float(41.0))
+        );
+        assert_eq!(get(&vals, "iwwc.temps.cpu"), Some(VarValue::Float(52.0)));
+    }
+
+    #[test]
+    fn thermal_zones_with_critical_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(root, "thermal/thermal_zone0/type", "acpitz");
+        write(root, "thermal/thermal_zone0/temp", "27800");
+        write(root, "thermal/thermal_zone0/trip_point_0_type", "passive");
+        write(root, "thermal/thermal_zone0/trip_point_0_temp", "90000");
+        write(root, "thermal/thermal_zone0/trip_point_1_type", "critical");
+        write(root, "thermal/thermal_zone0/trip_point_1_temp", "105000");
+        write(root, "thermal/thermal_zone1/type", "x86_pkg_temp");
+        write(root, "thermal/thermal_zone1/temp", "61000");
+        write(root, "thermal/thermal_zone2/type", "acpitz");
+        write(root, "thermal/thermal_zone2/temp", "30000");
+        let vals = values(root);
+        assert_eq!(
+            get(&vals, "iwwc.temps.zone.acpitz.current"),
+            Some(VarValue::Float(27.8))
+        );
+        assert_eq!(
+            get(&vals, "iwwc.temps.zone.acpitz.crit"),
+            Some(VarValue::Float(105.0))
+        );
+        assert_eq!(
+            get(&vals, "iwwc.temps.zone.acpitz_1.current"),
+            Some(VarValue::Float(30.0))
+        );
+        assert_eq!(get(&vals, "iwwc.temps.cpu"), Some(VarValue::Float(61.0)));
+    }
+
+    #[test]
+    fn per_core_follows_topology() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(root, "hwmon/hwmon3/name", "coretemp");
+        write(root, "hwmon/hwmon3/temp2_input", "45000");
+        write(root, "hwmon/hwmon3/temp2_label", "Core 0");
+        write(root, "hwmon/hwmon3/temp3_input", "47000");
+        write(root, "hwmon/hwmon3/temp3_label", "Core 4");
+        for (cpu, core) in [(0, 0), (1, 4), (2, 0), (3, 8)] {
+            write(
+                root,
+                &format!("cpu/cpu{cpu}/topology/core_id"),
+                &core.to_string(),
+            );
+            write(
+                root,
+                &format!("cpu/cpu{cpu}/topology/physical_package_id"),
+                "0",
+            );
+        }
+        // not a cpu
+        write(root, "cpu/cpufreq/boost", "1");
+        let vals = per_core(root, &root.join("cpu"));
+        assert_eq!(get(&vals, "iwwc.cpu.0.temp"), Some(VarValue::Float(45.0)));
+        assert_eq!(get(&vals, "iwwc.cpu.1.temp"), Some(VarValue::Float(47.0)));
+        assert_eq!(get(&vals, "iwwc.cpu.2.temp"), Some(VarValue::Float(45.0)));
+        assert_eq!(get(&vals, "iwwc.cpu.3.temp"), None);
+    }
+
+    #[test]
+    fn missing_tree_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(values(dir.path()).is_empty());
+    }
+}
@@ -0,0 +1,363 @@
+//! `iwwc.toplevels.*`: the compositor's open windows, from
+//! wlr-foreign-toplevel-management or, failing that, ext-foreign-toplevel-list.
+//! `crate::toplevels` turns the Wayland events into [`ToplevelEvent`]s;
+//! windows are keyed by a handle number that is never reused, outputs by
+//! their protocol id.
+
+use crate::config::types::VarValue;
+
+pub const MAXIMIZED: u32 = 1;
+pub const MINIMIZED: u32 = 2;
+pub const ACTIVATED: u32 = 4;
+pub const FULLSCREEN: u32 = 8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ToplevelEvent {
+    OutputName {
+        output: u32,
+        name: String,
+    },
+    OutputRemoved(u32),
+    Toplevel(u32),
+    Title {
+        toplevel: u32,
+        title: String,
+    },
+    AppId {
+        toplevel: u32,
+        app_id: String,
+    },
+    OutputEnter {
+        toplevel: u32,
+        output: u32,
+    },
+    OutputLeave {
+        toplevel: u32,
+        output: u32,
+    },
+    /// Bitfield of [`MAXIMIZED`], [`MINIMIZED`], [`ACTIVATED`] and
+    /// [`FULLSCREEN`].
+    State {
+        toplevel: u32,
+        state: u32,
+    },
+    /// The handle's `done`: its changes since the last one apply at once.
+    Done(u32),
+    Closed(u32),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Toplevel {
+    handle: u32,
+    app_id: String,
+    title: String,
+    state: u32,
+    outputs: Vec<u32>,
+}
+
+/// A window as the taskbar shows it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Window {
+    pub handle: u32,
+    pub app_id: String,
+    pub title: String,
+    pub state: u32,
+    /// Output names, empty under ext-foreign-toplevel-list.
+    pub outputs: Vec<String>,
+}
+
+impl Window {
+    pub fn activated(&self) -> bool {
+        self.state & ACTIVATED != 0
+    }
+
+    pub fn minimized(&self) -> bool {
+        self.state & MINIMIZED != 0
+    }
+}
+
+fn find(list: &mut [Toplevel], handle: u32) -> Option<&mut Toplevel> {
+    list.iter_mut().find(|t| t.handle == handle)
+}
+
+/// Windows as of each one's last `done`, in the order they were announced,
+/// plus the changes since.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Toplevels {
+    outputs: Vec<(u32, String)>,
+    current: Vec<Toplevel>,
+    pending: Vec<Toplevel>,
+}
+
+impl Toplevels {
+    pub fn apply(&mut self, event: ToplevelEvent) {
+        use ToplevelEvent as E;
+        let p = &mut self.pending;
+        match event {
+            E::OutputName { output, name } => {
+                self.outputs.retain(|(o, _)| *o != output);
+                self.outputs.push((output, name));
+            }
+            E::OutputRemoved(output) => {
+                self.outputs.retain(|(o, _)| *o != output);
+                for t in self.current.iter_mut().chain(self.pending.iter_mut()) {
+                    t.outputs.retain(|o| *o != output);
+                }
+            }
+            E::Toplevel(handle) => p.push(Toplevel {
+                handle,
+                ..Default::default()
+            }),
+            E::Title { toplevel, title } => {
+                if let Some(t) = find(p, toplevel) {
+                    t.title = title;
+                }
+            }
+            E::AppId { toplevel, app_id } => {
+                if let Some(t) = find(p, toplevel) {
+                    t.app_id = app_id;
+                }
+            }
+            E::OutputEnter { toplevel, output } => {
+                if let Some(t) = find(p, toplevel)
+                    && !t.outputs.contains(&output)
+                {
+                    t.outputs.push(output);
+                }
+            }
+            E::OutputLeave { toplevel, output } => {
+                if let Some(t) = find(p, toplevel) {
+                    t.outputs.retain(|o| *o != output);
+                }
+            }
+            E::State { toplevel, state } => {
+                if let Some(t) = find(p, toplevel) {
+                    t.state = state;
+                }
+            }
+            E::Done(handle) => {
+                let Some(t) = p.iter().find(|t| t.handle == handle).cloned() else {
+                    return;
+                };
+                match self.current.iter_mut().find(|c| c.handle == handle) {
+                    Some(c) => *c = t,
+                    None => self.current.push(t),
+                }
+            }
+            E::Closed(handle) => {
+                p.retain(|t| t.handle != handle);
+                self.current.retain(|t| t.handle != handle);
+            }
+        }
+    }
+
+    fn output_name(&self, output: u32) -> Option<&str> {
+        self.outputs
+            .iter()
+            .find(|(o, _)| *o == output)
+            .map(|(_, n)| n.as_str())
+    }
+
+    pub fn windows(&self) -> Vec<Window> {
+        self.current
+            .iter()
+            .map(|t| Window {
+                handle: t.handle,
+                app_id: t.app_id.clone(),
+                title: t.title.clone(),
+                state: t.state,
+                outputs: t
+                    .outputs
+                    .iter()
+                    .filter_map(|o| self.output_name(*o))
+                    .map(String::from)
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// Variable segments of the windows: the app id, with `_2`, `_3`, ...
+    /// for further windows of the same app.
+    fn keys(windows: &[Window]) -> Vec<String> {
+        let mut out: Vec<String> = Vec::new();
+        for w in windows {
+            let base = match super::sanitize(&w.app_id) {
+                s if s.is_empty() => "window".to_string(),
+                s => s,
+            };
+            let mut key = base.clone();
+            let mut n = 1;
+            while out.contains(&key) {
+                n += 1;
+                key = format!("{base}_{n}");
+            }
+            out.push(key);
+        }
+        out
+    }
+
+    pub fn values(&self) -> Vec<(String, VarValue)> {
+        let windows = self.windows();
+        let keys = Self::keys(&windows);
+        let active = windows.iter().position(Window::activated);
+        let str = |s: String| VarValue::Str(s);
+        let active_field = |f: fn(&Window) -> &String| {
+            str(active.map(|i| f(&windows[i]).clone()).unwrap_or_default())
+        };
+        let mut out = vec![
+            ("iwwc.toplevels.list".to_string(), str(keys.join(" "))),
+            (
+                "iwwc.toplevels.count".to_string(),
+                VarValue::Int(windows.len() as i128),
+            ),
+            (
+                "iwwc.toplevels.active".to_string(),
+                str(active.map(|i| keys[i].clone()).unwrap_or_default()),
+            ),
+            (
+                "iwwc.toplevels.active_title".to_string(),
+                active_field(|w| &w.title),
+            ),
+            (
+                "iwwc.toplevels.active_app_id".to_string(),
+                active_field(|w| &w.app_id),
+            ),
+        ];
+        let mut outputs: Vec<(String, Vec<&str>)> = Vec::new();
+        for (w, k) in windows.iter().zip(&keys) {
+            let key = |field: &str| format!("iwwc.toplevels.{k}.{field}");
+            let flag = |bit: u32| VarValue::Bool(w.state & bit != 0);
+            out.push((key("app_id"), str(w.app_id.clone())));
+            out.push((key("title"), str(w.title.clone())));
+            out.push((key("active"), flag(ACTIVATED)));
+            out.push((key("maximized"), flag(MAXIMIZED)));
+            out.push((key("minimized"), flag(MINIMIZED)));
+            out.push((key("fullscreen"), flag(FULLSCREEN)));
+            out.push((key("output"), str(w.outputs.join(" "))));
+            for output in &w.outputs {
+                let output = super::sanitize(output);
+                match outputs.iter_mut().find(|(o, _)| *o == output) {
+                    Some((_, list)) => list.push(k),
+                    None => outputs.push((output, vec![k])),
+                }
+            }
+        }
+        for (output, list) in outputs {
+            let key = |field: &str| format!("iwwc.toplevels.output.{output}.{field}");
+            out.push((key("list"), str(list.join(" "))));
+            out.push((key("count"), VarValue::Int(list.len() as i128)));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ToplevelEvent as E;
+    use super::*;
+
+    fn get(vals: &[(String, VarValue)], key: &str) -> Option<VarValue> {
+        vals.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone())
+    }
+
+    fn s(v: &str) -> Option<VarValue> {
+        Some(VarValue::Str(v.into()))
+    }
+
+    fn window(handle: u32, app_id: &str, title: &str, state: u32, output: u32) -> Vec<E> {
+        vec![
+            E::Toplevel(handle),
+            E::AppId {
+                toplevel: handle,
+                app_id: app_id.into(),
+            },
+            E::Title {
+                toplevel: handle,
+                title: title.into(),
+            },
+            E::State {
+                toplevel: handle,
+                state,
+            },
+            E::OutputEnter {
+                toplevel: handle,
+                output,
+            },
+            E::Done(handle),
+        ]
+    }
+
+    fn run(t: &mut Toplevels, events: Vec<E>) {
+        for e in events {
+            t.apply(e);
+        }
+    }
+
+    #[test]
+    fn windows_keyed_by_app_id() {
+        let mut t = Toplevels::default();
+        t.apply(E::OutputName {
+            output: 3,
+            name: "DP-1".into(),
+        });
+        t.apply(E::OutputName {
+            output: 4,
+            name: "eDP-1".into(),
+        });
+        run(&mut t, window(10, "foot", "~", 0, 3));
+        run(
+            &mut t,
+            window(11, "org.mozilla.firefox", "Docs", ACTIVATED, 3),
+        );
+        run(&mut t, window(12, "foot", "htop", MINIMIZED, 4));
+        let vals = t.values();
+        assert_eq!(
+            get(&vals, "iwwc.toplevels.list"),
+            s("foot org_mozilla_firefox foot_2")
+        );
+        assert_eq!(get(&vals, "iwwc.toplevels.count"), Some(VarValue::Int(3)));
+        assert_eq!(
+            get(&vals, "iwwc.toplevels.active"),
+            s("org_mozilla_firefox")
+        );
+        assert_eq!(get(&vals, "iwwc.toplevels.active_title"), s("Docs"));
+        assert_eq!(get(&vals, "iwwc.toplevels.foot_2.title"), s("htop"));
+        assert_eq!(
+            get(&vals, "iwwc.toplevels.foot_2.minimized"),
+            Some(VarValue::Bool(true))
+        );
+        assert_eq!(get(&vals, "iwwc.toplevels.foot_2.output"), s("eDP-1"));
+        assert_eq!(
+            get(&vals, "iwwc.toplevels.output.dp_1.list"),
+            s("foot org_mozilla_firefox")
+        );
+        assert_eq!(
+            get(&vals, "iwwc.toplevels.output.edp_1.count"),
+            Some(VarValue::Int(1))
+        );
+    }
+
+    #[test]
+    fn changes_wait_for_done_and_close() {
+        let mut t = Toplevels::default();
+        run(&mut t, window(10, "foot", "~", ACTIVATED, 3));
+        // a new window stays hidden until its first done
+        t.apply(E::Toplevel(11));
+        t.apply(E::Title {
+            toplevel: 10,
+            title: "vim".into(),
+        });
+        let before = t.values();
+        assert_eq!(get(&before, "iwwc.toplevels.count"), Some(VarValue::Int(1)));
+        assert_eq!(get(&before, "iwwc.toplevels.foot.title"), s("~"));
+        t.apply(E::Done(10));
+        assert_eq!(get(&t.values(), "iwwc.toplevels.foot.title"), s("vim"));
+        assert_eq!(t.windows()[0].outputs, Vec::<String>::new());
+
+        t.apply(E::Closed(10));
+        let vals = t.values();
+        assert_eq!(get(&vals, "iwwc.toplevels.list"), s(""));
+        assert_eq!(get(&vals, "iwwc.toplevels.active"), s(""));
+        assert_eq!(get(&vals, "iwwc.toplevels.active_title"), s(""));
+    }
+}
@@ -0,0 +1,238 @@
+//! `iwwc.upower.*`: UPower's power devices, including peripherals that
+//! sysfs doesn't see (Bluetooth mice, phones through KDE Connect).
+//! `crate::upower` keeps [`UPower`] current from property change signals.
+
+use crate::config::types::VarValue;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Device {
+    /// D-Bus path, e.g. `/org/freedesktop/UPower/devices/battery_BAT0`.
+    pub path: String,
+    pub model: String,
+    /// `UpDeviceKind`.
+    pub kind: u32,
+    /// `UpDeviceState`.
+    pub state: u32,
+    pub percentage: f64,
+    pub icon: String,
+    /// Seconds, `0` when unknown.
+    pub time_to_empty: i64,
+    pub time_to_full: i64,
+    /// Powers the computer, as opposed to a peripheral's own battery.
+    pub power_supply: bool,
+    pub present: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UPower {
+    /// UPower answered the last read.
+    pub running: bool,
+    pub on_battery: bool,
+    /// The composite battery UPower shows in panels.
+    pub display: Device,
+    pub devices: Vec<Device>,
+}
+
+pub fn kind_name(kind: u32) -> &'static str {
+    match kind {
+        1 => "line_power",
+        2 => "battery",
+        3 => "ups",
+        4 => "monitor",
+        5 => "mouse",
+        6 => "keyboard",
+        7 => "pda",
+        8 => "phone",
+        9 => "media_player",
+        10 => "tablet",
+        11 => "computer",
+        12 => "gaming_input",
+        13 => "pen",
+        14 => "touchpad",
+        15 => "modem",
+        16 => "network",
+        17 => "headset",
+        18 => "speakers",
+        19 => "headphones",
+        20 => "video",
+        21 => "other_audio",
+        22 => "remote_control",
+        23 => "printer",
+        24 => "scanner",
+        25 => "camera",
+        26 => "wearable",
+        27 => "toy",
+        28 => "bluetooth_generic",
+        _ => "unknown",
+    }
+}
+
+pub fn state_name(state: u32) -> &'static str {
+    match state {
+        1 => "charging",
+        2 => "discharging",
+        3 => "empty",
+        4 => "full",
+        5 => "pending_charge",
+        6 => "pending_discharge",
+        _ => "unknown",
+    }
+}
+
+impl Device {
+    /// Variable segment: the last path element, e.g. `battery_bat0` or
+    /// `mouse_hidpp_battery_0`.
+    fn key(&self) -> String {
+        super::sanitize(self.path.rsplit('/').next().unwrap_or(&self.path))
+    }
+
+    fn push(&self, out: &mut Vec<(String, VarValue)>, prefix: &str) {
+        let key = |field: &str| format!("{prefix}.{field}");
+        out.push((
+            key("percentage"),
+            VarValue::Int(self.percentage.round() as i128),
+        ));
+        out.push((key("state"), VarValue::Str(state_name(self.state).into())));
+        out.push((key("icon"), VarValue::Str(self.icon.clone())));
+        out.push((
+            key("time_to_empty"),
+            VarValue::Int(self.time_to_empty as i128),
+        ));
+        out.push((
+            key("time_to_full"),
+            VarValue::Int(self.time_to_full as i128),
+        ));
+        out.push((key("present"), VarValue::Bool(self.present)));
+    }
+}
+
+impl UPower {
+    pub fn device_mut(&mut self, path: &str) -> Option<&mut Device> {
+        if self.display.path == path {
+            return Some(&mut self.display);
+        }
+        self.devices.iter_mut().find(|d| d.path == path)
+    }
+
+    pub fn values(&self) -> Vec<(String, VarValue)> {
+        let mut out = vec![
+            (
+                "iwwc.upower.running".to_string(),
+                VarValue::Bool(self.running),
+            ),
+            (
+                "iwwc.upower.on_battery".to_string(),
+                VarValue::Bool(self.on_battery),
+            ),
+        ];
+        self.display.push(&mut out, "iwwc.upower");
+        let keys: Vec<String> = self.devices.iter().map(Device::key).collect();
+        out.push((
+            "iwwc.upower.devices".to_string(),
+            VarValue::Str(keys.join(" ")),
+        ));
+        for (d, k) in self.devices.iter().zip(&keys) {
+            let prefix = format!("iwwc.upower.device.{k}");
+            d.push(&mut out, &prefix);
+            out.push((
+                format!("{prefix}.kind"),
+                VarValue::Str(kind_name(d.kind).into()),
+            ));
+            out.push((format!("{prefix}.model"), VarValue::Str(d.model.clone())));
+            out.push((
+                format!("{prefix}.power_supply"),
+                VarValue::Bool(d.power_supply),
+            ));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get(vals: &[(String, VarValue)], key: &str) -> VarValue {
+        vals.iter()
+            .find(|(k, _)| k == key)
+            .unwrap_or_else(|| panic!("missing {key}"))
+            .1
+            .clone()
+    }
+
+    #[test]
+    fn display_device_and_peripherals() {
+        let up = UPower {
+            running: true,
+            on_battery: true,
+            display: Device {
+                path: "/org/freedesktop/UPower/devices/DisplayDevice".into(),
+                kind: 2,
+                state: 2,
+                percentage: 81.4,
+                icon: "battery-full-symbolic".into(),
+                time_to_empty: 14400,
+                present: true,
+                ..Default::default()
+            },
+            devices: vec![
+                Device {
+                    path: "/org/freedesktop/UPower/devices/battery_BAT0".into(),
+                    model: "5B10W13930".into(),
+                    kind: 2,
+                    state: 2,
+                    percentage: 81.4,
+                    power_supply: true,
+                    present: true,
+                    ..Default::default()
+                },
+                Device {
+                    path: "/org/freedesktop/UPower/devices/mouse_hidpp_battery_0".into(),
+                    model: "MX Master 3".into(),
+                    kind: 5,
+                    state: 2,
+                    percentage: 55.0,
+                    icon: "battery-good-symbolic".into(),
+                    present: true,
+                    ..Default::default()
+                },
+            ],
+        };
+        let vals = up.values();
+        assert_eq!(get(&vals, "iwwc.upower.percentage"), VarValue::Int(81));
+        assert_eq!(
+            get(&vals, "iwwc.upower.state"),
+            VarValue::Str("discharging".into())
+        );
+        assert_eq!(
+            get(&vals, "iwwc.upower.time_to_empty"),
+            VarValue::Int(14400)
+        );
+        assert_eq!(
+            get(&vals, "iwwc.upower.devices"),
+            VarValue::Str("battery_bat0 mouse_hidpp_battery_0".into())
+        );
+        assert_eq!(
+            get(&vals, "iwwc.upower.device.mouse_hidpp_battery_0.kind"),
+            VarValue::Str("mouse".into())
+        );
+        assert_eq!(
+            get(&vals, "iwwc.upower.device.mouse_hidpp_battery_0.percentage"),
+            VarValue::Int(55)
+        );
+        assert_eq!(
+            get(&vals, "iwwc.upower.device.battery_bat0.power_supply"),
+            VarValue::Bool(true)
+        );
+        // keys exist before UPower answered
+        let empty = UPower::default().values();
+        assert_eq!(
+            get(&empty, "iwwc.upower.state"),
+            VarValue::Str("unknown".into())
+        );
+        assert_eq!(
+            get(&empty, "iwwc.upower.devices"),
+            VarValue::Str(String::new())
+        );
+    }
+}
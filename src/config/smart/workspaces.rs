@@ -0,0 +1,446 @@
+//! `iwwc.workspaces.*`: workspace groups and workspaces from the
+//! compositor's ext-workspace-v1 manager. `crate::workspaces` turns the
+//! layer shell's workspace events into [`WorkspaceEvent`]s; objects are
+//! keyed by their protocol id, outputs by name.
+
+use crate::config::types::VarValue;
+
+const ACTIVE: u32 = 1;
+const URGENT: u32 = 2;
+const HIDDEN: u32 = 4;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WorkspaceEvent {
+    Group(u32),
+    GroupOutputEnter {
+        group: u32,
+        output: String,
+    },
+    GroupOutputLeave {
+        group: u32,
+        output: String,
+    },
+    GroupWorkspaceEnter {
+        group: u32,
+        workspace: u32,
+    },
+    GroupWorkspaceLeave {
+        group: u32,
+        workspace: u32,
+    },
+    GroupRemoved(u32),
+    Workspace(u32),
+    Id {
+        workspace: u32,
+        id: String,
+    },
+    Name {
+        workspace: u32,
+        name: String,
+    },
+    Coordinates {
+        workspace: u32,
+        coordinates: Vec<u32>,
+    },
+    /// Raw `state` bitfield: active 1, urgent 2, hidden 4.
+    State {
+        workspace: u32,
+        state: u32,
+    },
+    WorkspaceRemoved(u32),
+    /// The manager's `done`: everything since the last one applies at once.
+    Done,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Group {
+    handle: u32,
+    outputs: Vec<String>,
+    workspaces: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Workspace {
+    handle: u32,
+    id: String,
+    name: String,
+    coordinates: Vec<u32>,
+    state: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Layout {
+    groups: Vec<Group>,
+    workspaces: Vec<Workspace>,
+}
+
+impl Layout {
+    fn group(&mut self, handle: u32) -> Option<&mut Group> {
+        self.groups.iter_mut().find(|g| g.handle == handle)
+    }
+
+    fn workspace(&mut self, handle: u32) -> Option<&mut Workspace> {
+        self.workspaces.iter_mut().find(|w| w.handle == handle)
+    }
+}
+
+/// State as of the last `done`, plus the changes since.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Workspaces {
+    current: Layout,
+    pending: Layout,
+}
+
+impl Workspaces {
+    pub fn apply(&mut self, event: WorkspaceEvent) {
+        use WorkspaceEvent as E;
+        let p = &mut self.pending;
+        match event {
+            E::Group(handle) => p.groups.push(Group {
+                handle,
+                ..Default::default()
+            }),
+            E::GroupOutputEnter { group, output } => {
+                if let Some(g) = p.group(group)
+                    && !g.outputs.contains(&output)
+                {
+                    g.outputs.push(output);
+                }
+            }
+            E::GroupOutputLeave { group, output } => {
+                if let Some(g) = p.group(group) {
+                    g.outputs.retain(|o| *o != output);
+                }
+            }
+            E::GroupWorkspaceEnter { group, workspace } => {
+                if let Some(g) = p.group(group)
+                    && !g.workspaces.contains(&workspace)
+                {
+                    g.workspaces.push(workspace);
+                }
+            }
+            E::GroupWorkspaceLeave { group, workspace } => {
+                if let Some(g) = p.group(group) {
+                    g.workspaces.retain(|w| *w != workspace);
+                }
+            }
+            E::GroupRemoved(handle) => p.groups.retain(|g| g.handle != handle),
+            E::Workspace(handle) => p.workspaces.push(Workspace {
+                handle,
+                ..Default::default()
+            }),
+            E::Id { workspace, id } => {
+                if let Some(w) = p.workspace(workspace) {
+                    w.id = id;
+                }
+            }
+            E::Name { workspace, name } => {
+                if let Some(w) = p.workspace(workspace) {
+                    w.name = name;
+                }
+            }
+            E::Coordinates {
+                workspace,
+                coordinates,
+            } => {
+                if let Some(w) = p.workspace(workspace) {
+                    w.coordinates = coordinates;
+                }
+            }
+            E::State { workspace, state } => {
+                if let Some(w) = p.workspace(workspace) {
+                    w.state = state;
+                }
+            }
+            E::WorkspaceRemoved(handle) => {
+                p.workspaces.retain(|w| w.handle != handle);
+                for g in &mut p.groups {
+                    g.workspaces.retain(|w| *w != handle);
+                }
+            }
+            E::Done => self.current = self.pending.clone(),
+        }
+    }
+
+    /// Workspaces in display order: by coordinates, then as announced.
+    fn ordered(&self) -> Vec<&Workspace> {
+        let mut out: Vec<&Workspace> = self.current.workspaces.iter().collect();
+        out.sort_by(|a, b| a.coordinates.cmp(&b.coordinates));
+        out
+    }
+
+    /// Variable segment of a workspace: its name, else its id, else its
+    /// position.
+    fn key(w: &Workspace, index: usize) -> String {
+        match (w.name.as_str(), w.id.as_str()) {
+            ("", "") => (index + 1).to_string(),
+            ("", id) => super::sanitize(id),
+            (name, _) => super::sanitize(name),
+        }
+    }
+
+    /// Protocol id of the workspace a `workspace=` action names: a key from
+    /// `iwwc.workspaces.list`, a raw name or a compositor id.
+    pub fn lookup(&self, name: &str) -> Option<u32> {
+        let ordered = self.ordered();
+        ordered
+            .iter()
+            .enumerate()
+            .find(|(i, w)| Self::key(w, *i) == name)
+            .or_else(|| {
+                ordered
+                    .iter()
+                    .enumerate()
+                    .find(|(_, w)| w.name == name || (!w.id.is_empty() && w.id == name))
+            })
+            .map(|(_, w)| w.handle)
+    }
+
+    pub fn values(&self) -> Vec<(String, VarValue)> {
+        let ordered = self.ordered();
+        let keys: Vec<String> = ordered
+            .iter()
+            .enumerate()
+            .map(|(i, w)| Self::key(w, i))
+            .collect();
+        let group_of = |w: &Workspace| {
+            self.current
+                .groups
+                .iter()
+                .position(|g| g.workspaces.contains(&w.handle))
+        };
+        let outputs_of = |g: Option<usize>| -> Vec<String> {
+            g.map(|g| self.current.groups[g].outputs.clone())
+                .unwrap_or_default()
+        };
+        let with_state = |bit: u32| -> String {
+            ordered
+                .iter()
+                .zip(&keys)
+                .filter(|(w, _)| w.state & bit != 0)
+                .map(|(_, k)| k.as_str())
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
+        let str = |s: String| VarValue::Str(s);
+        let mut out = vec![
+            ("iwwc.workspaces.list".to_string(), str(keys.join(" "))),
+            (
+                "iwwc.workspaces.active".to_string(),
+                str(with_state(ACTIVE)),
+            ),
+            (
+                "iwwc.workspaces.urgent".to_string(),
+                str(with_state(URGENT)),
+            ),
+        ];
+        for (w, k) in ordered.iter().zip(&keys) {
+            let key = |field: &str| format!("iwwc.workspaces.{k}.{field}");
+            let group = group_of(w);
+            let coordinate =
+                |i: usize| VarValue::Int(w.coordinates.get(i).copied().unwrap_or(0).into());
+            let coordinates: Vec<String> = w.coordinates.iter().map(u32::to_string).collect();
+            out.push((key("name"), str(w.name.clone())));
+            out.push((key("id"), str(w.id.clone())));
+            out.push((key("coordinates"), str(coordinates.join(" "))));
+            out.push((key("x"), coordinate(0)));
+            out.push((key("y"), coordinate(1)));
+            out.push((key("active"), VarValue::Bool(w.state & ACTIVE != 0)));
+            out.push((key("urgent"), VarValue::Bool(w.state & URGENT != 0)));
+            out.push((key("hidden"), VarValue::Bool(w.state & HIDDEN != 0)));
+            out.push((key("output"), str(outputs_of(group).join(" "))));
+            out.push((
+                key("group"),
+                VarValue::Int(group.map(|g| g as i128).unwrap_or(-1)),
+            ));
+        }
+        for (i, g) in self.current.groups.iter().enumerate() {
+            let key = |field: &str| format!("iwwc.workspaces.group.{i}.{field}");
+            let members: Vec<(&Workspace, &String)> = ordered
+                .iter()
+                .copied()
+                .zip(&keys)
+                .filter(|(w, _)| g.workspaces.contains(&w.handle))
+                .collect();
+            let join = |only_active: bool| {
+                members
+                    .iter()
+                    .filter(|(w, _)| !only_active || w.state & ACTIVE != 0)
+                    .map(|(_, k)| k.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            };
+            out.push((key("output"), str(outputs_of(Some(i)).join(" "))));
+            out.push((key("list"), str(join(false))));
+            out.push((key("active"), str(join(true))));
+            for output in outputs_of(Some(i)) {
+                let key = |field: &str| {
+                    format!(
+                        "iwwc.workspaces.output.{}.{field}",
+                        super::sanitize(&output)
+                    )
+                };
+                out.push((key("list"), str(join(false))));
+                out.push((key("active"), str(join(true))));
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WorkspaceEvent as E;
+    use super::*;
+
+    fn get(vals: &[(String, VarValue)], key: &str) -> Option<VarValue> {
+        vals.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone())
+    }
+
+    fn s(v: &str) -> Option<VarValue> {
+        Some(VarValue::Str(v.into()))
+    }
+
+    /// What a compositor sends on bind: one group on DP-1 with three
+    /// workspaces announced out of order, the second one active.
+    fn initial() -> Vec<E> {
+        let mut events = vec![
+            E::Group(10),
+            E::GroupOutputEnter {
+                group: 10,
+                output: "DP-1".into(),
+            },
+        ];
+        for (handle, name, x) in [(20, "3", 2), (21, "1", 0), (22, "2", 1)] {
+            events.push(E::Workspace(handle));
+            events.push(E::Id {
+                workspace: handle,
+                id: format!("ws-{name}"),
+            });
+            events.push(E::Name {
+                workspace: handle,
+                name: name.into(),
+            });
+            events.push(E::Coordinates {
+                workspace: handle,
+                coordinates: vec![x, 0],
+            });
+            events.push(E::GroupWorkspaceEnter {
+                group: 10,
+                workspace: handle,
+            });
+        }
+        events.push(E::State {
+            workspace: 22,
+            state: ACTIVE,
+        });
+        events.push(E::Done);
+        events
+    }
+
+    fn run(ws: &mut Workspaces, events: Vec<E>) {
+        for e in events {
+            ws.apply(e);
+        }
+    }
+
+    #[test]
+    fn initial_state_in_coordinate_order() {
+        let mut ws = Workspaces::default();
+        run(&mut ws, initial());
+        let vals = ws.values();
+        assert_eq!(get(&vals, "iwwc.workspaces.list"), s("1 2 3"));
+        assert_eq!(get(&vals, "iwwc.workspaces.active"), s("2"));
+        assert_eq!(get(&vals, "iwwc.workspaces.urgent"), s(""));
+        assert_eq!(
+            get(&vals, "iwwc.workspaces.2.active"),
+            Some(VarValue::Bool(true))
+        );
+        assert_eq!(get(&vals, "iwwc.workspaces.3.x"), Some(VarValue::Int(2)));
+        assert_eq!(get(&vals, "iwwc.workspaces.3.id"), s("ws-3"));
+        assert_eq!(get(&vals, "iwwc.workspaces.1.output"), s("DP-1"));
+        assert_eq!(
+            get(&vals, "iwwc.workspaces.1.group"),
+            Some(VarValue::Int(0))
+        );
+        assert_eq!(get(&vals, "iwwc.workspaces.group.0.active"), s("2"));
+        assert_eq!(get(&vals, "iwwc.workspaces.output.dp_1.list"), s("1 2 3"));
+        assert_eq!(ws.lookup("3"), Some(20));
+        assert_eq!(ws.lookup("ws-1"), Some(21));
+        assert_eq!(ws.lookup("9"), None);
+    }
+
+    #[test]
+    fn changes_wait_for_done() {
+        let mut ws = Workspaces::default();
+        run(&mut ws, initial());
+        let before = ws.values();
+        run(
+            &mut ws,
+            vec![
+                E::State {
+                    workspace: 22,
+                    state: 0,
+                },
+                E::State {
+                    workspace: 20,
+                    state: ACTIVE | URGENT,
+                },
+                E::WorkspaceRemoved(21),
+            ],
+        );
+        assert_eq!(ws.values(), before);
+        ws.apply(E::Done);
+        let vals = ws.values();
+        assert_eq!(get(&vals, "iwwc.workspaces.list"), s("2 3"));
+        assert_eq!(get(&vals, "iwwc.workspaces.active"), s("3"));
+        assert_eq!(get(&vals, "iwwc.workspaces.urgent"), s("3"));
+        assert_eq!(get(&vals, "iwwc.workspaces.1.name"), None);
+    }
+
+    #[test]
+    fn unnamed_workspaces_and_outputs_leaving() {
+        let mut ws = Workspaces::default();
+        run(
+            &mut ws,
+            vec![
+                E::Group(1),
+                E::GroupOutputEnter {
+                    group: 1,
+                    output: "eDP-1".into(),
+                },
+                E::Workspace(2),
+                E::State {
+                    workspace: 2,
+                    state: ACTIVE | HIDDEN,
+                },
+                E::GroupWorkspaceEnter {
+                    group: 1,
+                    workspace: 2,
+                },
+                E::Workspace(5),
+                E::Done,
+            ],
+        );
+        let vals = ws.values();
+        assert_eq!(get(&vals, "iwwc.workspaces.list"), s("1 2"));
+        assert_eq!(
+            get(&vals, "iwwc.workspaces.1.hidden"),
+            Some(VarValue::Bool(true))
+        );
+        assert_eq!(
+            get(&vals, "iwwc.workspaces.2.group"),
+            Some(VarValue::Int(-1))
+        );
+        assert_eq!(get(&vals, "iwwc.workspaces.output.edp_1.active"), s("1"));
+        ws.apply(E::GroupOutputLeave {
+            group: 1,
+            output: "eDP-1".into(),
+        });
+        ws.apply(E::Done);
+        let vals = ws.values();
+        assert_eq!(get(&vals, "iwwc.workspaces.1.output"), s(""));
+        assert_eq!(get(&vals, "iwwc.workspaces.output.edp_1.active"), None);
+    }
+}
@@ -84,6 +84,14 @@ impl Store {
         &self.config.pulls
     }
 
+    pub fn dbus(&self) -> &indexmap::IndexMap<String, crate::config::types::DbusDecl> {
+        &self.config.dbus
+    }
+
+    pub fn smart(&self) -> &indexmap::IndexMap<String, crate::config::types::SmartDecl> {
+        &self.config.smart
+    }
+
     pub fn var_value(&self, name: &str) -> Option<&VarValue> {
         self.config.vars.get(name).map(|d| &d.value)
     }
@@ -180,9 +188,37 @@ impl Store {
             Some(VarValue::Bool(b)) if raw_value == "toggle" => VarValue::Bool(!b),
             _ => parse_value(raw_value),
         };
+        self.commit(name, parsed)
+    }
+
+    /// Like [`Store::update`] but stores `value` verbatim as a string, for
+    /// text typed into an `input` where "5" or "true" must stay text. The
+    /// resolver only lets an `input` bind a string var, and a var changed to
+    /// another type while an input binds it fails to resolve.
+    ///
+    /// Runs once per keystroke and, like every update, clones the config and
+    /// resolves all of it again: cheap for a bar, but it grows with the size
+    /// of the config rather than with the widgets showing the var.
+    pub fn update_str(&mut self, name: &str, value: &str) -> Result<(), UpdateError> {
+        if !self.config.vars.contains_key(name) {
+            return Err(UpdateError::UnknownVariable(name.to_string()));
+        }
+        self.commit(name, VarValue::Str(value.to_string()))
+    }
+
+    /// Like [`Store::update`] with an already typed value, e.g. one read
+    /// from D-Bus.
+    pub fn set(&mut self, name: &str, value: VarValue) -> Result<(), UpdateError> {
+        if !self.config.vars.contains_key(name) {
+            return Err(UpdateError::UnknownVariable(name.to_string()));
+        }
+        self.commit(name, value)
+    }
+
+    fn commit(&mut self, name: &str, value: VarValue) -> Result<(), UpdateError> {
         let mut candidate = self.config.clone();
         if let Some(decl) = candidate.vars.get_mut(name) {
-            decl.value = parsed;
+            decl.value = value;
         }
         let (resolved, msgs) = resolve(&candidate);
         match resolved {
@@ -365,6 +401,35 @@ mod tests {
         assert_eq!(store.resolved().widgets.get("bar").unwrap().h, Some(50.0));
     }
 
+    #[test]
+    fn update_str_keeps_text() {
+        let mut store =
+            store_from("var q=\"\"\nwidget bar { child i1 }\ninput i1 { var q }").unwrap();
+        store.update_str("q", "42").unwrap();
+        assert!(matches!(store.var_value("q"), Some(VarValue::Str(s)) if s == "42"));
+        match store
+            .resolved()
+            .widgets
+            .get("bar")
+            .unwrap()
+            .child
+            .as_deref()
+        {
+            Some(ResolvedElement::Input(i)) => assert_eq!(i.value, "42"),
+            other => panic!("expected input, got {other:?}"),
+        }
+        assert!(matches!(
+            store.update_str("nope", "x"),
+            Err(UpdateError::UnknownVariable(_))
+        ));
+        // the input's var cannot become a number behind its back
+        assert!(matches!(
+            store.update("q", "5"),
+            Err(UpdateError::Invalid(_))
+        ));
+        assert!(matches!(store.var_value("q"), Some(VarValue::Str(s)) if s == "42"));
+    }
+
     #[test]
     fn update_error_display() {
         let mut store = store_from("widget bar { child t1 }\ntext t1").unwrap();
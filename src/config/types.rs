@@ -42,7 +42,7 @@ pub enum FieldValue<T> {
     Expr(String),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum VarValue {
     Int(i128),
     Float(f64),
@@ -61,6 +61,8 @@ pub struct ParsedConfig {
     pub rows: IndexMap<String, Row>,
     pub columns: IndexMap<String, Column>,
     pub texts: IndexMap<String, TextEl>,
+    pub inputs: IndexMap<String, Input>,
+    pub images: IndexMap<String, Image>,
     pub styles: IndexMap<String, Style>,
     pub borders: IndexMap<String, Border>,
     pub shadows: IndexMap<String, Shadow>,
@@ -69,7 +71,11 @@ pub struct ParsedConfig {
     pub apptray: Option<ApptraySettings>,
     pub apptraymenu: Option<ApptrayMenuSettings>,
     pub apptraymenu_advanced: Option<ApptrayMenuAdvancedSettings>,
+    pub taskbar: Option<TaskbarSettings>,
     pub pulls: IndexMap<String, PullDecl>,
+    pub dbus: IndexMap<String, DbusDecl>,
+    pub smart: IndexMap<String, SmartDecl>,
+    pub keybinds: IndexMap<String, Keybind>,
     pub icon_theme: Option<String>,
 }
 
@@ -81,6 +87,39 @@ pub struct PullDecl {
     pub span: Span,
 }
 
+/// A variable following one D-Bus property, from `dbus <name> ...`.
+#[derive(Debug, Clone)]
+pub struct DbusDecl {
+    pub bus: crate::config::primitives::DbusBus,
+    pub service: String,
+    pub path: String,
+    pub interface: String,
+    pub property: String,
+    /// Value before the first read and while the service is gone.
+    pub default: VarValue,
+    pub span: Span,
+}
+
+/// Settings of one `iwwc.*` namespace, from `smart <namespace> ...`.
+#[derive(Debug, Clone)]
+pub struct SmartDecl {
+    pub interval: Option<std::time::Duration>,
+    pub mounts: Option<Vec<String>>,
+    /// `iwwc.idle`: inactivity before the seat counts as idle.
+    pub timeout: Option<std::time::Duration>,
+    /// `iwwc.idle`: stop pulls and smart polling while idle.
+    pub pause: bool,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct Keybind {
+    pub key: Option<FieldValue<crate::config::primitives::KeyChord>>,
+    pub widget: Option<String>,
+    pub action: Option<ActionDecl>,
+    pub span: Span,
+}
+
 #[derive(Debug, Clone)]
 pub struct VarDecl {
     pub value: VarValue,
@@ -124,18 +163,34 @@ pub struct Revealer {
 #[derive(Debug, Clone)]
 pub struct Event {
     pub evtype: crate::config::primitives::EventType,
-    pub action: Option<FieldValue<String>>,
+    pub action: Option<ActionDecl>,
     pub var: Option<String>,
     pub duration: Option<FieldValue<std::time::Duration>>,
     pub child: Option<FieldValue<String>>,
     pub span: Span,
 }
 #[derive(Debug, Clone)]
+pub struct ActionDecl {
+    pub command: Option<FieldValue<String>>,
+    pub internal: Vec<(String, FieldValue<String>)>,
+    pub stdout: Option<String>,
+    pub status: Option<String>,
+    pub on_error: Option<FieldValue<String>>,
+    pub span: Span,
+}
+#[derive(Debug, Clone)]
+pub struct BoundAction {
+    pub trigger: crate::config::primitives::PointerTrigger,
+    pub modifiers: iced::keyboard::Modifiers,
+    pub action: ActionDecl,
+}
+#[derive(Debug, Clone)]
 pub struct Button {
     pub w: Option<FieldValue<iced::Length>>,
     pub h: Option<FieldValue<iced::Length>>,
     pub padding: Option<FieldValue<Padding>>,
-    pub action: Option<FieldValue<String>>,
+    pub action: Option<ActionDecl>,
+    pub bindings: Vec<BoundAction>,
     pub clip: Option<FieldValue<bool>>,
     pub style: Option<FieldValue<String>>,
     pub style_hover: Option<FieldValue<String>>,
@@ -145,6 +200,31 @@ pub struct Button {
     pub span: Span,
 }
 #[derive(Debug, Clone)]
+pub struct Input {
+    pub var: Option<String>,
+    pub placeholder: Option<FieldValue<String>>,
+    pub password: Option<FieldValue<bool>>,
+    pub w: Option<FieldValue<iced::Length>>,
+    pub padding: Option<FieldValue<Padding>>,
+    pub size: Option<FieldValue<f32>>,
+    pub font: Option<FieldValue<String>>,
+    pub style: Option<FieldValue<String>>,
+    pub style_hover: Option<FieldValue<String>>,
+    pub style_focus: Option<FieldValue<String>>,
+    pub on_submit: Option<ActionDecl>,
+    pub on_change: Option<ActionDecl>,
+    pub span: Span,
+}
+#[derive(Debug, Clone)]
+pub struct Image {
+    pub src: Option<FieldValue<String>>,
+    pub fallback: Option<FieldValue<String>>,
+    pub w: Option<FieldValue<iced::Length>>,
+    pub h: Option<FieldValue<iced::Length>>,
+    pub fit: Option<FieldValue<iced::ContentFit>>,
+    pub span: Span,
+}
+#[derive(Debug, Clone)]
 pub struct Row {
     pub children: Option<FieldValue<Vec<String>>>,
     pub w: Option<FieldValue<iced::Length>>,
@@ -213,6 +293,19 @@ pub struct ApptraySettings {
     pub span: Span,
 }
 
+#[derive(Debug, Clone)]
+pub struct TaskbarSettings {
+    pub icon_size: Option<FieldValue<f32>>,
+    pub spacing: Option<FieldValue<f32>>,
+    pub padding: Option<FieldValue<Padding>>,
+    pub bg: Option<FieldValue<Color>>,
+    pub border: Option<FieldValue<String>>,
+    pub active_bg: Option<FieldValue<Color>>,
+    pub vertical: Option<FieldValue<bool>>,
+    pub own_output: Option<FieldValue<bool>>,
+    pub span: Span,
+}
+
 #[derive(Debug, Clone)]
 pub struct ApptrayMenuSettings {
     pub font_size: Option<FieldValue<f32>>,
@@ -1,29 +1,155 @@
-pub fn run_action(action: &str) {
-    match std::process::Command::new("sh")
+use std::process::Stdio;
+
+use iced::keyboard::Modifiers;
+
+use crate::config::primitives::PointerTrigger;
+use crate::config::resolved::{ResolvedAction, ResolvedBinding};
+
+pub const DOUBLE_CLICK: std::time::Duration = std::time::Duration::from_millis(400);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActionOutcome {
+    pub stdout: String,
+    pub status: i32,
+}
+
+impl ActionOutcome {
+    pub fn failed(&self) -> bool {
+        self.status != 0
+    }
+}
+
+pub fn select_binding(
+    bindings: &[ResolvedBinding],
+    trigger: PointerTrigger,
+    modifiers: Modifiers,
+) -> Option<&ResolvedAction> {
+    let find = |m: Modifiers| {
+        bindings
+            .iter()
+            .find(|b| b.trigger == trigger && b.modifiers == m)
+    };
+    find(modifiers)
+        .or_else(|| find(Modifiers::empty()))
+        .map(|b| &b.action)
+}
+
+pub async fn run(action: String, capture: bool, env: Vec<(&'static str, String)>) -> ActionOutcome {
+    let child = tokio::process::Command::new("sh")
         .arg("-c")
-        .arg(action)
+        .arg(&action)
         .env(
             "IWWC",
             std::env::current_exe().unwrap_or_else(|_| "iwwc".into()),
         )
-        .spawn()
-    {
-        Ok(_child) => {}
-        Err(e) => log::warn!("failed to run action `{action}`: {e}"),
+        .envs(env)
+        .stdout(if capture {
+            Stdio::piped()
+        } else {
+            Stdio::inherit()
+        })
+        .spawn();
+    let out = match child {
+        Ok(child) => child.wait_with_output().await,
+        Err(e) => Err(e),
+    };
+    match out {
+        Ok(out) => ActionOutcome {
+            stdout: String::from_utf8_lossy(&out.stdout).trim().to_string(),
+            status: exit_code(out.status),
+        },
+        Err(e) => {
+            log::warn!("failed to run action `{action}`: {e}");
+            ActionOutcome {
+                stdout: String::new(),
+                status: 127,
+            }
+        }
     }
 }
 
+fn exit_code(status: std::process::ExitStatus) -> i32 {
+    use std::os::unix::process::ExitStatusExt;
+    status
+        .code()
+        .or_else(|| status.signal().map(|s| 128 + s))
+        .unwrap_or(-1)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn run_true_succeeds() {
-        run_action("true");
+    #[tokio::test]
+    async fn run_true_succeeds() {
+        let out = run("true".into(), false, Vec::new()).await;
+        assert!(!out.failed());
+    }
+
+    #[tokio::test]
+    async fn run_garbage_does_not_panic() {
+        let out = run(
+            "this_command_definitely_does_not_exist_12345 2>/dev/null".into(),
+            false,
+            Vec::new(),
+        )
+        .await;
+        assert_eq!(out.status, 127);
+    }
+
+    #[tokio::test]
+    async fn captures_trimmed_stdout_and_status() {
+        let out = run("echo connected; exit 3".into(), true, Vec::new()).await;
+        assert_eq!(
+            out,
+            ActionOutcome {
+                stdout: "connected".into(),
+                status: 3,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn signal_maps_to_shell_convention() {
+        let out = run("kill -TERM $$".into(), true, Vec::new()).await;
+        assert_eq!(out.status, 128 + 15);
+    }
+
+    #[tokio::test]
+    async fn env_is_passed_to_command() {
+        let out = run(
+            "echo $IWWC_SCROLL_DELTA".into(),
+            true,
+            vec![("IWWC_SCROLL_DELTA", "-1".into())],
+        )
+        .await;
+        assert_eq!(out.stdout, "-1");
+    }
+
+    fn binding(trigger: PointerTrigger, modifiers: Modifiers, cmd: &str) -> ResolvedBinding {
+        ResolvedBinding {
+            trigger,
+            modifiers,
+            action: ResolvedAction::shell(cmd),
+        }
     }
 
     #[test]
-    fn run_garbage_does_not_panic() {
-        run_action("this_command_definitely_does_not_exist_12345");
+    fn select_prefers_exact_modifiers_then_plain() {
+        let bindings = vec![
+            binding(PointerTrigger::Left, Modifiers::empty(), "plain"),
+            binding(PointerTrigger::Left, Modifiers::SHIFT, "shift"),
+            binding(PointerTrigger::ScrollUp, Modifiers::CTRL, "ctrl-up"),
+        ];
+        let cmd = |t, m| select_binding(&bindings, t, m).and_then(|a| a.command.as_deref());
+        assert_eq!(cmd(PointerTrigger::Left, Modifiers::SHIFT), Some("shift"));
+        assert_eq!(cmd(PointerTrigger::Left, Modifiers::ALT), Some("plain"));
+        assert_eq!(cmd(PointerTrigger::Left, Modifiers::empty()), Some("plain"));
+        assert_eq!(
+            cmd(PointerTrigger::ScrollUp, Modifiers::CTRL),
+            Some("ctrl-up")
+        );
+        assert_eq!(cmd(PointerTrigger::ScrollUp, Modifiers::empty()), None);
+        assert_eq!(cmd(PointerTrigger::Middle, Modifiers::empty()), None);
     }
 }
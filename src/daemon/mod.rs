@@ -16,11 +16,14 @@ use iced_layershell::to_layer_message;
 use indexmap::IndexMap;
 use tokio::sync::oneshot;
 
+use crate::config::primitives::InternalAction;
+use crate::config::resolved::{ResolvedAction, ResolvedInput};
 use crate::config::store::Store;
 use crate::ipc::{Command, Response};
 use crate::notification::types::{Notification, PreCalc};
 use crate::render;
 use crate::render::UiMessage;
+use crate::toplevels::ToplevelCommand;
 
 #[to_layer_message(multi)]
 #[derive(Debug, Clone)]
@@ -31,7 +34,8 @@ pub enum Message {
         reply: Arc<Mutex<Option<oneshot::Sender<Response>>>>,
     },
     WindowClosed(WindowId),
-    OutputChanged(iced_wayland_subscriber::shell::ShellEvent),
+    /// Output and workspace changes from the layer shell.
+    Shell(iced_wayland_subscriber::shell::ShellEvent),
     Notify(crate::notification::types::Notification),
     NotifClose(u32),
     NotifTimeout {
@@ -42,6 +46,8 @@ pub enum Message {
         id: String,
         generation: u64,
     },
+    /// No second click came for the pending click of this generation.
+    ClickTimeout(u64),
     TrayItems(Vec<crate::tray::types::TrayItem>),
     MenuOpen {
         bus_name: String,
@@ -70,11 +76,34 @@ pub enum Message {
     NotifHoverEnter(WindowId),
     NotifHoverLeave(WindowId),
     PullTick(String),
+    /// A `dbus` variable's property changed; `None` resets it to its default.
+    DbusValue {
+        name: String,
+        value: Option<crate::config::types::VarValue>,
+    },
     PullResult {
         name: String,
         value: String,
     },
-    SmartRefresh,
+    ActionDone {
+        action: ResolvedAction,
+        outcome: action::ActionOutcome,
+    },
+    /// Resample these `iwwc.*` namespaces and re-resolve.
+    SmartRefresh(Vec<String>),
+    /// Taskbar icons looked up off the UI thread.
+    TaskbarIcons(render::TaskbarIcons),
+    /// logind's `PrepareForSleep`: `true` going to sleep, `false` on resume.
+    PrepareForSleep(bool),
+    /// ext-idle-notify: the seat went idle (`true`) or is back.
+    Idle(bool),
+    ModifiersChanged(iced::keyboard::Modifiers),
+    KeyPressed {
+        window: WindowId,
+        key: iced::keyboard::Key,
+        modifiers: iced::keyboard::Modifiers,
+        captured: bool,
+    },
     Noop,
 }
 
@@ -87,6 +116,9 @@ pub struct App {
     notifications: IndexMap<u32, NotifState>,
     notif_windows: HashMap<WindowId, u32>,
     tray_items: Vec<crate::tray::types::TrayItem>,
+    /// Copy of `iwwc.toplevels` for taskbars, taken on each refresh.
+    toplevels: Vec<crate::config::smart::toplevels::Window>,
+    taskbar_icons: render::TaskbarIcons,
     menus: Vec<crate::daemon::menu::MenuLevel>,
     menu_root_anchor: Option<crate::daemon::menu::MenuAnchor>,
     menu_windows: HashMap<WindowId, usize>,
@@ -97,6 +129,12 @@ pub struct App {
     watch_timers: HashMap<String, u64>,
     watch_gen: u64,
     dnd: u8,
+    modifiers: iced::keyboard::Modifiers,
+    last_click: Option<PendingClick>,
+    click_gen: u64,
+    focus: HashMap<WindowId, render::FocusId>,
+    /// Between `PrepareForSleep(true)` and the resume; pulls are paused.
+    asleep: bool,
 }
 
 struct NotifState {
@@ -108,6 +146,16 @@ struct NotifState {
     timer_gen: u64,
 }
 
+/// A press on a button with a double binding, held back for one
+/// [`action::DOUBLE_CLICK`] in case the second click follows.
+struct PendingClick {
+    source: Option<render::ClickSource>,
+    bindings: Vec<crate::config::resolved::ResolvedBinding>,
+    modifiers: iced::keyboard::Modifiers,
+    at: std::time::Instant,
+    generation: u64,
+}
+
 pub fn run(store: Store, config_path: std::path::PathBuf) -> iced_layershell::Result {
     let (shell_broadcast, shell_events) = iced_wayland_subscriber::shell::channel();
     iced_layershell::daemon(
@@ -166,6 +214,8 @@ impl App {
             notifications: IndexMap::new(),
             notif_windows: HashMap::new(),
             tray_items: Vec::new(),
+            toplevels: Vec::new(),
+            taskbar_icons: render::TaskbarIcons::new(),
             menus: Vec::new(),
             menu_root_anchor: None,
             menu_windows: HashMap::new(),
@@ -175,6 +225,11 @@ impl App {
             menu_refetch_pending: HashMap::new(),
             watch_timers: HashMap::new(),
             watch_gen: 0,
+            modifiers: iced::keyboard::Modifiers::empty(),
+            last_click: None,
+            click_gen: 0,
+            focus: HashMap::new(),
+            asleep: false,
         }
     }
 
@@ -198,50 +253,180 @@ impl App {
                 self.store.resolved().apptray.icon_size as u16,
             ),
             iced::window::close_events().map(Message::WindowClosed),
-            self.shell_events.listen().map(Message::OutputChanged),
+            self.shell_events.listen().map(Message::Shell),
             iced::window::open_events().map(Message::SurfaceOpened),
             iced::keyboard::listen().map(|ev| match ev {
                 iced::keyboard::Event::KeyPressed {
                     key: iced::keyboard::Key::Named(iced::keyboard::key::Named::Escape),
                     ..
                 } => Message::Ui(UiMessage::MenuDismiss),
+                iced::keyboard::Event::ModifiersChanged(m) => Message::ModifiersChanged(m),
                 _ => Message::Noop,
             }),
             iced::event::listen_with(pointer_event),
+            iced::event::listen_with(key_event),
         ];
-        for (name, decl) in self.store.pulls() {
+        let paused = self.idle_paused();
+        for (name, decl) in self.store.pulls().iter().filter(|_| !paused) {
             subs.push(
                 iced::time::every(decl.interval)
                     .with(name.clone())
                     .map(|(name, _instant)| Message::PullTick(name)),
             );
         }
+        for (name, decl) in self.store.dbus() {
+            subs.push(crate::dbus::subscription(name, decl));
+        }
         let polls = &self.store.resolved().smart_polls;
-        if polls.iter().any(|(ns, _)| ns == "iwwc.activesong") {
+        if polls
+            .iter()
+            .any(|(ns, _)| ns == "iwwc.activesong" || ns == "iwwc.mpris")
+        {
             subs.push(crate::mpris::subscription());
         }
-        let mut intervals: Vec<std::time::Duration> =
-            polls.iter().filter_map(|(_, d)| *d).collect();
-        intervals.sort();
-        intervals.dedup();
-        for d in intervals {
-            subs.push(iced::time::every(d).map(|_| Message::SmartRefresh));
+        if polls.iter().any(|(ns, _)| ns == "iwwc.audio") {
+            subs.push(crate::audio::subscription());
+        }
+        if polls.iter().any(|(ns, _)| ns == "iwwc.idle") || self.pause_when_idle() {
+            subs.push(crate::idle::subscription(
+                crate::config::smart::idle_timeout(self.store.smart()),
+            ));
+        }
+        if polls.iter().any(|(ns, _)| ns == "iwwc.toplevels") {
+            subs.push(crate::toplevels::subscription());
+        }
+        if polls.iter().any(|(ns, _)| ns == "iwwc.backlight") {
+            subs.push(crate::backlight::subscription());
+        }
+        if polls.iter().any(|(ns, _)| ns == "iwwc.network") {
+            subs.push(crate::network::subscription());
+        }
+        if polls.iter().any(|(ns, _)| ns == "iwwc.bluetooth") {
+            subs.push(crate::bluetooth::subscription());
+        }
+        if polls.iter().any(|(ns, _)| ns == "iwwc.upower") {
+            subs.push(crate::upower::subscription());
+        }
+        if !self.store.pulls().is_empty() || !polls.is_empty() {
+            subs.push(crate::logind::subscription());
+        }
+        // one timer per interval, refreshing only the namespaces on it
+        let mut timers: std::collections::BTreeMap<std::time::Duration, Vec<String>> =
+            Default::default();
+        let playing = crate::config::smart::mpris_playing();
+        for (ns, d) in polls {
+            // a paused or stopped position does not move
+            if ns == "iwwc.mpris" && !playing {
+                continue;
+            }
+            if let Some(d) = d {
+                timers.entry(*d).or_default().push(ns.clone());
+            }
+        }
+        for (d, mut namespaces) in timers.into_iter().filter(|_| !paused) {
+            namespaces.sort();
+            subs.push(
+                iced::time::every(d)
+                    .with(namespaces)
+                    .map(|(namespaces, _instant)| Message::SmartRefresh(namespaces)),
+            );
         }
         Subscription::batch(subs)
     }
 
     fn update(&mut self, message: Message) -> Task<Message> {
         match message {
-            Message::Ui(UiMessage::Action(cmd)) => {
-                action::run_action(&cmd);
+            Message::Ui(UiMessage::Action(a)) => self.run_action(a, Vec::new()),
+            Message::Ui(UiMessage::Pointer {
+                bindings,
+                mut trigger,
+                delta,
+                source,
+            }) => {
+                use crate::config::primitives::PointerTrigger;
+                if trigger == PointerTrigger::Left
+                    && bindings.iter().any(|b| b.trigger == PointerTrigger::Double)
+                {
+                    let now = std::time::Instant::now();
+                    match self.last_click.take() {
+                        Some(prev)
+                            if prev.source == source && now - prev.at <= action::DOUBLE_CLICK =>
+                        {
+                            trigger = PointerTrigger::Double;
+                        }
+                        // the single click waits until it can't be a double one
+                        prev => {
+                            // a click elsewhere ends the previous button's wait
+                            let flushed = prev.map_or_else(Task::none, |p| self.single_click(p));
+                            self.click_gen += 1;
+                            let generation = self.click_gen;
+                            self.last_click = Some(PendingClick {
+                                source,
+                                bindings,
+                                modifiers: self.modifiers,
+                                at: now,
+                                generation,
+                            });
+                            return Task::batch([
+                                flushed,
+                                Task::perform(
+                                    tokio::time::sleep(action::DOUBLE_CLICK),
+                                    move |_| Message::ClickTimeout(generation),
+                                ),
+                            ]);
+                        }
+                    }
+                }
+                let Some(a) = action::select_binding(&bindings, trigger, self.modifiers) else {
+                    return Task::none();
+                };
+                let env = match trigger {
+                    PointerTrigger::ScrollUp | PointerTrigger::ScrollDown => {
+                        vec![("IWWC_SCROLL_DELTA", delta.to_string())]
+                    }
+                    _ => Vec::new(),
+                };
+                self.run_action(a.clone(), env)
+            }
+            Message::Ui(UiMessage::InputChanged { id, var, value }) => {
+                if let Err(e) = self.store.update_str(&var, &value) {
+                    log::debug!("input {id} update of {var} rejected: {e}");
+                }
+                match self.find_input(&id).and_then(|i| i.on_change) {
+                    Some(a) => self.run_action(a, vec![("IWWC_INPUT", value)]),
+                    None => Task::none(),
+                }
+            }
+            Message::Ui(UiMessage::InputSubmit { id }) => match self.find_input(&id) {
+                Some(ResolvedInput {
+                    value,
+                    on_submit: Some(a),
+                    ..
+                }) => self.run_action(a, vec![("IWWC_INPUT", value)]),
+                _ => Task::none(),
+            },
+            Message::ModifiersChanged(m) => {
+                self.modifiers = m;
                 Task::none()
             }
+            Message::KeyPressed {
+                window,
+                key,
+                modifiers,
+                captured,
+            } => self.on_key(window, key, modifiers, captured),
             Message::Ui(UiMessage::NotifAction { id, key }) => {
                 let emit = emit_action_task(id, key);
                 let close = self.close_notification(id, 2);
                 Task::batch([emit, close])
             }
-            Message::OutputChanged(event) => {
+            Message::Shell(event) => {
+                if crate::workspaces::apply(&event) {
+                    return Task::done(Message::SmartRefresh(vec!["iwwc.workspaces".into()]));
+                }
+                if crate::keyboard::apply(&event) {
+                    return Task::done(Message::SmartRefresh(vec!["iwwc.keyboard".into()]));
+                }
                 if let iced_wayland_subscriber::shell::ShellEvent::WindowOutputChanged {
                     window,
                     output,
@@ -258,6 +443,7 @@ impl App {
                 self.windows.remove(&id);
                 self.window_outputs.remove(&id);
                 self.cursor.remove(&id);
+                self.focus.remove(&id);
                 if let Some(nid) = self.notif_windows.remove(&id) {
                     self.notifications.shift_remove(&nid);
                     return self.restack();
@@ -282,6 +468,12 @@ impl App {
                     Task::none()
                 }
             }
+            Message::ClickTimeout(generation) => {
+                match self.last_click.take_if(|c| c.generation == generation) {
+                    Some(click) => self.single_click(click),
+                    None => Task::none(),
+                }
+            }
             Message::WatchTimeout { id, generation } => {
                 if self.watch_timers.get(&id) != Some(&generation) {
                     return Task::none();
@@ -297,9 +489,9 @@ impl App {
                 else {
                     return Task::none();
                 };
-                action::run_action(&w.action);
+                let run = self.run_action(w.action, Vec::new());
                 let (_res, task) = self.apply_var_update(&w.var, "#false");
-                task
+                Task::batch([run, task])
             }
             Message::TrayItems(items) => {
                 self.tray_items = items;
@@ -325,6 +517,15 @@ impl App {
             Message::Ui(UiMessage::TraySecondary(idx)) => {
                 self.tray_call(idx, TrayMethod::Secondary)
             }
+            Message::Ui(UiMessage::TaskbarActivate(handle)) => {
+                taskbar_control(handle, ToplevelCommand::Activate)
+            }
+            Message::Ui(UiMessage::TaskbarMinimize(handle)) => {
+                taskbar_control(handle, ToplevelCommand::Minimize)
+            }
+            Message::Ui(UiMessage::TaskbarClose(handle)) => {
+                taskbar_control(handle, ToplevelCommand::Close)
+            }
             Message::Ui(UiMessage::TrayContextMenu { window, idx }) => {
                 let target = self
                     .tray_items
@@ -364,6 +565,7 @@ impl App {
                 }
             }
             Message::Ui(UiMessage::MenuDismiss) => self.close_menus(),
+            Message::Ui(UiMessage::Ignored) => Task::none(),
             Message::Ui(UiMessage::MenuClick { level, id }) => {
                 let addr = self
                     .menus
@@ -431,10 +633,14 @@ impl App {
                 self.cursor.insert(window, (x, y));
                 Task::none()
             }
-            Message::SurfaceOpened(window) => match self.widget_transparent(window) {
-                Some(transparent) => region_task(window, transparent),
-                None => Task::none(),
-            },
+            Message::SurfaceOpened(window) => {
+                let region = match self.widget_transparent(window) {
+                    Some(transparent) => region_task(window, transparent),
+                    None => Task::none(),
+                };
+                Task::batch([region, self.autofocus(window)])
+            }
+            Message::PullTick(_) if self.asleep => Task::none(),
             Message::PullTick(name) => match self.store.pulls().get(&name) {
                 Some(decl) => {
                     run_pull_task(name.clone(), decl.command.clone(), decl.default.clone())
@@ -448,10 +654,82 @@ impl App {
                 }
                 task
             }
-            Message::SmartRefresh => {
+            Message::DbusValue { name, value } => {
+                let Some(decl) = self.store.dbus().get(&name) else {
+                    return Task::none();
+                };
+                let value = value.unwrap_or_else(|| decl.default.clone());
+                let (res, task) = self.apply_var_with(&name, |store| store.set(&name, value));
+                if let Err(e) = res {
+                    log::debug!("dbus {name} update rejected: {e}");
+                }
+                task
+            }
+            Message::ActionDone { action, outcome } => {
+                let mut tasks = Vec::new();
+                let captures = [
+                    (&action.stdout, outcome.stdout.clone()),
+                    (&action.status, outcome.status.to_string()),
+                ];
+                for (var, value) in captures {
+                    let Some(var) = var else { continue };
+                    let (res, task) = self.apply_var_update(var, &value);
+                    if let Err(e) = res {
+                        log::debug!("action capture into {var} rejected: {e}");
+                    }
+                    tasks.push(task);
+                }
+                if outcome.failed()
+                    && let Some(on_error) = action.on_error
+                {
+                    tasks.push(shell_action_task(
+                        ResolvedAction::shell(on_error),
+                        Vec::new(),
+                    ));
+                }
+                Task::batch(tasks)
+            }
+            Message::SmartRefresh(namespaces) => {
+                let before = self.watched_smart_bools();
+                crate::config::smart::refresh(&namespaces, self.store.smart());
                 self.store.refresh();
+                let mut icons = Task::none();
+                if namespaces
+                    .iter()
+                    .any(|ns| ns == "iwwc" || ns == "iwwc.toplevels")
+                {
+                    self.toplevels = crate::config::smart::toplevels();
+                    icons = self.lookup_taskbar_icons();
+                }
+                let after = self.watched_smart_bools();
+                let mut tasks: Vec<Task<Message>> = after
+                    .into_iter()
+                    .filter(|entry| !before.contains(entry))
+                    .map(|(name, now)| self.on_var_flip(&name, now))
+                    .collect();
+                tasks.push(icons);
+                Task::batch(tasks)
+            }
+            Message::TaskbarIcons(found) => {
+                self.taskbar_icons.extend(found);
                 Task::none()
             }
+            Message::PrepareForSleep(start) => {
+                let resumed = self.asleep && !start;
+                self.asleep = start;
+                if !resumed {
+                    return Task::none();
+                }
+                // everything polled went stale while the machine slept
+                self.resync()
+            }
+            Message::Idle(idle) => {
+                let refresh = Task::done(Message::SmartRefresh(vec!["iwwc.idle".into()]));
+                if idle || !self.pause_when_idle() {
+                    return refresh;
+                }
+                Task::batch([refresh, self.resync()])
+            }
             Message::Ipc { command, reply } => {
                 let (response, task) = self.dispatch_command(command);
                 if let Some(tx) = reply.lock().unwrap().take() {
@@ -576,7 +854,8 @@ impl App {
             Command::Reload => match self.store.reload(&self.config_path) {
                 Ok(warns) => {
                     self.watch_timers.clear();
-                    let task = self.reapply();
+                    // taskbars may have changed size or theme
+                    let task = Task::batch([self.reapply(), self.lookup_taskbar_icons()]);
                     let resp = if warns.is_empty() {
                         Response::Ok
                     } else {
@@ -589,12 +868,200 @@ impl App {
         }
     }
 
+    /// Runs a held-back click's plain action.
+    fn single_click(&mut self, click: PendingClick) -> Task<Message> {
+        let trigger = crate::config::primitives::PointerTrigger::Left;
+        match action::select_binding(&click.bindings, trigger, click.modifiers) {
+            Some(a) => self.run_action(a.clone(), Vec::new()),
+            None => Task::none(),
+        }
+    }
+
+    fn run_action(&mut self, a: ResolvedAction, env: Vec<(&'static str, String)>) -> Task<Message> {
+        let mut tasks: Vec<Task<Message>> = a
+            .internal
+            .iter()
+            .cloned()
+            .map(|ia| self.run_internal(ia))
+            .collect();
+        if a.command.is_some() {
+            tasks.push(shell_action_task(a, env));
+        }
+        Task::batch(tasks)
+    }
+
+    fn run_internal(&mut self, ia: InternalAction) -> Task<Message> {
+        let command = match ia {
+            InternalAction::Update { name, value } => Command::Update { name, value },
+            InternalAction::Open(window) => Command::Open { window },
+            InternalAction::Close(window) => Command::Close { window },
+            InternalAction::Toggle(window) => Command::Toggle { window },
+            InternalAction::Dnd(v) => Command::Update {
+                name: "dnd".to_string(),
+                value: v.to_string(),
+            },
+            InternalAction::Refresh(name) if crate::config::smart::is_namespace(&name) => {
+                return Task::done(Message::SmartRefresh(vec![name]));
+            }
+            InternalAction::Refresh(name) => return Task::done(Message::PullTick(name)),
+            InternalAction::Mpris(player, command) => {
+                return Task::perform(crate::mpris::control(player, command), |res| {
+                    if let Err(e) = res {
+                        log::warn!("mpris action failed: {e}");
+                    }
+                    Message::Noop
+                });
+            }
+            InternalAction::Audio(source, command) => {
+                return Task::perform(crate::audio::control(source, command), |changed| {
+                    if changed {
+                        Message::SmartRefresh(vec!["iwwc.audio".into()])
+                    } else {
+                        Message::Noop
+                    }
+                });
+            }
+            InternalAction::Workspace(name) => {
+                if !crate::workspaces::activate(&self.shell_events, &name) {
+                    log::warn!("workspace action: no workspace \"{name}\"");
+                }
+                return Task::none();
+            }
+            InternalAction::Network(command) => {
+                return Task::perform(crate::network::control(command), |res| {
+                    if let Err(e) = res {
+                        log::warn!("network action failed: {e}");
+                    }
+                    Message::Noop
+                });
+            }
+            InternalAction::Bluetooth(command) => {
+                return Task::perform(crate::bluetooth::control(command), |res| {
+                    if let Err(e) = res {
+                        log::warn!("bluetooth action failed: {e}");
+                    }
+                    Message::Noop
+                });
+            }
+            InternalAction::Power(command) => {
+                return Task::perform(crate::logind::power(command), |res| {
+                    if let Err(e) = res {
+                        log::warn!("power action failed: {e}");
+                    }
+                    Message::Noop
+                });
+            }
+            InternalAction::DbusCall(call) => {
+                return Task::perform(crate::dbus::call(call), |res| {
+                    if let Err(e) = res {
+                        log::warn!("dbus-call action failed: {e}");
+                    }
+                    Message::Noop
+                });
+            }
+            InternalAction::Inhibit(on) => {
+                return Task::perform(crate::logind::inhibit(on), |res| match res {
+                    Ok(true) => Message::SmartRefresh(vec!["iwwc.inhibited".into()]),
+                    Ok(false) => Message::Noop,
+                    Err(e) => {
+                        log::warn!("inhibit action failed: {e}");
+                        Message::Noop
+                    }
+                });
+            }
+            InternalAction::Brightness(device, change) => {
+                return Task::perform(crate::backlight::set(device, change), |changed| {
+                    if changed {
+                        Message::SmartRefresh(vec!["iwwc.backlight".into()])
+                    } else {
+                        Message::Noop
+                    }
+                });
+            }
+            InternalAction::Dismiss(Some(id)) => return self.close_notification(id, 2),
+            InternalAction::Dismiss(None) => {
+                let ids: Vec<u32> = self.notifications.keys().copied().collect();
+                return Task::batch(ids.into_iter().map(|id| self.close_notification(id, 2)));
+            }
+        };
+        let (response, task) = self.dispatch_command(command);
+        if let Response::Error(e) = response {
+            log::warn!("internal action failed: {e}");
+        }
+        task
+    }
+
+    fn on_key(
+        &mut self,
+        window: WindowId,
+        key: iced::keyboard::Key,
+        modifiers: iced::keyboard::Modifiers,
+        captured: bool,
+    ) -> Task<Message> {
+        use iced::keyboard::{Key, key::Named};
+        let Some(name) = self.windows.get(&window) else {
+            return Task::none();
+        };
+        let resolved = self.store.resolved();
+        let bound = resolved
+            .keybinds
+            .iter()
+            .find(|k| {
+                k.widget.as_ref().is_none_or(|w| w == name)
+                    && k.chord.matches(&key, modifiers)
+                    && (!captured || k.chord.fires_over_input())
+            })
+            .map(|k| k.action.clone());
+        if let Some(a) = bound {
+            return self.run_action(a, Vec::new());
+        }
+        if captured {
+            return Task::none();
+        }
+        let Some(widget) = resolved.widgets.get(name) else {
+            return Task::none();
+        };
+        let step: isize = match key {
+            Key::Named(Named::Tab) if modifiers.shift() => -1,
+            Key::Named(Named::Tab | Named::ArrowRight | Named::ArrowDown) => 1,
+            Key::Named(Named::ArrowLeft | Named::ArrowUp) => -1,
+            Key::Named(Named::Enter | Named::Space) => {
+                let plain = self
+                    .focus
+                    .get(&window)
+                    .and_then(|id| render::focused(widget, id))
+                    .and_then(render::press_action);
+                return match plain {
+                    Some(a) => self.run_action(a, Vec::new()),
+                    None => Task::none(),
+                };
+            }
+            _ => return Task::none(),
+        };
+        if let Some(next) = render::step_focus(widget, self.focus.get(&window), step) {
+            self.focus.insert(window, next);
+        }
+        Task::none()
+    }
+
     fn get_value(&self, name: &str) -> Response {
         if name == "dnd" {
             return Response::Note(self.dnd.to_string());
         }
         if name == "iwwc" || name.starts_with("iwwc.") {
-            let values = crate::config::smart::values();
+            // namespaces the config does not poll would otherwise stay at
+            // their first sample
+            let ns = crate::config::smart::namespace_of(name).unwrap_or(name);
+            let polled = self
+                .store
+                .resolved()
+                .smart_polls
+                .iter()
+                .any(|(p, _)| p == ns);
+            if !polled {
+                crate::config::smart::refresh(&[ns.to_string()], self.store.smart());
+            }
+            let values = crate::config::smart::values(self.store.smart());
             if let Some((_, v)) = values.iter().find(|(k, _)| k == name) {
                 if crate::config::smart::is_unset(name, v)
                     && let Some(d) = self.store.var_value(name)
@@ -728,13 +1195,68 @@ impl App {
         &mut self,
         name: &str,
         value: &str,
+    ) -> (Result<(), crate::config::store::UpdateError>, Task<Message>) {
+        self.apply_var_with(name, |store| store.update(name, value))
+    }
+
+    /// Runs every pull and resamples every smart namespace, after a
+    /// suspend or an idle pause.
+    fn resync(&self) -> Task<Message> {
+        let mut tasks: Vec<Task<Message>> = self
+            .store
+            .pulls()
+            .keys()
+            .map(|name| Task::done(Message::PullTick(name.clone())))
+            .collect();
+        tasks.push(Task::done(Message::SmartRefresh(vec!["iwwc".into()])));
+        Task::batch(tasks)
+    }
+
+    /// `smart iwwc.idle pause=#true`
+    fn pause_when_idle(&self) -> bool {
+        self.store.smart().get("iwwc.idle").is_some_and(|d| d.pause)
+    }
+
+    fn idle_paused(&self) -> bool {
+        self.pause_when_idle() && crate::config::smart::idle()
+    }
+
+    /// Current values of the `iwwc.*` bools watch events follow.
+    fn watched_smart_bools(&self) -> Vec<(String, bool)> {
+        use crate::config::types::VarValue;
+        let watched: Vec<&str> = self
+            .store
+            .resolved()
+            .watches
+            .iter()
+            .map(|w| w.var.as_str())
+            .filter(|v| v.starts_with("iwwc."))
+            .collect();
+        if watched.is_empty() {
+            return Vec::new();
+        }
+        crate::config::smart::values(self.store.smart())
+            .into_iter()
+            .filter_map(|(name, value)| match value {
+                VarValue::Bool(b) if watched.contains(&name.as_str()) => Some((name, b)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Runs a store update of `name`, then fires the `watch` events a bool
+    /// flip triggers.
+    fn apply_var_with(
+        &mut self,
+        name: &str,
+        update: impl FnOnce(&mut Store) -> Result<(), crate::config::store::UpdateError>,
     ) -> (Result<(), crate::config::store::UpdateError>, Task<Message>) {
         use crate::config::types::VarValue;
         let old = match self.store.var_value(name) {
             Some(VarValue::Bool(b)) => Some(*b),
             _ => None,
         };
-        let res = self.store.update(name, value);
+        let res = update(&mut self.store);
         if res.is_err() {
             return (res, Task::none());
         }
@@ -751,6 +1273,70 @@ impl App {
         (res, task)
     }
 
+    /// Drops icons no taskbar shows any more and looks up the missing ones
+    /// on a blocking thread, since theme lookups walk the filesystem.
+    fn lookup_taskbar_icons(&mut self) -> Task<Message> {
+        let mut wanted = std::collections::HashSet::new();
+        for w in self.store.resolved().widgets.values() {
+            for s in render::taskbars(w) {
+                for t in &self.toplevels {
+                    wanted.insert((t.app_id.clone(), s.icon_size as u16, s.icon_theme.clone()));
+                }
+            }
+        }
+        self.taskbar_icons.retain(|key, _| wanted.contains(key));
+        let missing: Vec<_> = wanted
+            .into_iter()
+            .filter(|key| !self.taskbar_icons.contains_key(key))
+            .collect();
+        if missing.is_empty() {
+            return Task::none();
+        }
+        // spawned once polled, on the runtime rather than from `update`
+        let lookup = async move {
+            tokio::task::spawn_blocking(move || {
+                missing
+                    .into_iter()
+                    .map(|key| {
+                        let icon = crate::iconlookup::lookup_app(&key.0, key.1, &key.2);
+                        (key, icon)
+                    })
+                    .collect::<render::TaskbarIcons>()
+            })
+            .await
+            .unwrap_or_default()
+        };
+        Task::perform(lookup, Message::TaskbarIcons)
+    }
+
+    fn find_input(&self, id: &str) -> Option<ResolvedInput> {
+        self.store
+            .resolved()
+            .widgets
+            .values()
+            .find_map(|w| render::inputs(w).into_iter().find(|i| i.id == id))
+            .cloned()
+    }
+
+    /// Focuses the first input of a widget that takes keyboard focus, so a
+    /// launcher or dialog can be typed into as soon as it opens.
+    fn autofocus(&self, window: WindowId) -> Task<Message> {
+        let Some(w) = self
+            .windows
+            .get(&window)
+            .and_then(|name| self.store.resolved().widgets.get(name))
+        else {
+            return Task::none();
+        };
+        if w.keyboard != Some(true) {
+            return Task::none();
+        }
+        match render::inputs(w).first() {
+            Some(i) => iced::widget::operation::focus(i.id.clone()),
+            None => Task::none(),
+        }
+    }
+
     fn widget_transparent(&self, window: WindowId) -> Option<bool> {
         let name = self.windows.get(&window)?;
         self.store.resolved().widgets.get(name)?.transparent
@@ -776,8 +1362,10 @@ impl App {
         let mut tasks = Vec::new();
         for w in watches {
             match w.evtype {
-                EventType::WatchOn if now_true => action::run_action(&w.action),
-                EventType::WatchOff if !now_true => action::run_action(&w.action),
+                EventType::WatchOn if now_true => tasks.push(self.run_action(w.action, Vec::new())),
+                EventType::WatchOff if !now_true => {
+                    tasks.push(self.run_action(w.action, Vec::new()))
+                }
                 EventType::Timeout => {
                     if now_true {
                         let Some(dur) = w.duration else { continue };
@@ -1137,6 +1725,10 @@ impl App {
                     &render::RenderCtx {
                         tray: &self.tray_items,
                         window: id,
+                        focused: self.focus.get(&id).and_then(|f| render::focused(w, f)),
+                        toplevels: &self.toplevels,
+                        taskbar_icons: &self.taskbar_icons,
+                        output: self.window_outputs.get(&id).map(String::as_str),
                     },
                 )
                 .map(Message::Ui),
@@ -1147,6 +1739,13 @@ impl App {
     }
 }
 
+fn taskbar_control(handle: u32, command: ToplevelCommand) -> Task<Message> {
+    if !crate::toplevels::control(handle, command) {
+        log::debug!("taskbar: cannot {command:?} without wlr-foreign-toplevel-management");
+    }
+    Task::none()
+}
+
 fn menu_fetch_task(
     bus: String,
     path: String,
@@ -1221,6 +1820,17 @@ fn region_task(id: WindowId, transparent: bool) -> Task<Message> {
     Task::done(Message::SetInputRegion { id, callback })
 }
 
+fn shell_action_task(a: ResolvedAction, env: Vec<(&'static str, String)>) -> Task<Message> {
+    let capture = a.stdout.is_some();
+    let command = a.command.clone().unwrap_or_default();
+    Task::perform(action::run(command, capture, env), move |outcome| {
+        Message::ActionDone {
+            action: a.clone(),
+            outcome,
+        }
+    })
+}
+
 fn run_pull_task(name: String, command: String, default: String) -> Task<Message> {
     Task::perform(crate::daemon::pull::run(command, default), move |value| {
         Message::PullResult {
@@ -1260,6 +1870,24 @@ fn pointer_event(
     }
 }
 
+fn key_event(
+    event: iced::Event,
+    status: iced::event::Status,
+    window: iced::window::Id,
+) -> Option<Message> {
+    match event {
+        iced::Event::Keyboard(iced::keyboard::Event::KeyPressed { key, modifiers, .. }) => {
+            Some(Message::KeyPressed {
+                window,
+                key,
+                modifiers,
+                captured: status == iced::event::Status::Captured,
+            })
+        }
+        _ => None,
+    }
+}
+
 fn menu_event_task(bus: String, path: String, id: i32) -> Task<Message> {
     Task::perform(
         async move {
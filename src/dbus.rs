@@ -0,0 +1,237 @@
+//! `dbus` variables, each following one property through
+//! `PropertiesChanged`, and the `dbus-call` action.
+
+use std::time::Duration;
+
+use futures::channel::mpsc::Sender;
+use futures::sink::SinkExt;
+use futures::stream::{BoxStream, StreamExt};
+use iced::Subscription;
+use zbus::names::InterfaceName;
+use zbus::zvariant::{ObjectPath, StructureBuilder, Value};
+
+use crate::config::primitives::{DbusArg, DbusBus, DbusCall};
+use crate::config::types::{DbusDecl, VarValue};
+use crate::daemon::Message;
+
+/// Variable name, bus, service, path, interface, property.
+type Key = (String, DbusBus, String, String, String, String);
+
+/// Numbers, bools and strings map to the same kind of variable; arrays
+/// become space-separated strings, anything else its GVariant text.
+pub fn to_var(value: &Value) -> VarValue {
+    match value {
+        Value::Bool(b) => VarValue::Bool(*b),
+        Value::U8(n) => VarValue::Int((*n).into()),
+        Value::I16(n) => VarValue::Int((*n).into()),
+        Value::U16(n) => VarValue::Int((*n).into()),
+        Value::I32(n) => VarValue::Int((*n).into()),
+        Value::U32(n) => VarValue::Int((*n).into()),
+        Value::I64(n) => VarValue::Int((*n).into()),
+        Value::U64(n) => VarValue::Int((*n).into()),
+        Value::F64(f) => VarValue::Float(*f),
+        Value::Str(s) => VarValue::Str(s.to_string()),
+        Value::ObjectPath(p) => VarValue::Str(p.to_string()),
+        Value::Signature(s) => VarValue::Str(s.to_string()),
+        Value::Value(v) => to_var(v),
+        Value::Array(a) => {
+            let words: Vec<String> = a
+                .iter()
+                .map(|v| match to_var(v) {
+                    VarValue::Str(s) => s,
+                    VarValue::Int(n) => n.to_string(),
+                    VarValue::Float(f) => f.to_string(),
+                    VarValue::Bool(b) => b.to_string(),
+                })
+                .collect();
+            VarValue::Str(words.join(" "))
+        }
+        other => VarValue::Str(other.to_string()),
+    }
+}
+
+/// The system bus, connected on first use and shared by every service iwwc
+/// talks to there.
+static SYSTEM: tokio::sync::OnceCell<zbus::Connection> = tokio::sync::OnceCell::const_new();
+
+pub async fn system() -> zbus::Result<&'static zbus::Connection> {
+    SYSTEM.get_or_try_init(zbus::Connection::system).await
+}
+
+async fn connect(bus: DbusBus) -> zbus::Result<zbus::Connection> {
+    match bus {
+        DbusBus::System => system().await.cloned(),
+        DbusBus::Session => zbus::Connection::session().await,
+    }
+}
+
+async fn get(
+    props: &zbus::fdo::PropertiesProxy<'_>,
+    interface: &InterfaceName<'_>,
+    property: &str,
+) -> Option<VarValue> {
+    match props.get(interface.clone(), property).await {
+        Ok(v) => Some(to_var(&v)),
+        Err(e) => {
+            log::debug!("dbus {interface}.{property}: {e}");
+            None
+        }
+    }
+}
+
+/// Reads the property, then follows `PropertiesChanged`, reading it again
+/// when it is only invalidated or the service restarts. `None` stands for
+/// the declared default.
+async fn watch(key: &Key, output: &mut Sender<Message>) -> zbus::Result<()> {
+    let (name, bus, service, path, interface, property) = key;
+    let conn = connect(*bus).await?;
+    let props = zbus::fdo::PropertiesProxy::builder(&conn)
+        .destination(service.as_str())?
+        .path(path.as_str())?
+        .build()
+        .await?;
+    let interface = InterfaceName::try_from(interface.as_str())?;
+    let mut changes = props.receive_properties_changed().await?;
+    let dbus = zbus::fdo::DBusProxy::new(&conn).await?;
+    let mut owner_changes = dbus
+        .receive_name_owner_changed_with_args(&[(0, service.as_str())])
+        .await?;
+
+    let mut last = get(&props, &interface, property).await;
+    let _ = output
+        .send(Message::DbusValue {
+            name: name.clone(),
+            value: last.clone(),
+        })
+        .await;
+    loop {
+        let value = tokio::select! {
+            Some(signal) = changes.next() => {
+                let Ok(args) = signal.args() else { continue };
+                if args.interface_name().as_str() != interface.as_str() {
+                    continue;
+                }
+                if let Some(v) = args.changed_properties().get(property.as_str()) {
+                    Some(to_var(v))
+                } else if args.invalidated_properties().contains(&property.as_str()) {
+                    get(&props, &interface, property).await
+                } else {
+                    continue;
+                }
+            }
+            change = owner_changes.next() => {
+                let Some(change) = change else { return Ok(()) };
+                match change.args()?.new_owner().as_ref() {
+                    Some(_) => get(&props, &interface, property).await,
+                    None => None,
+                }
+            }
+        };
+        if value != last {
+            last = value.clone();
+            let _ = output
+                .send(Message::DbusValue {
+                    name: name.clone(),
+                    value,
+                })
+                .await;
+        }
+    }
+}
+
+fn property_stream(key: &Key) -> BoxStream<'static, Message> {
+    let key = key.clone();
+    iced::stream::channel(4, async move |mut output| {
+        loop {
+            if let Err(e) = watch(&key, &mut output).await {
+                log::debug!("dbus {}: {e}", key.0);
+            }
+            let _ = output
+                .send(Message::DbusValue {
+                    name: key.0.clone(),
+                    value: None,
+                })
+                .await;
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        }
+    })
+    .boxed()
+}
+
+pub fn subscription(name: &str, decl: &DbusDecl) -> Subscription<Message> {
+    let key = (
+        name.to_string(),
+        decl.bus,
+        decl.service.clone(),
+        decl.path.clone(),
+        decl.interface.clone(),
+        decl.property.clone(),
+    );
+    Subscription::run_with(key, property_stream)
+}
+
+fn arg_value(arg: &DbusArg) -> zbus::Result<Value<'_>> {
+    Ok(match arg {
+        DbusArg::Bool(b) => Value::Bool(*b),
+        DbusArg::Byte(n) => Value::U8(*n),
+        DbusArg::I16(n) => Value::I16(*n),
+        DbusArg::U16(n) => Value::U16(*n),
+        DbusArg::I32(n) => Value::I32(*n),
+        DbusArg::U32(n) => Value::U32(*n),
+        DbusArg::I64(n) => Value::I64(*n),
+        DbusArg::U64(n) => Value::U64(*n),
+        DbusArg::Double(f) => Value::F64(*f),
+        DbusArg::Str(s) => Value::from(s.as_str()),
+        DbusArg::ObjectPath(p) => Value::ObjectPath(ObjectPath::try_from(p.as_str())?),
+    })
+}
+
+/// Calls a method and waits for its reply, which is dropped.
+pub async fn call(call: DbusCall) -> zbus::Result<()> {
+    let conn = connect(call.bus).await?;
+    let destination = Some(call.service.as_str());
+    let interface = Some(call.interface.as_str());
+    let (path, method) = (call.path.as_str(), call.method.as_str());
+    if call.args.is_empty() {
+        conn.call_method(destination, path, interface, method, &())
+            .await?;
+        return Ok(());
+    }
+    let mut body = StructureBuilder::new();
+    for arg in &call.args {
+        body = body.append_field(arg_value(arg)?);
+    }
+    conn.call_method(destination, path, interface, method, &body.build()?)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zbus::zvariant::Array;
+
+    #[test]
+    fn property_values() {
+        assert_eq!(to_var(&Value::U32(3)), VarValue::Int(3));
+        assert_eq!(to_var(&Value::I64(-7)), VarValue::Int(-7));
+        assert_eq!(to_var(&Value::F64(0.5)), VarValue::Float(0.5));
+        assert_eq!(to_var(&Value::Bool(true)), VarValue::Bool(true));
+        assert_eq!(
+            to_var(&Value::from("balanced")),
+            VarValue::Str("balanced".into())
+        );
+        assert_eq!(
+            to_var(&Value::new(Value::U8(42))),
+            VarValue::Int(42),
+            "variants are unwrapped"
+        );
+        let layouts = Array::from(vec!["us", "de"]);
+        assert_eq!(
+            to_var(&Value::Array(layouts)),
+            VarValue::Str("us de".into())
+        );
+        let empty = Array::from(Vec::<u32>::new());
+        assert_eq!(to_var(&Value::Array(empty)), VarValue::Str(String::new()));
+    }
+}
@@ -15,3 +15,14 @@ pub fn lookup_named(name: &str, size: u16, theme: &str) -> Option<PathBuf> {
         .with_cache()
         .find()
 }
+
+/// Icon for a Wayland app id: the id itself, lowercased, then its last
+/// dotted part (`org.gnome.Nautilus` -> `nautilus`), then a generic one.
+pub fn lookup_app(app_id: &str, size: u16, theme: &str) -> Option<PathBuf> {
+    let lower = app_id.to_lowercase();
+    let last = lower.rsplit('.').next().unwrap_or_default().to_string();
+    [app_id, &lower, &last, "application-x-executable"]
+        .into_iter()
+        .filter(|name| !name.is_empty())
+        .find_map(|name| lookup_named(name, size, theme))
+}
@@ -0,0 +1,165 @@
+use std::os::fd::{AsFd, AsRawFd};
+use std::time::Duration;
+
+use futures::channel::mpsc::Sender;
+use futures::sink::SinkExt;
+use futures::stream::{BoxStream, StreamExt};
+use iced::Subscription;
+use tokio::io::unix::AsyncFd;
+use wayland_client::backend::WaylandError;
+use wayland_client::protocol::{wl_registry, wl_seat};
+use wayland_client::{Connection, Dispatch, QueueHandle};
+use wayland_protocols::ext::idle_notify::v1::client::ext_idle_notification_v1::{
+    self, ExtIdleNotificationV1,
+};
+use wayland_protocols::ext::idle_notify::v1::client::ext_idle_notifier_v1::{
+    self, ExtIdleNotifierV1,
+};
+
+use crate::daemon::Message;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+#[derive(Default)]
+struct Client {
+    seat: Option<wl_seat::WlSeat>,
+    notifier: Option<ExtIdleNotifierV1>,
+    /// Latest `idled` (true) or `resumed` (false) since the last dispatch.
+    idle: Option<bool>,
+}
+
+impl Dispatch<wl_registry::WlRegistry, ()> for Client {
+    fn event(
+        state: &mut Self,
+        registry: &wl_registry::WlRegistry,
+        event: wl_registry::Event,
+        _: &(),
+        _: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        if let wl_registry::Event::Global {
+            name, interface, ..
+        } = event
+        {
+            match interface.as_str() {
+                "wl_seat" if state.seat.is_none() => {
+                    state.seat = Some(registry.bind(name, 1, qh, ()));
+                }
+                "ext_idle_notifier_v1" if state.notifier.is_none() => {
+                    state.notifier = Some(registry.bind(name, 1, qh, ()));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Dispatch<wl_seat::WlSeat, ()> for Client {
+    fn event(
+        _: &mut Self,
+        _: &wl_seat::WlSeat,
+        _: wl_seat::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ExtIdleNotifierV1, ()> for Client {
+    fn event(
+        _: &mut Self,
+        _: &ExtIdleNotifierV1,
+        _: ext_idle_notifier_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ExtIdleNotificationV1, ()> for Client {
+    fn event(
+        state: &mut Self,
+        _: &ExtIdleNotificationV1,
+        event: ext_idle_notification_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            ext_idle_notification_v1::Event::Idled => state.idle = Some(true),
+            ext_idle_notification_v1::Event::Resumed => state.idle = Some(false),
+            _ => {}
+        }
+    }
+}
+
+async fn publish(output: &mut Sender<Message>, idle: bool) {
+    if crate::config::smart::set_idle(idle) {
+        let _ = output.send(Message::Idle(idle)).await;
+    }
+}
+
+/// Runs until the connection drops. `Ok(false)` when the compositor lacks
+/// ext-idle-notify-v1.
+async fn watch(timeout: Duration, output: &mut Sender<Message>) -> Result<bool> {
+    let conn = Connection::connect_to_env()?;
+    let mut queue = conn.new_event_queue();
+    let qh = queue.handle();
+    let _registry = conn.display().get_registry(&qh, ());
+    let mut client = Client::default();
+    queue.roundtrip(&mut client)?;
+    let (Some(notifier), Some(seat)) = (&client.notifier, &client.seat) else {
+        return Ok(false);
+    };
+    let millis = u32::try_from(timeout.as_millis()).unwrap_or(u32::MAX);
+    let _notification = notifier.get_idle_notification(millis, seat, &qh, ());
+
+    let backend = conn.backend();
+    let fd = AsyncFd::new(backend.poll_fd().as_fd().as_raw_fd())?;
+    loop {
+        queue.dispatch_pending(&mut client)?;
+        if let Some(idle) = client.idle.take() {
+            publish(output, idle).await;
+        }
+        conn.flush()?;
+        let mut ready = fd.readable().await?;
+        if let Some(guard) = queue.prepare_read() {
+            match guard.read() {
+                Ok(_) => {}
+                Err(WaylandError::Io(e)) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    ready.clear_ready();
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+fn idle_stream(timeout: &Duration) -> BoxStream<'static, Message> {
+    let timeout = *timeout;
+    iced::stream::channel(4, async move |mut output| {
+        loop {
+            let supported = watch(timeout, &mut output).await;
+            // without a watcher nobody is away
+            publish(&mut output, false).await;
+            match supported {
+                Ok(false) => {
+                    log::warn!("idle: the compositor does not support ext-idle-notify-v1");
+                    return;
+                }
+                Ok(true) => {}
+                Err(e) => log::debug!("idle: {e}"),
+            }
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        }
+    })
+    .boxed()
+}
+
+/// One notification per timeout; changing `smart iwwc.idle timeout=`
+/// starts a new one.
+pub fn subscription(timeout: Duration) -> Subscription<Message> {
+    Subscription::run_with(timeout, idle_stream)
+}
@@ -0,0 +1,84 @@
+//! `iwwc.keyboard` from the keymap and modifier events of the layer shell's
+//! `wl_keyboard`, forwarded on the shell stream. Being iwwc's own keyboard,
+//! it gets modifiers whenever one of iwwc's surfaces has keyboard focus.
+
+use iced_wayland_subscriber::shell::ShellEvent;
+
+use crate::config::smart::keyboard::{Keyboard, Keymap};
+
+/// Applies a shell event to `iwwc.keyboard`; true when the variables
+/// changed. Other events are ignored.
+pub fn apply(event: &ShellEvent) -> bool {
+    match event {
+        // a new keymap starts at its first group
+        ShellEvent::KeyboardKeymap { keymap } => crate::config::smart::update_keyboard(|kb| {
+            *kb = Keyboard {
+                keymap: Keymap::parse(keymap.trim_end_matches('\0')),
+                ..Default::default()
+            }
+        }),
+        ShellEvent::KeyboardModifiers { locked, group, .. } => {
+            crate::config::smart::update_keyboard(|kb| {
+                kb.locked = *locked;
+                kb.group = *group;
+            })
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::smart::testutil::get;
+    use crate::config::types::VarValue;
+
+    const KEYMAP: &str = r#"xkb_keymap {
+xkb_symbols "pc+us+de:2+inet(evdev)" {
+	name[Group1]="English (US)";
+	name[Group2]="German";
+	modifier_map Lock { <CAPS> };
+	modifier_map Mod2 { <NMLK> };
+};
+};
+"#;
+
+    fn values() -> Vec<(String, VarValue)> {
+        let mut vals = Vec::new();
+        crate::config::smart::update_keyboard(|kb| vals = kb.values());
+        vals
+    }
+
+    #[test]
+    fn shell_events_feed_keyboard() {
+        let events = [
+            ShellEvent::KeyboardKeymap {
+                keymap: format!("{KEYMAP}\0"),
+            },
+            ShellEvent::KeyboardModifiers {
+                depressed: 0,
+                latched: 0,
+                locked: 1 << 1,
+                group: 1,
+            },
+        ];
+        for e in &events {
+            apply(e);
+        }
+        let vals = values();
+        let s = |v: &str| Some(VarValue::Str(v.into()));
+        assert_eq!(get(&vals, "iwwc.keyboard.layouts"), s("us de"));
+        assert_eq!(get(&vals, "iwwc.keyboard.layout"), s("German"));
+        assert_eq!(
+            get(&vals, "iwwc.keyboard.caps_lock"),
+            Some(VarValue::Bool(true))
+        );
+
+        // the layout list follows a new keymap, back at its first group
+        apply(&ShellEvent::KeyboardKeymap {
+            keymap: KEYMAP.replace("+de:2", ""),
+        });
+        let vals = values();
+        assert_eq!(get(&vals, "iwwc.keyboard.layout"), s("English (US)"));
+    }
+}
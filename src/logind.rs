@@ -0,0 +1,107 @@
+//! The parts of logind iwwc calls into.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use futures::sink::SinkExt;
+use futures::stream::{Stream, StreamExt};
+use iced::Subscription;
+use zbus::zvariant::OwnedFd;
+
+use crate::config::primitives::PowerCommand;
+use crate::daemon::Message;
+
+#[zbus::proxy(
+    interface = "org.freedesktop.login1.Session",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1/session/auto"
+)]
+pub trait Session {
+    /// Writes a backlight or LED brightness the session's user could not.
+    fn set_brightness(&self, subsystem: &str, name: &str, brightness: u32) -> zbus::Result<()>;
+    /// Asks the session's screen locker to lock.
+    fn lock(&self) -> zbus::Result<()>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.login1.Manager",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1"
+)]
+pub trait Manager {
+    fn suspend(&self, interactive: bool) -> zbus::Result<()>;
+    fn hibernate(&self, interactive: bool) -> zbus::Result<()>;
+    fn reboot(&self, interactive: bool) -> zbus::Result<()>;
+    fn power_off(&self, interactive: bool) -> zbus::Result<()>;
+    /// Held for as long as the returned descriptor stays open.
+    fn inhibit(&self, what: &str, who: &str, why: &str, mode: &str) -> zbus::Result<OwnedFd>;
+    /// `true` just before the machine sleeps, `false` once it woke up.
+    #[zbus(signal)]
+    fn prepare_for_sleep(&self, start: bool) -> zbus::Result<()>;
+}
+
+/// The inhibitor `inhibit=` took, released by dropping it.
+static INHIBITOR: Mutex<Option<OwnedFd>> = Mutex::new(None);
+
+/// The session iwwc runs in, on the system bus.
+pub async fn session() -> zbus::Result<SessionProxy<'static>> {
+    SessionProxy::new(crate::dbus::system().await?).await
+}
+
+async fn manager() -> zbus::Result<ManagerProxy<'static>> {
+    ManagerProxy::new(crate::dbus::system().await?).await
+}
+
+/// Suspends, hibernates, reboots or powers off the machine, or locks the
+/// session. Polkit may ask for a password.
+pub async fn power(command: PowerCommand) -> zbus::Result<()> {
+    match command {
+        PowerCommand::Suspend => manager().await?.suspend(true).await,
+        PowerCommand::Hibernate => manager().await?.hibernate(true).await,
+        PowerCommand::Reboot => manager().await?.reboot(true).await,
+        PowerCommand::PowerOff => manager().await?.power_off(true).await,
+        PowerCommand::Lock => session().await?.lock().await,
+    }
+}
+
+/// Takes or releases an idle and sleep inhibitor; `None` toggles. True
+/// when `iwwc.inhibited` changed.
+pub async fn inhibit(on: Option<bool>) -> zbus::Result<bool> {
+    let held = INHIBITOR.lock().unwrap().is_some();
+    let on = on.unwrap_or(!held);
+    if on && !held {
+        let fd = manager()
+            .await?
+            .inhibit("idle:sleep", "iwwc", "inhibit= action", "block")
+            .await?;
+        *INHIBITOR.lock().unwrap() = Some(fd);
+    } else if !on {
+        INHIBITOR.lock().unwrap().take();
+    }
+    Ok(crate::config::smart::set_inhibited(on))
+}
+
+async fn watch(output: &mut futures::channel::mpsc::Sender<Message>) -> zbus::Result<()> {
+    let mut signals = manager().await?.receive_prepare_for_sleep().await?;
+    while let Some(signal) = signals.next().await {
+        let start = *signal.args()?.start();
+        let _ = output.send(Message::PrepareForSleep(start)).await;
+    }
+    Ok(())
+}
+
+fn sleep_stream() -> impl Stream<Item = Message> {
+    iced::stream::channel(4, async |mut output| {
+        loop {
+            if let Err(e) = watch(&mut output).await {
+                log::debug!("logind: {e}");
+            }
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        }
+    })
+}
+
+/// `PrepareForSleep` as [`Message::PrepareForSleep`].
+pub fn subscription() -> Subscription<Message> {
+    Subscription::run(sleep_stream)
+}
@@ -1,11 +1,22 @@
+pub mod audio;
+pub mod backlight;
+pub mod bluetooth;
 pub mod config;
 pub mod daemon;
+pub mod dbus;
 pub mod iconlookup;
+pub mod idle;
 pub mod ipc;
+pub mod keyboard;
+pub mod logind;
 pub mod mpris;
+pub mod network;
 pub mod notification;
 pub mod render;
+pub mod toplevels;
 pub mod tray;
+pub mod upower;
+pub mod workspaces;
 
 use crate::ipc::{Command, IpcClient, IpcError, Response};
 use clap::{Parser, Subcommand};
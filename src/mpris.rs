@@ -1,64 +1,382 @@
 use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 
 use futures::channel::mpsc::Sender;
 use futures::sink::SinkExt;
-use futures::stream::{Stream, StreamExt};
+use futures::stream::{Stream, StreamExt, select_all};
 use iced::Subscription;
+use zbus::proxy::CacheProperties;
 use zbus::zvariant::OwnedValue;
 
+use crate::config::primitives::MprisCommand;
+use crate::config::smart::mpris::Player;
 use crate::daemon::Message;
 
+const PREFIX: &str = "org.mpris.MediaPlayer2.";
+
 #[zbus::proxy(
     interface = "org.mpris.MediaPlayer2.Player",
-    default_service = "org.mpris.MediaPlayer2.playerctld",
     default_path = "/org/mpris/MediaPlayer2"
 )]
-trait Player {
+trait MediaPlayer {
+    fn play_pause(&self) -> zbus::Result<()>;
+    fn play(&self) -> zbus::Result<()>;
+    fn pause(&self) -> zbus::Result<()>;
+    fn stop(&self) -> zbus::Result<()>;
+    fn next(&self) -> zbus::Result<()>;
+    fn previous(&self) -> zbus::Result<()>;
+    fn seek(&self, offset: i64) -> zbus::Result<()>;
     #[zbus(property)]
     fn metadata(&self) -> zbus::Result<HashMap<String, OwnedValue>>;
+    #[zbus(property)]
+    fn playback_status(&self) -> zbus::Result<String>;
+    #[zbus(property)]
+    fn position(&self) -> zbus::Result<i64>;
+    #[zbus(property)]
+    fn rate(&self) -> zbus::Result<f64>;
+    #[zbus(property)]
+    fn volume(&self) -> zbus::Result<f64>;
+    #[zbus(property)]
+    fn shuffle(&self) -> zbus::Result<bool>;
+    #[zbus(property)]
+    fn loop_status(&self) -> zbus::Result<String>;
+    #[zbus(signal)]
+    fn seeked(&self, position: i64) -> zbus::Result<()>;
+}
+
+/// playerctld re-exports whichever player it considers active; tracking it
+/// too would list every player twice.
+fn is_player(bus_name: &str) -> bool {
+    bus_name.starts_with(PREFIX) && bus_name != "org.mpris.MediaPlayer2.playerctld"
+}
+
+fn str_prop(meta: &HashMap<String, OwnedValue>, key: &str) -> Option<String> {
+    meta.get(key).and_then(|v| String::try_from(&**v).ok())
+}
+
+/// Fills title, artist, album, art and length from `Metadata`. Players
+/// disagree on whether `mpris:length` is signed.
+fn apply_metadata(p: &mut Player, meta: &HashMap<String, OwnedValue>) {
+    p.title = str_prop(meta, "xesam:title").unwrap_or_default();
+    p.album = str_prop(meta, "xesam:album").unwrap_or_default();
+    p.art_url = str_prop(meta, "mpris:artUrl").unwrap_or_default();
+    p.artist = meta
+        .get("xesam:artist")
+        .and_then(|v| Vec::<String>::try_from(v.try_clone().ok()?).ok())
+        .map(|a| a.join(", "))
+        .unwrap_or_default();
+    p.length = meta
+        .get("mpris:length")
+        .and_then(|v| {
+            i64::try_from(&**v)
+                .ok()
+                .or_else(|| u64::try_from(&**v).ok().map(|l| l as i64))
+        })
+        .unwrap_or(0);
+}
+
+async fn proxy(conn: &zbus::Connection, bus_name: &str) -> zbus::Result<MediaPlayerProxy<'static>> {
+    MediaPlayerProxy::builder(conn)
+        .destination(bus_name.to_string())?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await
+}
+
+async fn read(proxy: &MediaPlayerProxy<'_>, bus_name: &str) -> Option<Player> {
+    let status = proxy.playback_status().await.ok()?.to_lowercase();
+    let mut p = Player {
+        status,
+        position: proxy.position().await.unwrap_or(0),
+        position_at: Instant::now(),
+        rate: proxy.rate().await.unwrap_or(1.0),
+        volume: proxy.volume().await.unwrap_or(0.0),
+        shuffle: proxy.shuffle().await.unwrap_or(false),
+        loop_status: proxy
+            .loop_status()
+            .await
+            .map(|l| l.to_lowercase())
+            .unwrap_or_else(|_| "none".into()),
+        ..Player::new(&bus_name[PREFIX.len()..])
+    };
+    apply_metadata(&mut p, &proxy.metadata().await.unwrap_or_default());
+    Some(p)
+}
+
+/// Remote `mpris:artUrl`s downloaded to `$XDG_CACHE_HOME/iwwc/art`, one
+/// file per player. A player's old file is deleted when its art changes.
+/// Downloads run in their own task, which publishes `iwwc.mpris.art` once
+/// the file is there.
+struct ArtCache {
+    dir: PathBuf,
+    /// player -> art url
+    current: HashMap<String, String>,
+    output: Sender<Message>,
+}
+
+fn art_dir() -> PathBuf {
+    let base = std::env::var("XDG_CACHE_HOME")
+        .ok()
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var("HOME")
+                .ok()
+                .filter(|h| !h.is_empty())
+                .map(|h| PathBuf::from(h).join(".cache"))
+        })
+        .unwrap_or_else(std::env::temp_dir);
+    base.join("iwwc/art")
+}
+
+fn art_file(dir: &Path, player: &str, url: &str) -> PathBuf {
+    let mut h = DefaultHasher::new();
+    url.hash(&mut h);
+    dir.join(format!(
+        "{}-{:016x}",
+        crate::config::smart::sanitize(player),
+        h.finish()
+    ))
 }
 
-fn title_of(meta: &HashMap<String, OwnedValue>) -> Option<String> {
-    meta.get("xesam:title")
-        .and_then(|v| String::try_from(&**v).ok())
-        .filter(|s| !s.is_empty())
+/// The sanitized player of one of [`art_file`]'s names, or a download of
+/// one; `None` for any other file.
+fn art_owner(name: &str) -> Option<&str> {
+    let name = name.strip_suffix(".part").unwrap_or(name);
+    let (player, hash) = name.rsplit_once('-')?;
+    let ok = !player.is_empty()
+        && player
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_')
+        && hash.len() == 16
+        && hash.bytes().all(|b| b.is_ascii_hexdigit());
+    ok.then_some(player)
 }
 
-async fn publish(output: &mut Sender<Message>, title: Option<String>) {
-    if crate::config::smart::set_activesong(title) {
-        let _ = output.send(Message::SmartRefresh).await;
+/// Deletes the art files of a previous run, leaving anything else in `dir`.
+fn clear_stale_art(dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for e in entries.flatten() {
+        if art_owner(&e.file_name().to_string_lossy()).is_some() {
+            let _ = std::fs::remove_file(e.path());
+        }
+    }
+}
+
+/// Deletes `player`'s cached files other than `keep`.
+fn clear_art(dir: &Path, player: &str, keep: Option<&Path>) {
+    let player = crate::config::smart::sanitize(player);
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for e in entries.flatten() {
+        let path = e.path();
+        if art_owner(&e.file_name().to_string_lossy()) == Some(player.as_str())
+            && Some(path.as_path()) != keep
+        {
+            let _ = std::fs::remove_file(path);
+        }
     }
 }
 
-async fn watch(output: &mut Sender<Message>) -> zbus::Result<()> {
+/// Needs `curl`; there is no HTTP client in-process.
+async fn download(url: &str, to: &Path) -> bool {
+    if let Some(dir) = to.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    let part = to.with_extension("part");
+    let status = tokio::process::Command::new("curl")
+        .args(["-sfL", "--max-time", "10", "-o"])
+        .arg(&part)
+        .arg(url)
+        .status()
+        .await;
+    let ok = match status {
+        Ok(s) => s.success(),
+        Err(e) => {
+            log::warn!("mpris: cannot run curl for remote art: {e}");
+            false
+        }
+    };
+    if ok && std::fs::rename(&part, to).is_ok() {
+        return true;
+    }
+    let _ = std::fs::remove_file(&part);
+    false
+}
+
+impl ArtCache {
+    /// Art left from a previous run is dropped.
+    fn new(dir: PathBuf, output: Sender<Message>) -> ArtCache {
+        clear_stale_art(&dir);
+        ArtCache {
+            dir,
+            current: HashMap::new(),
+            output,
+        }
+    }
+
+    /// Sets `p.art` from `p.art_url`. Only a changed url does any work, so
+    /// a failed download is not retried on every position update; until a
+    /// download finishes the art is empty.
+    fn resolve(&mut self, p: &mut Player) {
+        let url = p.art_url.as_str();
+        let changed = self.current.get(&p.name).is_none_or(|u| u != url);
+        if changed {
+            self.current.insert(p.name.clone(), url.to_string());
+        }
+        p.art = if let Some(path) = crate::config::primitives::local_image_path(url) {
+            if changed {
+                clear_art(&self.dir, &p.name, None);
+            }
+            path.to_string_lossy().into_owned()
+        } else if url.starts_with("http://") || url.starts_with("https://") {
+            let file = art_file(&self.dir, &p.name, url);
+            if changed {
+                clear_art(&self.dir, &p.name, Some(&file));
+                if !file.exists() {
+                    self.fetch(p.name.clone(), url.to_string(), file.clone());
+                }
+            }
+            match file.exists() {
+                true => file.to_string_lossy().into_owned(),
+                false => String::new(),
+            }
+        } else {
+            if changed {
+                clear_art(&self.dir, &p.name, None);
+            }
+            String::new()
+        };
+    }
+
+    /// Downloads `url` for `player` and sets its art if the player still
+    /// shows that url; otherwise the file is dropped again.
+    fn fetch(&self, player: String, url: String, file: PathBuf) {
+        let mut output = self.output.clone();
+        tokio::spawn(async move {
+            if !download(&url, &file).await {
+                log::debug!("mpris: could not fetch art {url}");
+                return;
+            }
+            let art = file.to_string_lossy().into_owned();
+            let mut current = false;
+            publish(&mut output, |ps| {
+                if let Some(p) = ps.get_mut(&player)
+                    && p.art_url == url
+                {
+                    p.art = art;
+                    current = true;
+                }
+            })
+            .await;
+            if !current {
+                let _ = std::fs::remove_file(&file);
+            }
+        });
+    }
+}
+
+async fn publish(
+    output: &mut Sender<Message>,
+    f: impl FnOnce(&mut crate::config::smart::mpris::Players),
+) {
+    if crate::config::smart::update_mpris(f) {
+        let _ = output
+            .send(Message::SmartRefresh(vec!["iwwc.mpris".into()]))
+            .await;
+    }
+}
+
+async fn watch(output: &mut Sender<Message>, art: &mut ArtCache) -> zbus::Result<()> {
     let conn = zbus::Connection::session().await?;
-    let proxy = PlayerProxy::new(&conn).await?;
-    let title = title_of(&proxy.metadata().await?);
-    let mut owner_changes = proxy.inner().receive_owner_changed().await?;
-    let mut meta_changes = proxy.receive_metadata_changed().await;
-    publish(output, title).await;
+    let dbus = zbus::fdo::DBusProxy::new(&conn).await?;
+    let mut owner_changes = dbus.receive_name_owner_changed().await?;
     loop {
-        tokio::select! {
-            change = meta_changes.next() => {
-                let Some(change) = change else { return Ok(()) };
-                let title = change.get().await.ok().and_then(|m| title_of(&m));
-                publish(output, title).await;
+        let names: Vec<String> = dbus
+            .list_names()
+            .await?
+            .into_iter()
+            .map(|n| n.to_string())
+            .filter(|n| is_player(n))
+            .collect();
+        let mut proxies = Vec::new();
+        let mut players = Vec::new();
+        for name in &names {
+            let Ok(p) = proxy(&conn, name).await else {
+                continue;
+            };
+            if let Some(mut player) = read(&p, name).await {
+                art.resolve(&mut player);
+                players.push(player);
+            }
+            proxies.push((name.clone(), p));
+        }
+        publish(output, |ps| {
+            let gone: Vec<String> = ps
+                .names()
+                .filter(|n| !players.iter().any(|p| p.name == *n))
+                .map(String::from)
+                .collect();
+            for n in gone {
+                ps.remove(&n);
+            }
+            for p in players {
+                ps.update(p);
+            }
+        })
+        .await;
+
+        let mut changes = Vec::new();
+        for (i, (name, p)) in proxies.iter().enumerate() {
+            if let Ok(b) = zbus::fdo::PropertiesProxy::builder(&conn).destination(name.clone())
+                && let Ok(b) = b.path("/org/mpris/MediaPlayer2")
+                && let Ok(props) = b.build().await
+                && let Ok(s) = props.receive_properties_changed().await
+            {
+                changes.push(s.map(move |_| i).boxed());
+            }
+            if let Ok(s) = p.receive_seeked().await {
+                changes.push(s.map(move |_| i).boxed());
+            }
+        }
+        let mut changes = select_all(changes);
+
+        loop {
+            tokio::select! {
+                Some(i) = changes.next() => {
+                    let (name, p) = &proxies[i];
+                    if let Some(mut player) = read(p, name).await {
+                        art.resolve(&mut player);
+                        publish(output, |ps| ps.update(player)).await;
+                    }
+                }
+                change = owner_changes.next() => {
+                    let Some(change) = change else { return Ok(()) };
+                    if change.args().is_ok_and(|a| is_player(a.name().as_str())) {
+                        break;
+                    }
+                }
             }
-            _ = owner_changes.next() => { return Ok(()) }
         }
     }
 }
 
 fn mpris_stream() -> impl Stream<Item = Message> {
     iced::stream::channel(16, async |mut output| {
+        let mut art = ArtCache::new(art_dir(), output.clone());
         loop {
-            match watch(&mut output).await {
+            match watch(&mut output, &mut art).await {
                 Ok(()) => {
                     tokio::time::sleep(std::time::Duration::from_secs(1)).await;
                 }
                 Err(e) => {
                     log::debug!("mpris: {e}");
-                    publish(&mut output, None).await;
+                    publish(&mut output, |ps| *ps = Default::default()).await;
                     tokio::time::sleep(std::time::Duration::from_secs(5)).await;
                 }
             }
@@ -69,3 +387,137 @@ fn mpris_stream() -> impl Stream<Item = Message> {
 pub fn subscription() -> Subscription<Message> {
     Subscription::run(mpris_stream)
 }
+
+/// Runs a playback command on `player` (a name from `iwwc.mpris.players`)
+/// or the active player. Without a watcher running the first player on the
+/// bus is used.
+pub async fn control(player: Option<String>, command: MprisCommand) -> zbus::Result<()> {
+    let conn = zbus::Connection::session().await?;
+    let target = match crate::config::smart::mpris_player(player.as_deref()) {
+        Some(name) => format!("{PREFIX}{name}"),
+        None if player.is_some() => return Ok(()),
+        None => {
+            let dbus = zbus::fdo::DBusProxy::new(&conn).await?;
+            let names = dbus.list_names().await?;
+            let Some(name) = names.iter().map(|n| n.as_str()).find(|n| is_player(n)) else {
+                return Ok(());
+            };
+            name.to_string()
+        }
+    };
+    let p = proxy(&conn, &target).await?;
+    match command {
+        MprisCommand::PlayPause => p.play_pause().await,
+        MprisCommand::Play => p.play().await,
+        MprisCommand::Pause => p.pause().await,
+        MprisCommand::Stop => p.stop().await,
+        MprisCommand::Next => p.next().await,
+        MprisCommand::Previous => p.previous().await,
+        MprisCommand::Seek(offset) => p.seek(offset).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zbus::zvariant::Value;
+
+    fn owned(v: Value<'static>) -> OwnedValue {
+        OwnedValue::try_from(v).unwrap()
+    }
+
+    #[test]
+    fn metadata_fields() {
+        let meta: HashMap<String, OwnedValue> = [
+            ("xesam:title", owned(Value::from("Song"))),
+            ("xesam:album", owned(Value::from("Album"))),
+            (
+                "xesam:artist",
+                owned(Value::from(vec!["A".to_string(), "B".to_string()])),
+            ),
+            ("mpris:artUrl", owned(Value::from("file:///tmp/a.png"))),
+            ("mpris:length", owned(Value::from(180_000_000u64))),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect();
+        let mut p = Player::new("mpd");
+        apply_metadata(&mut p, &meta);
+        assert_eq!(p.title, "Song");
+        assert_eq!(p.album, "Album");
+        assert_eq!(p.artist, "A, B");
+        assert_eq!(p.art_url, "file:///tmp/a.png");
+        assert_eq!(p.length, 180_000_000);
+    }
+
+    #[test]
+    fn metadata_missing_clears() {
+        let mut p = Player {
+            title: "old".into(),
+            ..Player::new("x")
+        };
+        let meta = HashMap::from([("mpris:length".to_string(), owned(Value::from(5i64)))]);
+        apply_metadata(&mut p, &meta);
+        assert_eq!(p.title, "");
+        assert_eq!(p.length, 5);
+    }
+
+    #[test]
+    fn art_follows_track_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let (output, _rx) = futures::channel::mpsc::channel(1);
+        let mut cache = ArtCache::new(dir.path().join("art"), output);
+        std::fs::create_dir_all(&cache.dir).unwrap();
+        let old = art_file(&cache.dir, "spotify", "https://i.scdn.co/image/old");
+        let new = art_file(&cache.dir, "spotify", "https://i.scdn.co/image/new");
+        let other = art_file(&cache.dir, "vlc", "https://example.com/a.jpg");
+        // named like a player's file but not one of ours
+        let foreign = cache.dir.join("spotify-notes.txt");
+        for f in [&old, &new, &other, &foreign] {
+            std::fs::write(f, b"").unwrap();
+        }
+
+        let mut p = Player {
+            art_url: "https://i.scdn.co/image/new".into(),
+            ..Player::new("spotify")
+        };
+        cache.resolve(&mut p);
+        assert_eq!(p.art, new.to_string_lossy());
+        assert!(!old.exists());
+        assert!(other.exists());
+        assert!(foreign.exists());
+
+        p.art_url = "file:///music/My%20Album/cover.jpg".into();
+        cache.resolve(&mut p);
+        assert_eq!(p.art, "/music/My Album/cover.jpg");
+        assert!(!new.exists());
+
+        p.art_url = String::new();
+        cache.resolve(&mut p);
+        assert_eq!(p.art, "");
+        assert!(other.exists());
+    }
+
+    #[test]
+    fn stale_art_cleared_other_files_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let ours = art_file(dir.path(), "spotify", "https://i.scdn.co/image/a");
+        let part = ours.with_extension("part");
+        let foreign = dir.path().join("notes.txt");
+        for f in [&ours, &part, &foreign] {
+            std::fs::write(f, b"").unwrap();
+        }
+        let (output, _rx) = futures::channel::mpsc::channel(1);
+        ArtCache::new(dir.path().to_path_buf(), output);
+        assert!(!ours.exists());
+        assert!(!part.exists());
+        assert!(foreign.exists());
+    }
+
+    #[test]
+    fn playerctld_is_not_a_player() {
+        assert!(is_player("org.mpris.MediaPlayer2.spotify"));
+        assert!(!is_player("org.mpris.MediaPlayer2.playerctld"));
+        assert!(!is_player("org.freedesktop.Notifications"));
+    }
+}
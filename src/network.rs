@@ -0,0 +1,631 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use futures::channel::mpsc::Sender;
+use futures::sink::SinkExt;
+use futures::stream::{Stream, StreamExt};
+use iced::Subscription;
+use zbus::proxy::CacheProperties;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue};
+
+use crate::config::primitives::NetworkCommand;
+use crate::config::smart::network::{
+    AccessPoint, ActiveConnection, Network, SavedConnection, kind_name, merge_access_points,
+};
+use crate::daemon::Message;
+
+const NM: &str = "org.freedesktop.NetworkManager";
+const PATH: &str = "/org/freedesktop/NetworkManager";
+const SETTINGS_PATH: &str = "/org/freedesktop/NetworkManager/Settings";
+/// `NM_DEVICE_TYPE_WIFI`.
+const DEVICE_WIFI: u32 = 2;
+
+/// A scan or a reconnect changes many objects at once; one read answers
+/// all signals this close together.
+const BURST: Duration = Duration::from_millis(100);
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager",
+    default_service = "org.freedesktop.NetworkManager",
+    default_path = "/org/freedesktop/NetworkManager"
+)]
+trait NetworkManager {
+    fn activate_connection(
+        &self,
+        connection: &ObjectPath<'_>,
+        device: &ObjectPath<'_>,
+        specific_object: &ObjectPath<'_>,
+    ) -> zbus::Result<OwnedObjectPath>;
+    fn deactivate_connection(&self, active_connection: &ObjectPath<'_>) -> zbus::Result<()>;
+    fn get_devices(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+    #[zbus(property)]
+    fn state(&self) -> zbus::Result<u32>;
+    #[zbus(property)]
+    fn primary_connection(&self) -> zbus::Result<OwnedObjectPath>;
+    #[zbus(property)]
+    fn active_connections(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager.Connection.Active",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+trait Active {
+    #[zbus(property)]
+    fn id(&self) -> zbus::Result<String>;
+    #[zbus(property, name = "Type")]
+    fn kind(&self) -> zbus::Result<String>;
+    #[zbus(property)]
+    fn vpn(&self) -> zbus::Result<bool>;
+    #[zbus(property)]
+    fn devices(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+    #[zbus(property)]
+    fn connection(&self) -> zbus::Result<OwnedObjectPath>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager.Device",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+trait Device {
+    #[zbus(property)]
+    fn interface(&self) -> zbus::Result<String>;
+    #[zbus(property)]
+    fn device_type(&self) -> zbus::Result<u32>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager.Device.Wireless",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+trait Wireless {
+    #[zbus(property)]
+    fn access_points(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+    #[zbus(property)]
+    fn active_access_point(&self) -> zbus::Result<OwnedObjectPath>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager.AccessPoint",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+trait AccessPointInfo {
+    #[zbus(property)]
+    fn ssid(&self) -> zbus::Result<Vec<u8>>;
+    #[zbus(property)]
+    fn strength(&self) -> zbus::Result<u8>;
+    #[zbus(property)]
+    fn frequency(&self) -> zbus::Result<u32>;
+    #[zbus(property)]
+    fn flags(&self) -> zbus::Result<u32>;
+    #[zbus(property)]
+    fn wpa_flags(&self) -> zbus::Result<u32>;
+    #[zbus(property)]
+    fn rsn_flags(&self) -> zbus::Result<u32>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager.Settings",
+    default_service = "org.freedesktop.NetworkManager",
+    default_path = "/org/freedesktop/NetworkManager/Settings"
+)]
+trait Settings {
+    fn list_connections(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager.Settings.Connection",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+trait SettingsConnection {
+    fn get_settings(&self) -> zbus::Result<HashMap<String, HashMap<String, OwnedValue>>>;
+}
+
+/// Properties are read once per change burst, so zbus's cache would only
+/// add a match rule per object.
+async fn proxy<T>(conn: &zbus::Connection, path: &str) -> zbus::Result<T>
+where
+    T: From<zbus::Proxy<'static>> + zbus::proxy::Defaults,
+{
+    zbus::proxy::Builder::<T>::new(conn)
+        .path(path.to_string())?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await
+}
+
+async fn read_active(conn: &zbus::Connection, path: &str) -> zbus::Result<ActiveConnection> {
+    let a: ActiveProxy = proxy(conn, path).await?;
+    let kind = a.kind().await?;
+    let device = match a.devices().await?.first() {
+        Some(dev) => proxy::<DeviceProxy>(conn, dev).await?.interface().await?,
+        None => String::new(),
+    };
+    Ok(ActiveConnection {
+        id: a.id().await?,
+        // WireGuard is not a plugin VPN to NetworkManager, but is one here
+        vpn: a.vpn().await? || kind == "wireguard",
+        kind: kind_name(&kind),
+        device,
+        path: path.to_string(),
+        settings: a.connection().await?.to_string(),
+    })
+}
+
+async fn read_access_points(conn: &zbus::Connection, path: &str) -> zbus::Result<Vec<AccessPoint>> {
+    let device: DeviceProxy = proxy(conn, path).await?;
+    if device.device_type().await? != DEVICE_WIFI {
+        return Ok(Vec::new());
+    }
+    let wireless: WirelessProxy = proxy(conn, path).await?;
+    let active = wireless.active_access_point().await?;
+    let mut out = Vec::new();
+    for ap_path in wireless.access_points().await? {
+        let ap: AccessPointInfoProxy = proxy(conn, &ap_path).await?;
+        out.push(AccessPoint {
+            ssid: String::from_utf8_lossy(&ap.ssid().await?).into_owned(),
+            strength: ap.strength().await?,
+            frequency: ap.frequency().await?,
+            secure: ap.flags().await? & 1 != 0
+                || ap.wpa_flags().await? != 0
+                || ap.rsn_flags().await? != 0,
+            active: ap_path == active,
+        });
+    }
+    Ok(out)
+}
+
+async fn read_saved(conn: &zbus::Connection, path: &str) -> zbus::Result<SavedConnection> {
+    let c: SettingsConnectionProxy = proxy(conn, path).await?;
+    let settings = c.get_settings().await?;
+    let field = |key: &str| {
+        settings
+            .get("connection")
+            .and_then(|c| c.get(key))
+            .and_then(|v| String::try_from(&**v).ok())
+            .unwrap_or_default()
+    };
+    Ok(SavedConnection {
+        id: field("id"),
+        kind: kind_name(&field("type")),
+        path: path.to_string(),
+    })
+}
+
+/// Everything under `iwwc.network`. Objects that vanish while being read
+/// are skipped; the signal that removed them triggers another read.
+async fn read(conn: &zbus::Connection) -> zbus::Result<Network> {
+    let nm: NetworkManagerProxy = proxy(conn, PATH).await?;
+    let mut net = Network {
+        running: true,
+        state: nm.state().await?,
+        primary: nm.primary_connection().await?.to_string(),
+        ..Default::default()
+    };
+    for path in nm.active_connections().await? {
+        if let Ok(a) = read_active(conn, &path).await {
+            net.active.push(a);
+        }
+    }
+    let mut aps = Vec::new();
+    for path in nm.get_devices().await? {
+        if let Ok(found) = read_access_points(conn, &path).await {
+            aps.extend(found);
+        }
+    }
+    net.access_points = merge_access_points(aps);
+    let settings: SettingsProxy = proxy(conn, SETTINGS_PATH).await?;
+    for path in settings.list_connections().await? {
+        if let Ok(s) = read_saved(conn, &path).await {
+            net.saved.push(s);
+        }
+    }
+    Ok(net)
+}
+
+async fn publish(output: &mut Sender<Message>, net: Network) {
+    if crate::config::smart::update_network(|n| *n = net) {
+        let _ = output
+            .send(Message::SmartRefresh(vec!["iwwc.network".into()]))
+            .await;
+    }
+}
+
+async fn watch(output: &mut Sender<Message>) -> zbus::Result<()> {
+    let conn = crate::dbus::system().await?;
+    let rule = zbus::MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .sender(NM)?
+        .path_namespace(PATH)?
+        .build();
+    let signals = zbus::MessageStream::for_match_rule(rule, conn, None).await?;
+    let dbus = zbus::fdo::DBusProxy::new(conn).await?;
+    let owner_changes = dbus
+        .receive_name_owner_changed_with_args(&[(0, NM)])
+        .await?;
+    let mut events = futures::stream::select(signals.map(|_| ()), owner_changes.map(|_| ()));
+
+    publish(output, read(conn).await.unwrap_or_default()).await;
+    while events.next().await.is_some() {
+        while let Ok(Some(())) = tokio::time::timeout(BURST, events.next()).await {}
+        publish(output, read(conn).await.unwrap_or_default()).await;
+    }
+    Ok(())
+}
+
+fn network_stream() -> impl Stream<Item = Message> {
+    iced::stream::channel(16, async |mut output| {
+        loop {
+            if let Err(e) = watch(&mut output).await {
+                log::debug!("network: {e}");
+            }
+            publish(&mut output, Network::default()).await;
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        }
+    })
+}
+
+pub fn subscription() -> Subscription<Message> {
+    Subscription::run(network_stream)
+}
+
+async fn run(conn: &zbus::Connection, command: NetworkCommand) -> zbus::Result<()> {
+    let net = read(conn).await?;
+    let nm: NetworkManagerProxy = proxy(conn, PATH).await?;
+    let root = ObjectPath::from_static_str_unchecked("/");
+    match command {
+        NetworkCommand::Up(name) => {
+            let saved = net
+                .lookup(&name)
+                .ok_or_else(|| zbus::Error::Failure(format!("no connection \"{name}\"")))?;
+            let path = ObjectPath::try_from(saved.path.as_str())?;
+            nm.activate_connection(&path, &root, &root).await?;
+        }
+        NetworkCommand::Down(name) => {
+            let settings = net.lookup(&name).map(|s| s.path.as_str());
+            let active = net
+                .active
+                .iter()
+                .find(|a| a.id == name || Some(a.settings.as_str()) == settings)
+                .ok_or_else(|| zbus::Error::Failure(format!("\"{name}\" is not active")))?;
+            nm.deactivate_connection(&ObjectPath::try_from(active.path.as_str())?)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Brings a saved connection (an id or a key from
+/// `iwwc.network.connections`) up or down. NetworkManager picks the device.
+pub async fn control(command: NetworkCommand) -> zbus::Result<()> {
+    run(crate::dbus::system().await?, command).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use zbus::zvariant::Value;
+
+    /// Stand-in NetworkManager objects, served over a private connection.
+    struct Manager {
+        calls: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[zbus::interface(name = "org.freedesktop.NetworkManager")]
+    impl Manager {
+        fn activate_connection(
+            &self,
+            connection: ObjectPath<'_>,
+            _device: ObjectPath<'_>,
+            _specific_object: ObjectPath<'_>,
+        ) -> OwnedObjectPath {
+            self.calls.lock().unwrap().push(format!("up {connection}"));
+            OwnedObjectPath::try_from("/org/freedesktop/NetworkManager/ActiveConnection/9").unwrap()
+        }
+        fn deactivate_connection(&self, active_connection: ObjectPath<'_>) {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("down {active_connection}"));
+        }
+        fn get_devices(&self) -> Vec<OwnedObjectPath> {
+            vec![path("Devices/1"), path("Devices/2")]
+        }
+        #[zbus(property)]
+        fn state(&self) -> u32 {
+            70
+        }
+        #[zbus(property)]
+        fn primary_connection(&self) -> OwnedObjectPath {
+            path("ActiveConnection/1")
+        }
+        #[zbus(property)]
+        fn active_connections(&self) -> Vec<OwnedObjectPath> {
+            vec![path("ActiveConnection/1"), path("ActiveConnection/2")]
+        }
+    }
+
+    struct ActiveObj {
+        id: &'static str,
+        kind: &'static str,
+        vpn: bool,
+        device: Option<&'static str>,
+        settings: &'static str,
+    }
+
+    #[zbus::interface(name = "org.freedesktop.NetworkManager.Connection.Active")]
+    impl ActiveObj {
+        #[zbus(property)]
+        fn id(&self) -> String {
+            self.id.into()
+        }
+        #[zbus(property, name = "Type")]
+        fn kind(&self) -> String {
+            self.kind.into()
+        }
+        #[zbus(property)]
+        fn vpn(&self) -> bool {
+            self.vpn
+        }
+        #[zbus(property)]
+        fn devices(&self) -> Vec<OwnedObjectPath> {
+            self.device.into_iter().map(path).collect()
+        }
+        #[zbus(property)]
+        fn connection(&self) -> OwnedObjectPath {
+            path(self.settings)
+        }
+    }
+
+    struct DeviceObj {
+        interface: &'static str,
+        kind: u32,
+    }
+
+    #[zbus::interface(name = "org.freedesktop.NetworkManager.Device")]
+    impl DeviceObj {
+        #[zbus(property)]
+        fn interface(&self) -> String {
+            self.interface.into()
+        }
+        #[zbus(property)]
+        fn device_type(&self) -> u32 {
+            self.kind
+        }
+    }
+
+    struct WirelessObj;
+
+    #[zbus::interface(name = "org.freedesktop.NetworkManager.Device.Wireless")]
+    impl WirelessObj {
+        #[zbus(property)]
+        fn access_points(&self) -> Vec<OwnedObjectPath> {
+            vec![path("AccessPoint/1"), path("AccessPoint/2")]
+        }
+        #[zbus(property)]
+        fn active_access_point(&self) -> OwnedObjectPath {
+            path("AccessPoint/1")
+        }
+    }
+
+    struct ApObj {
+        ssid: &'static str,
+        strength: u8,
+        rsn: u32,
+    }
+
+    #[zbus::interface(name = "org.freedesktop.NetworkManager.AccessPoint")]
+    impl ApObj {
+        #[zbus(property)]
+        fn ssid(&self) -> Vec<u8> {
+            self.ssid.as_bytes().to_vec()
+        }
+        #[zbus(property)]
+        fn strength(&self) -> u8 {
+            self.strength
+        }
+        #[zbus(property)]
+        fn frequency(&self) -> u32 {
+            5180
+        }
+        #[zbus(property)]
+        fn flags(&self) -> u32 {
+            0
+        }
+        #[zbus(property)]
+        fn wpa_flags(&self) -> u32 {
+            0
+        }
+        #[zbus(property)]
+        fn rsn_flags(&self) -> u32 {
+            self.rsn
+        }
+    }
+
+    struct SettingsObj;
+
+    #[zbus::interface(name = "org.freedesktop.NetworkManager.Settings")]
+    impl SettingsObj {
+        fn list_connections(&self) -> Vec<OwnedObjectPath> {
+            vec![path("Settings/1"), path("Settings/2"), path("Settings/3")]
+        }
+    }
+
+    struct SavedObj {
+        id: &'static str,
+        kind: &'static str,
+    }
+
+    #[zbus::interface(name = "org.freedesktop.NetworkManager.Settings.Connection")]
+    impl SavedObj {
+        fn get_settings(&self) -> HashMap<String, HashMap<String, OwnedValue>> {
+            let owned = |s: &str| OwnedValue::try_from(Value::from(s.to_string())).unwrap();
+            HashMap::from([(
+                "connection".to_string(),
+                HashMap::from([
+                    ("id".to_string(), owned(self.id)),
+                    ("type".to_string(), owned(self.kind)),
+                ]),
+            )])
+        }
+    }
+
+    fn path(rel: &str) -> OwnedObjectPath {
+        OwnedObjectPath::try_from(format!("{PATH}/{rel}")).unwrap()
+    }
+
+    async fn stand_in(calls: Arc<Mutex<Vec<String>>>) -> (zbus::Connection, zbus::Connection) {
+        let p = |rel: &str| format!("{PATH}/{rel}");
+        let (a, b) = std::os::unix::net::UnixStream::pair().unwrap();
+        let server = zbus::connection::Builder::unix_stream(a)
+            .server(zbus::Guid::generate())
+            .unwrap()
+            .p2p()
+            .serve_at(PATH, Manager { calls })
+            .unwrap()
+            .serve_at(
+                p("ActiveConnection/1"),
+                ActiveObj {
+                    id: "home",
+                    kind: "802-11-wireless",
+                    vpn: false,
+                    device: Some("Devices/2"),
+                    settings: "Settings/1",
+                },
+            )
+            .unwrap()
+            .serve_at(
+                p("ActiveConnection/2"),
+                ActiveObj {
+                    id: "work",
+                    kind: "wireguard",
+                    vpn: false,
+                    device: None,
+                    settings: "Settings/3",
+                },
+            )
+            .unwrap()
+            .serve_at(
+                p("Devices/1"),
+                DeviceObj {
+                    interface: "enp3s0",
+                    kind: 1,
+                },
+            )
+            .unwrap()
+            .serve_at(
+                p("Devices/2"),
+                DeviceObj {
+                    interface: "wlan0",
+                    kind: DEVICE_WIFI,
+                },
+            )
+            .unwrap()
+            .serve_at(p("Devices/2"), WirelessObj)
+            .unwrap()
+            .serve_at(
+                p("AccessPoint/1"),
+                ApObj {
+                    ssid: "home",
+                    strength: 64,
+                    rsn: 0x188,
+                },
+            )
+            .unwrap()
+            .serve_at(
+                p("AccessPoint/2"),
+                ApObj {
+                    ssid: "cafe",
+                    strength: 80,
+                    rsn: 0,
+                },
+            )
+            .unwrap()
+            .serve_at(SETTINGS_PATH, SettingsObj)
+            .unwrap()
+            .serve_at(
+                p("Settings/1"),
+                SavedObj {
+                    id: "home",
+                    kind: "802-11-wireless",
+                },
+            )
+            .unwrap()
+            .serve_at(
+                p("Settings/2"),
+                SavedObj {
+                    id: "Wired connection 1",
+                    kind: "802-3-ethernet",
+                },
+            )
+            .unwrap()
+            .serve_at(
+                p("Settings/3"),
+                SavedObj {
+                    id: "work",
+                    kind: "wireguard",
+                },
+            )
+            .unwrap()
+            .build();
+        let client = zbus::connection::Builder::unix_stream(b).p2p().build();
+        futures::try_join!(server, client).unwrap()
+    }
+
+    #[tokio::test]
+    async fn reads_network_manager_state() {
+        let (_server, client) = stand_in(Default::default()).await;
+        let net = read(&client).await.unwrap();
+        let vals = net.values();
+        let get = |k: &str| vals.iter().find(|(key, _)| key == k).unwrap().1.clone();
+        let s = |v: &str| crate::config::types::VarValue::Str(v.into());
+        assert_eq!(get("iwwc.network.primary"), s("home"));
+        assert_eq!(get("iwwc.network.type"), s("wifi"));
+        assert_eq!(get("iwwc.network.device"), s("wlan0"));
+        assert_eq!(get("iwwc.network.ssid"), s("home"));
+        assert_eq!(
+            get("iwwc.network.strength"),
+            crate::config::types::VarValue::Int(64)
+        );
+        assert_eq!(get("iwwc.network.vpn_name"), s("work"));
+        assert_eq!(get("iwwc.network.access_points"), s("cafe home"));
+        assert_eq!(
+            get("iwwc.network.ap.home.secure"),
+            crate::config::types::VarValue::Bool(true)
+        );
+        assert_eq!(
+            get("iwwc.network.connections"),
+            s("home wired_connection_1 work")
+        );
+    }
+
+    #[tokio::test]
+    async fn activates_saved_connections() {
+        let calls: Arc<Mutex<Vec<String>>> = Default::default();
+        let (_server, client) = stand_in(calls.clone()).await;
+        run(&client, NetworkCommand::Up("wired_connection_1".into()))
+            .await
+            .unwrap();
+        run(&client, NetworkCommand::Down("work".into()))
+            .await
+            .unwrap();
+        assert!(
+            run(&client, NetworkCommand::Up("cafe".into()))
+                .await
+                .is_err()
+        );
+        assert!(
+            run(&client, NetworkCommand::Down("Wired connection 1".into()))
+                .await
+                .is_err()
+        );
+        assert_eq!(
+            *calls.lock().unwrap(),
+            [
+                format!("up {PATH}/Settings/2"),
+                format!("down {PATH}/ActiveConnection/2"),
+            ]
+        );
+    }
+}
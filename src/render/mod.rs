@@ -4,7 +4,22 @@ pub mod revealer;
 
 #[derive(Debug, Clone)]
 pub enum UiMessage {
-    Action(String),
+    Action(crate::config::resolved::ResolvedAction),
+    Pointer {
+        bindings: Vec<ResolvedBinding>,
+        trigger: PointerTrigger,
+        delta: f32,
+        /// The button pressed, so double clicks only pair up on the same one.
+        source: Option<ClickSource>,
+    },
+    InputChanged {
+        id: String,
+        var: String,
+        value: String,
+    },
+    InputSubmit {
+        id: String,
+    },
     NotifAction {
         id: u32,
         key: String,
@@ -20,6 +35,10 @@ pub enum UiMessage {
         delta: f32,
     },
     TrayHover(usize),
+    /// Toplevel handles from `iwwc.toplevels`.
+    TaskbarActivate(u32),
+    TaskbarMinimize(u32),
+    TaskbarClose(u32),
     MenuClick {
         level: usize,
         id: i32,
@@ -32,20 +51,43 @@ pub enum UiMessage {
         level: usize,
     },
     MenuDismiss,
+    /// Input no binding takes, e.g. a sideways scroll.
+    Ignored,
+}
+
+/// A button on a surface: the window plus where the button is declared.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClickSource {
+    pub window: iced::window::Id,
+    pub file: std::sync::Arc<str>,
+    pub offset: usize,
 }
 
+/// Icons of the taskbar's windows by app id, icon size and theme, looked up
+/// in the background as windows appear rather than on every view. A window
+/// whose icon is still being looked up shows none.
+pub type TaskbarIcons =
+    std::collections::HashMap<(String, u16, String), Option<std::path::PathBuf>>;
+
 pub struct RenderCtx<'a> {
     pub tray: &'a [crate::tray::types::TrayItem],
     pub window: iced::window::Id,
+    pub focused: Option<&'a ResolvedButton>,
+    pub toplevels: &'a [crate::config::smart::toplevels::Window],
+    pub taskbar_icons: &'a TaskbarIcons,
+    /// Output the window is on, once the compositor said.
+    pub output: Option<&'a str>,
 }
 
+use crate::config::primitives::PointerTrigger;
 use crate::config::resolved::{
-    ResolvedApptraySettings, ResolvedButton, ResolvedColumn, ResolvedContainer, ResolvedElement,
-    ResolvedEvent, ResolvedRevealer, ResolvedRow, ResolvedText, ResolvedWidget,
+    ResolvedAction, ResolvedApptraySettings, ResolvedBinding, ResolvedButton, ResolvedColumn,
+    ResolvedContainer, ResolvedElement, ResolvedEvent, ResolvedImage, ResolvedInput,
+    ResolvedRevealer, ResolvedRow, ResolvedTaskbarSettings, ResolvedText, ResolvedWidget,
 };
 use crate::tray::types::TrayIcon;
 use iced::Element;
-use iced::widget::{Column, Row, button, container, text};
+use iced::widget::{Column, Row, button, container, text, text_input};
 
 pub fn view_widget(w: &ResolvedWidget, ctx: &RenderCtx) -> Element<'static, UiMessage> {
     match &w.child {
@@ -54,6 +96,121 @@ pub fn view_widget(w: &ResolvedWidget, ctx: &RenderCtx) -> Element<'static, UiMe
     }
 }
 
+/// Visits every element that is currently shown, skipping the children of
+/// collapsed revealers.
+fn walk_visible<'a>(el: &'a ResolvedElement, visit: &mut impl FnMut(&'a ResolvedElement)) {
+    visit(el);
+    match el {
+        ResolvedElement::Container(c) => walk_visible(&c.child, visit),
+        ResolvedElement::Revealer(r) if r.active => walk_visible(&r.child, visit),
+        ResolvedElement::Event(e) => walk_visible(&e.child, visit),
+        ResolvedElement::Row(r) => r.children.iter().for_each(|c| walk_visible(c, visit)),
+        ResolvedElement::Column(c) => c.children.iter().for_each(|c| walk_visible(c, visit)),
+        _ => {}
+    }
+}
+
+/// Buttons reachable by keyboard focus, in traversal order. Buttons inside
+/// collapsed revealers and buttons without any action are skipped.
+pub fn focusables(w: &ResolvedWidget) -> Vec<&ResolvedButton> {
+    let mut out = Vec::new();
+    if let Some(child) = &w.child {
+        walk_visible(child, &mut |el| {
+            if let ResolvedElement::Button(b) = el
+                && (b.action.is_some() || !b.bindings.is_empty())
+            {
+                out.push(&**b);
+            }
+        });
+    }
+    out
+}
+
+/// Where a focusable button is declared. Focus is kept by this rather than
+/// by index, since a revealer toggling shifts the indices in [`focusables`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FocusId {
+    file: std::sync::Arc<str>,
+    offset: usize,
+}
+
+impl FocusId {
+    pub fn of(b: &ResolvedButton) -> FocusId {
+        FocusId {
+            file: b.span.source.label.clone(),
+            offset: b.span.span.offset(),
+        }
+    }
+}
+
+/// The focused button, `None` once it is hidden or gone.
+pub fn focused<'a>(w: &'a ResolvedWidget, id: &FocusId) -> Option<&'a ResolvedButton> {
+    focusables(w).into_iter().find(|b| FocusId::of(b) == *id)
+}
+
+/// The button `step` places from the focused one, wrapping around. With
+/// nothing focused, or the focused button hidden, a step forward starts at
+/// the first button and a step back at the last.
+pub fn step_focus(w: &ResolvedWidget, from: Option<&FocusId>, step: isize) -> Option<FocusId> {
+    let buttons = focusables(w);
+    if buttons.is_empty() {
+        return None;
+    }
+    let len = buttons.len() as isize;
+    let at = from.and_then(|id| buttons.iter().position(|b| FocusId::of(b) == *id));
+    let next = match at {
+        Some(i) => (i as isize + step).rem_euclid(len),
+        None if step > 0 => 0,
+        None => len - 1,
+    };
+    Some(FocusId::of(buttons[next as usize]))
+}
+
+/// What Enter or Space runs on a focused button: its action, else its
+/// unmodified left-click binding.
+pub fn press_action(b: &ResolvedButton) -> Option<ResolvedAction> {
+    b.action.clone().or_else(|| {
+        b.bindings
+            .iter()
+            .find(|x| x.trigger == PointerTrigger::Left && x.modifiers.is_empty())
+            .map(|x| x.action.clone())
+    })
+}
+
+/// Settings of every taskbar in the widget, shown or not.
+pub fn taskbars(w: &ResolvedWidget) -> Vec<&ResolvedTaskbarSettings> {
+    fn walk<'a>(el: &'a ResolvedElement, out: &mut Vec<&'a ResolvedTaskbarSettings>) {
+        match el {
+            ResolvedElement::Taskbar(s) => out.push(&**s),
+            ResolvedElement::Container(c) => walk(&c.child, out),
+            ResolvedElement::Revealer(r) => walk(&r.child, out),
+            ResolvedElement::Event(e) => walk(&e.child, out),
+            ResolvedElement::Button(b) => walk(&b.child, out),
+            ResolvedElement::Row(r) => r.children.iter().for_each(|c| walk(c, out)),
+            ResolvedElement::Column(c) => c.children.iter().for_each(|c| walk(c, out)),
+            _ => {}
+        }
+    }
+    let mut out = Vec::new();
+    if let Some(child) = &w.child {
+        walk(child, &mut out);
+    }
+    out
+}
+
+/// Inputs currently shown in the widget, in traversal order.
+pub fn inputs(w: &ResolvedWidget) -> Vec<&ResolvedInput> {
+    let mut out = Vec::new();
+    if let Some(child) = &w.child {
+        walk_visible(child, &mut |el| {
+            if let ResolvedElement::Input(i) = el {
+                out.push(&**i);
+            }
+        });
+    }
+    out
+}
+
 fn view_element(el: &ResolvedElement, ctx: &RenderCtx) -> Element<'static, UiMessage> {
     match el {
         ResolvedElement::Container(c) => build_container(c, ctx),
@@ -62,7 +219,10 @@ fn view_element(el: &ResolvedElement, ctx: &RenderCtx) -> Element<'static, UiMes
         ResolvedElement::Row(r) => build_row(r, ctx),
         ResolvedElement::Column(c) => build_column(c, ctx),
         ResolvedElement::Text(t) => build_text(t, ctx),
+        ResolvedElement::Input(i) => build_input(i, ctx),
+        ResolvedElement::Image(i) => build_image(i),
         ResolvedElement::Apptray(s) => build_apptray(s, ctx),
+        ResolvedElement::Taskbar(s) => build_taskbar(s, ctx),
         ResolvedElement::Event(e) => build_event(e, ctx),
     }
 }
@@ -90,6 +250,66 @@ fn build_text(t: &ResolvedText, _ctx: &RenderCtx) -> Element<'static, UiMessage>
     el.into()
 }
 
+fn build_image(i: &ResolvedImage) -> Element<'static, UiMessage> {
+    let w = i.w.unwrap_or(iced::Length::Shrink);
+    let h = i.h.unwrap_or(iced::Length::Shrink);
+    let fit = i.fit.unwrap_or(iced::ContentFit::Contain);
+    match &i.path {
+        Some(p) if p.extension().and_then(|e| e.to_str()) == Some("svg") => {
+            iced::widget::svg(iced::widget::svg::Handle::from_path(p))
+                .width(w)
+                .height(h)
+                .content_fit(fit)
+                .into()
+        }
+        Some(p) => iced::widget::image(iced::widget::image::Handle::from_path(p))
+            .width(w)
+            .height(h)
+            .content_fit(fit)
+            .into(),
+        None => iced::widget::Space::new().width(w).height(h).into(),
+    }
+}
+
+fn build_input(i: &ResolvedInput, _ctx: &RenderCtx) -> Element<'static, UiMessage> {
+    let (id, var) = (i.id.clone(), i.var.clone());
+    let mut el = text_input(i.placeholder.as_deref().unwrap_or_default(), &i.value)
+        .id(i.id.clone())
+        .on_input(move |value| UiMessage::InputChanged {
+            id: id.clone(),
+            var: var.clone(),
+            value,
+        })
+        .on_submit(UiMessage::InputSubmit { id: i.id.clone() })
+        .secure(i.password.unwrap_or(false));
+    if let Some(w) = i.w {
+        el = el.width(w);
+    }
+    if let Some(p) = i.padding {
+        el = el.padding(p);
+    }
+    if let Some(size) = i.size {
+        el = el.size(size);
+    }
+    if let Some(f) = i.font {
+        el = el.font(f);
+    }
+    let base = i.style;
+    let hover = i.style_hover;
+    let focus = i.style_focus;
+    el = el.style(move |theme, status| {
+        let chosen = match status {
+            text_input::Status::Focused { .. } => focus.as_ref().or(base.as_ref()),
+            text_input::Status::Hovered => hover.as_ref().or(base.as_ref()),
+            text_input::Status::Active | text_input::Status::Disabled => base.as_ref(),
+        };
+        chosen
+            .cloned()
+            .unwrap_or_else(|| text_input::default(theme, status))
+    });
+    el.into()
+}
+
 fn build_container(c: &ResolvedContainer, ctx: &RenderCtx) -> Element<'static, UiMessage> {
     let mut el = container(view_element(&c.child, ctx));
     if let Some(w) = c.w {
@@ -135,6 +355,20 @@ fn build_event(e: &ResolvedEvent, ctx: &RenderCtx) -> Element<'static, UiMessage
             EventType::OnHover => area.on_enter(msg),
             EventType::OnHoverExit => area.on_exit(msg),
             EventType::RightClick => area.on_right_press(msg),
+            EventType::MiddleClick => area.on_middle_press(msg),
+            EventType::DoubleClick => area.on_double_click(msg),
+            EventType::ScrollUp | EventType::ScrollDown => {
+                let trigger = if e.evtype == EventType::ScrollUp {
+                    PointerTrigger::ScrollUp
+                } else {
+                    PointerTrigger::ScrollDown
+                };
+                area.on_scroll(scroll_msg(vec![ResolvedBinding {
+                    trigger,
+                    modifiers: iced::keyboard::Modifiers::empty(),
+                    action: action.clone(),
+                }]))
+            }
             _ => area,
         };
     }
@@ -155,13 +389,45 @@ fn build_button(b: &ResolvedButton, ctx: &RenderCtx) -> Element<'static, UiMessa
     if let Some(clip) = b.clip {
         el = el.clip(clip);
     }
-    el = el.on_press_maybe(b.action.clone().map(UiMessage::Action));
+    let bindings = (!b.bindings.is_empty()).then(|| {
+        let mut all = b.bindings.clone();
+        if let Some(action) = &b.action {
+            all.push(ResolvedBinding {
+                trigger: PointerTrigger::Left,
+                modifiers: iced::keyboard::Modifiers::empty(),
+                action: action.clone(),
+            });
+        }
+        all
+    });
+    el = match &bindings {
+        None => el.on_press_maybe(b.action.clone().map(UiMessage::Action)),
+        // a double click starts as a press, so `action:double` alone needs one too
+        Some(all) => el.on_press_maybe(
+            (has_trigger(all, PointerTrigger::Left) || has_trigger(all, PointerTrigger::Double))
+                .then(|| UiMessage::Pointer {
+                    bindings: all.clone(),
+                    trigger: PointerTrigger::Left,
+                    delta: 0.0,
+                    source: Some(ClickSource {
+                        window: ctx.window,
+                        file: b.span.source.label.clone(),
+                        offset: b.span.span.offset(),
+                    }),
+                }),
+        ),
+    };
 
     let base = b.style;
     let hover = b.style_hover;
     let active = b.style_active;
     let disabled = b.style_disabled;
+    let focused = ctx.focused.is_some_and(|f| std::ptr::eq(f, b));
     el = el.style(move |_theme, status| {
+        let status = match status {
+            button::Status::Active if focused => button::Status::Hovered,
+            other => other,
+        };
         let chosen = match status {
             button::Status::Hovered => hover.as_ref().or(base.as_ref()),
             button::Status::Pressed => active.as_ref().or(base.as_ref()),
@@ -170,7 +436,54 @@ fn build_button(b: &ResolvedButton, ctx: &RenderCtx) -> Element<'static, UiMessa
         };
         chosen.cloned().unwrap_or_default()
     });
-    el.into()
+    let Some(all) = bindings else {
+        return el.into();
+    };
+    let mut area = iced::widget::mouse_area(el);
+    if has_trigger(&all, PointerTrigger::Right) {
+        area = area.on_right_press(pointer_msg(&all, PointerTrigger::Right));
+    }
+    if has_trigger(&all, PointerTrigger::Middle) {
+        area = area.on_middle_press(pointer_msg(&all, PointerTrigger::Middle));
+    }
+    if has_trigger(&all, PointerTrigger::ScrollUp) || has_trigger(&all, PointerTrigger::ScrollDown)
+    {
+        area = area.on_scroll(scroll_msg(all));
+    }
+    area.into()
+}
+
+fn has_trigger(bindings: &[ResolvedBinding], trigger: PointerTrigger) -> bool {
+    bindings.iter().any(|b| b.trigger == trigger)
+}
+
+fn pointer_msg(bindings: &[ResolvedBinding], trigger: PointerTrigger) -> UiMessage {
+    UiMessage::Pointer {
+        bindings: bindings.to_vec(),
+        trigger,
+        delta: 0.0,
+        source: None,
+    }
+}
+
+fn scroll_msg(bindings: Vec<ResolvedBinding>) -> impl Fn(iced::mouse::ScrollDelta) -> UiMessage {
+    move |delta| {
+        let y = scroll_y(delta);
+        // a purely horizontal swipe is neither up nor down
+        if y == 0.0 {
+            return UiMessage::Ignored;
+        }
+        UiMessage::Pointer {
+            bindings: bindings.clone(),
+            trigger: if y < 0.0 {
+                PointerTrigger::ScrollDown
+            } else {
+                PointerTrigger::ScrollUp
+            },
+            delta: y,
+            source: None,
+        }
+    }
 }
 
 fn build_row(r: &ResolvedRow, ctx: &RenderCtx) -> Element<'static, UiMessage> {
@@ -303,6 +616,84 @@ fn build_apptray(s: &ResolvedApptraySettings, ctx: &RenderCtx) -> Element<'stati
     }
 }
 
+fn build_taskbar(s: &ResolvedTaskbarSettings, ctx: &RenderCtx) -> Element<'static, UiMessage> {
+    let size = s.icon_size;
+    let mut items: Vec<Element<'static, UiMessage>> = Vec::new();
+    for w in ctx.toplevels {
+        // windows the compositor places on no output show everywhere
+        if s.own_output
+            && let Some(output) = ctx.output
+            && !w.outputs.is_empty()
+            && !w.outputs.iter().any(|o| o == output)
+        {
+            continue;
+        }
+        let key = (w.app_id.clone(), size as u16, s.icon_theme.clone());
+        let icon: Element<'static, UiMessage> =
+            match ctx.taskbar_icons.get(&key).and_then(Option::as_ref) {
+                Some(p) if p.extension().and_then(|e| e.to_str()) == Some("svg") => {
+                    iced::widget::svg(iced::widget::svg::Handle::from_path(p))
+                        .width(size)
+                        .height(size)
+                        .into()
+                }
+                Some(p) => iced::widget::image(iced::widget::image::Handle::from_path(p))
+                    .width(size)
+                    .height(size)
+                    .into(),
+                None => iced::widget::Space::new().width(size).height(size).into(),
+            };
+        let active = w.activated() && !w.minimized();
+        let icon: Element<'static, UiMessage> = match s.active_bg {
+            Some(bg) if active => iced::widget::container(icon)
+                .style(move |_| iced::widget::container::Style {
+                    background: Some(iced::Background::Color(bg)),
+                    ..Default::default()
+                })
+                .into(),
+            _ => icon,
+        };
+        // clicking the focused window hides it, like most taskbars
+        let left = if active {
+            UiMessage::TaskbarMinimize(w.handle)
+        } else {
+            UiMessage::TaskbarActivate(w.handle)
+        };
+        let area = iced::widget::mouse_area(icon)
+            .on_press(left)
+            .on_right_press(UiMessage::TaskbarMinimize(w.handle))
+            .on_middle_press(UiMessage::TaskbarClose(w.handle));
+        items.push(area.into());
+    }
+    let pad = s.padding;
+    let inner: Element<'static, UiMessage> = if s.vertical {
+        let mut col = Column::with_children(items).spacing(s.spacing);
+        if let Some(p) = pad {
+            col = col.padding(p);
+        }
+        col.into()
+    } else {
+        let mut row = Row::with_children(items).spacing(s.spacing);
+        if let Some(p) = pad {
+            row = row.padding(p);
+        }
+        row.into()
+    };
+    let bg = s.bg;
+    let border = s.border;
+    if bg.is_some() || border.is_some() {
+        iced::widget::container(inner)
+            .style(move |_| iced::widget::container::Style {
+                background: bg.map(iced::Background::Color),
+                border: border.unwrap_or_default(),
+                ..Default::default()
+            })
+            .into()
+    } else {
+        inner
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -342,6 +733,29 @@ mod tests {
             &RenderCtx {
                 tray: &[],
                 window: iced::window::Id::unique(),
+                focused: None,
+                toplevels: &[],
+                taskbar_icons: &TaskbarIcons::new(),
+                output: None,
+            },
+        );
+    }
+
+    #[test]
+    fn renders_images_and_missing_art() {
+        let rc = render_kdl(
+            "widget bar { child r1 }\nrow r1 {\n  children a b\n}\nimage a { src \"/nonexistent.png\"; w 24; h 24 }\nimage b { src \"/nonexistent.svg\"; fallback \"/nonexistent.svg\"; fit scale-down }",
+        );
+        let w = rc.widgets.get("bar").unwrap();
+        let _el = view_widget(
+            w,
+            &RenderCtx {
+                tray: &[],
+                window: iced::window::Id::unique(),
+                focused: None,
+                toplevels: &[],
+                taskbar_icons: &TaskbarIcons::new(),
+                output: None,
             },
         );
     }
@@ -357,6 +771,10 @@ mod tests {
             &RenderCtx {
                 tray: &[],
                 window: iced::window::Id::unique(),
+                focused: None,
+                toplevels: &[],
+                taskbar_icons: &TaskbarIcons::new(),
+                output: None,
             },
         );
     }
@@ -372,6 +790,10 @@ mod tests {
             &RenderCtx {
                 tray: &[],
                 window: iced::window::Id::unique(),
+                focused: None,
+                toplevels: &[],
+                taskbar_icons: &TaskbarIcons::new(),
+                output: None,
             },
         );
     }
@@ -385,6 +807,10 @@ mod tests {
             &RenderCtx {
                 tray: &[],
                 window: iced::window::Id::unique(),
+                focused: None,
+                toplevels: &[],
+                taskbar_icons: &TaskbarIcons::new(),
+                output: None,
             },
         );
     }
@@ -400,10 +826,53 @@ mod tests {
             &RenderCtx {
                 tray: &[],
                 window: iced::window::Id::unique(),
+                focused: None,
+                toplevels: &[],
+                taskbar_icons: &TaskbarIcons::new(),
+                output: None,
             },
         );
     }
 
+    #[test]
+    fn renders_button_with_bindings() {
+        let rc = render_kdl(
+            "widget bar { child btn }\nbutton btn { child t1; action \"echo l\"; action:middle \"echo m\"; action:scrollup:ctrl \"echo u\"; action:double \"echo d\" }\ntext t1",
+        );
+        let w = rc.widgets.get("bar").unwrap();
+        let _el = view_widget(
+            w,
+            &RenderCtx {
+                tray: &[],
+                window: iced::window::Id::unique(),
+                focused: None,
+                toplevels: &[],
+                taskbar_icons: &TaskbarIcons::new(),
+                output: None,
+            },
+        );
+    }
+
+    #[test]
+    fn scroll_msg_picks_direction_and_delta() {
+        let f = scroll_msg(Vec::new());
+        match f(iced::mouse::ScrollDelta::Lines { x: 0.0, y: -2.0 }) {
+            UiMessage::Pointer { trigger, delta, .. } => {
+                assert_eq!(trigger, PointerTrigger::ScrollDown);
+                assert_eq!(delta, -2.0);
+            }
+            other => panic!("expected pointer message, got {other:?}"),
+        }
+        match f(iced::mouse::ScrollDelta::Pixels { x: 0.0, y: 12.0 }) {
+            UiMessage::Pointer { trigger, .. } => assert_eq!(trigger, PointerTrigger::ScrollUp),
+            other => panic!("expected pointer message, got {other:?}"),
+        }
+        assert!(matches!(
+            f(iced::mouse::ScrollDelta::Pixels { x: 30.0, y: 0.0 }),
+            UiMessage::Ignored
+        ));
+    }
+
     #[test]
     fn renders_button_minimal() {
         let rc = render_kdl("widget bar { child btn }\nbutton btn { child t1 }\ntext t1");
@@ -413,6 +882,10 @@ mod tests {
             &RenderCtx {
                 tray: &[],
                 window: iced::window::Id::unique(),
+                focused: None,
+                toplevels: &[],
+                taskbar_icons: &TaskbarIcons::new(),
+                output: None,
             },
         );
     }
@@ -428,6 +901,10 @@ mod tests {
             &RenderCtx {
                 tray: &[],
                 window: iced::window::Id::unique(),
+                focused: None,
+                toplevels: &[],
+                taskbar_icons: &TaskbarIcons::new(),
+                output: None,
             },
         );
     }
@@ -443,6 +920,10 @@ mod tests {
             &RenderCtx {
                 tray: &[],
                 window: iced::window::Id::unique(),
+                focused: None,
+                toplevels: &[],
+                taskbar_icons: &TaskbarIcons::new(),
+                output: None,
             },
         );
     }
@@ -466,6 +947,45 @@ mod tests {
             &RenderCtx {
                 tray: &items,
                 window: iced::window::Id::unique(),
+                focused: None,
+                toplevels: &[],
+                taskbar_icons: &TaskbarIcons::new(),
+                output: None,
+            },
+        );
+    }
+
+    #[test]
+    fn renders_taskbar_with_windows() {
+        use crate::config::smart::toplevels::{ACTIVATED, Window};
+        let rc = render_kdl(
+            "widget bar { child taskbar }\ntaskbar { icon_size 20; active_bg 44475a; own_output #true }",
+        );
+        let w = rc.widgets.get("bar").unwrap();
+        let windows = vec![
+            Window {
+                handle: 7,
+                app_id: "foot".into(),
+                title: "~".into(),
+                state: ACTIVATED,
+                outputs: vec!["DP-1".into()],
+            },
+            Window {
+                handle: 8,
+                app_id: "".into(),
+                title: "untitled".into(),
+                state: 0,
+                outputs: vec!["eDP-1".into()],
+            },
+        ];
+        let _el = view_widget(
+            w,
+            &RenderCtx {
+                tray: &[],
+                window: iced::window::Id::unique(),
+                focused: None,
+                toplevels: &windows,
+                output: Some("DP-1"),
             },
         );
     }
@@ -481,6 +1001,10 @@ mod tests {
             &RenderCtx {
                 tray: &[],
                 window: iced::window::Id::unique(),
+                focused: None,
+                toplevels: &[],
+                taskbar_icons: &TaskbarIcons::new(),
+                output: None,
             },
         );
     }
@@ -496,7 +1020,133 @@ mod tests {
             &RenderCtx {
                 tray: &[],
                 window: iced::window::Id::unique(),
+                focused: None,
+                toplevels: &[],
+                taskbar_icons: &TaskbarIcons::new(),
+                output: None,
+            },
+        );
+    }
+
+    #[test]
+    fn renders_scroll_and_middle_events() {
+        let rc = render_kdl(
+            "widget bar { child r1 }\nrow r1 { children e1 e2 }\nevent e1 { type scrollup; action \"true\"; child t1 }\nevent e2 { type middleclick; action \"true\"; child t1 }\ntext t1",
+        );
+        let w = rc.widgets.get("bar").unwrap();
+        let _el = view_widget(
+            w,
+            &RenderCtx {
+                tray: &[],
+                window: iced::window::Id::unique(),
+                focused: None,
+                toplevels: &[],
+                taskbar_icons: &TaskbarIcons::new(),
+                output: None,
+            },
+        );
+    }
+
+    #[test]
+    fn focusables_skip_hidden_and_inert_buttons() {
+        let rc = render_kdl(
+            "widget bar { child r1 }\nrow r1 { children a b rv c }\nbutton a { child t1; action \"true\" }\nbutton b { child t1 }\nrevealer rv { active #false; child h }\nbutton h { child t1; action \"true\" }\nbutton c { child t1; action:middle \"true\" }\ntext t1",
+        );
+        let w = rc.widgets.get("bar").unwrap();
+        let found = focusables(w);
+        assert_eq!(found.len(), 2);
+        assert!(found[0].action.is_some());
+        assert_eq!(found[1].bindings.len(), 1);
+    }
+
+    const FOCUS_KDL: &str = "widget bar { child r1 }\nrow r1 { children a rv c }\nbutton a { child t1; action \"echo a\" }\nrevealer rv { child b }\nbutton b { child t1; action \"echo b\" }\nbutton c { child t1; action \"echo c\"; action:shift \"echo s\" }\ntext t1";
+
+    fn step_command(w: &ResolvedWidget, from: Option<&FocusId>, step: isize) -> Option<String> {
+        let id = step_focus(w, from, step)?;
+        let b = focused(w, &id)?;
+        press_action(b)?.command
+    }
+
+    #[test]
+    fn tab_and_shift_tab_wrap_around() {
+        let rc = render_kdl(FOCUS_KDL);
+        let w = rc.widgets.get("bar").unwrap();
+        let ids: Vec<FocusId> = focusables(w).into_iter().map(FocusId::of).collect();
+        assert_eq!(step_command(w, None, 1).as_deref(), Some("echo a"));
+        assert_eq!(step_command(w, None, -1).as_deref(), Some("echo c"));
+        assert_eq!(step_command(w, Some(&ids[0]), 1).as_deref(), Some("echo b"));
+        assert_eq!(step_command(w, Some(&ids[2]), 1).as_deref(), Some("echo a"));
+        assert_eq!(
+            step_command(w, Some(&ids[0]), -1).as_deref(),
+            Some("echo c")
+        );
+    }
+
+    #[test]
+    fn focus_survives_revealer_toggle() {
+        let mut rc = render_kdl(FOCUS_KDL);
+        let w = rc.widgets.get_mut("bar").unwrap();
+        let b = FocusId::of(focusables(w)[1]);
+        let c = FocusId::of(focusables(w)[2]);
+        let Some(ResolvedElement::Row(row)) = w.child.as_deref_mut() else {
+            panic!("row expected");
+        };
+        let ResolvedElement::Revealer(rv) = &mut row.children[1] else {
+            panic!("revealer expected");
+        };
+        rv.active = false;
+        // still `c`, though it moved from index 2 to 1
+        assert_eq!(
+            focused(w, &c)
+                .and_then(press_action)
+                .and_then(|a| a.command)
+                .as_deref(),
+            Some("echo c")
+        );
+        assert_eq!(step_command(w, Some(&c), -1).as_deref(), Some("echo a"));
+        // a hidden button loses focus; Tab starts over
+        assert!(focused(w, &b).is_none());
+        assert_eq!(step_command(w, Some(&b), 1).as_deref(), Some("echo a"));
+    }
+
+    #[test]
+    fn enter_runs_action_or_plain_left_click() {
+        let rc = render_kdl(FOCUS_KDL);
+        let w = rc.widgets.get("bar").unwrap();
+        let buttons = focusables(w);
+        assert_eq!(
+            press_action(buttons[0]).and_then(|a| a.command).as_deref(),
+            Some("echo a")
+        );
+        // a modified click is not what Enter means
+        let rc = render_kdl(
+            "widget bar { child d }\nbutton d { child t1; action:shift \"echo s\" }\ntext t1",
+        );
+        let w = rc.widgets.get("bar").unwrap();
+        assert_eq!(focusables(w).len(), 1);
+        assert_eq!(press_action(focusables(w)[0]), None);
+    }
+
+    #[test]
+    fn renders_input_and_lists_visible_inputs() {
+        let rc = render_kdl(
+            "var q=\"hi\"\nvar open=#false\nwidget bar { child c1 }\ncolumn c1 { children i1 rv }\ninput i1 { var q; placeholder \"Search\"; password #true; style s1; style:focus s2 }\nrevealer rv { active \"${open}\"; child i2 }\ninput i2 { var q }\nstyle s1 { bg 000000 }\nstyle s2 { bg ffffff }",
+        );
+        let w = rc.widgets.get("bar").unwrap();
+        let _el = view_widget(
+            w,
+            &RenderCtx {
+                tray: &[],
+                window: iced::window::Id::unique(),
+                focused: None,
+                toplevels: &[],
+                taskbar_icons: &TaskbarIcons::new(),
+                output: None,
             },
         );
+        let found = inputs(w);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, "i1");
+        assert_eq!(found[0].value, "hi");
     }
 }
@@ -0,0 +1,397 @@
+use std::collections::HashMap;
+use std::os::fd::{AsFd, AsRawFd};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use futures::channel::mpsc::Sender;
+use futures::sink::SinkExt;
+use futures::stream::Stream;
+use iced::Subscription;
+use tokio::io::unix::AsyncFd;
+use wayland_client::backend::WaylandError;
+use wayland_client::protocol::{wl_output, wl_registry, wl_seat};
+use wayland_client::{Connection, Dispatch, Proxy, QueueHandle, event_created_child};
+use wayland_protocols::ext::foreign_toplevel_list::v1::client::ext_foreign_toplevel_handle_v1::{
+    self, ExtForeignToplevelHandleV1,
+};
+use wayland_protocols::ext::foreign_toplevel_list::v1::client::ext_foreign_toplevel_list_v1::{
+    self, ExtForeignToplevelListV1,
+};
+use wayland_protocols_wlr::foreign_toplevel::v1::client::zwlr_foreign_toplevel_handle_v1::{
+    self, ZwlrForeignToplevelHandleV1,
+};
+use wayland_protocols_wlr::foreign_toplevel::v1::client::zwlr_foreign_toplevel_manager_v1::{
+    self, ZwlrForeignToplevelManagerV1,
+};
+
+use crate::config::smart::toplevels::{ACTIVATED, FULLSCREEN, MAXIMIZED, MINIMIZED, ToplevelEvent};
+use crate::daemon::Message;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToplevelCommand {
+    /// Also restores a minimized window.
+    Activate,
+    Minimize,
+    Close,
+}
+
+/// Windows to act on, by handle; set while the wlr manager is watched.
+static CONTROL: Mutex<Option<tokio::sync::mpsc::UnboundedSender<(u32, ToplevelCommand)>>> =
+    Mutex::new(None);
+
+/// Wayland-side state; every event is also queued as a [`ToplevelEvent`].
+#[derive(Default)]
+struct Client {
+    /// Global name and version of each manager; only one gets bound.
+    wlr: Option<(u32, u32)>,
+    ext: Option<u32>,
+    seat: Option<wl_seat::WlSeat>,
+    /// global name -> output
+    outputs: HashMap<u32, wl_output::WlOutput>,
+    handles: HashMap<u32, ZwlrForeignToplevelHandleV1>,
+    finished: bool,
+    events: Vec<ToplevelEvent>,
+}
+
+fn id(proxy: &impl Proxy) -> u32 {
+    proxy.id().protocol_id()
+}
+
+/// Window handles count up for the whole run: protocol ids come back after
+/// a window closes, and a click queued for the old window must not reach
+/// the new one.
+static NEXT_HANDLE: AtomicU32 = AtomicU32::new(1);
+
+fn next_handle() -> u32 {
+    NEXT_HANDLE.fetch_add(1, Ordering::Relaxed)
+}
+
+/// The handle stored in a toplevel object's user data.
+fn handle(proxy: &impl Proxy) -> u32 {
+    proxy.data::<u32>().copied().unwrap_or_default()
+}
+
+impl Dispatch<wl_registry::WlRegistry, ()> for Client {
+    fn event(
+        state: &mut Self,
+        registry: &wl_registry::WlRegistry,
+        event: wl_registry::Event,
+        _: &(),
+        _: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            wl_registry::Event::Global {
+                name,
+                interface,
+                version,
+            } => match interface.as_str() {
+                "zwlr_foreign_toplevel_manager_v1" if state.wlr.is_none() => {
+                    state.wlr = Some((name, version.min(3)));
+                }
+                "ext_foreign_toplevel_list_v1" if state.ext.is_none() => state.ext = Some(name),
+                "wl_seat" if state.seat.is_none() => {
+                    state.seat = Some(registry.bind(name, 1, qh, ()));
+                }
+                // v4 is the first with `name`
+                "wl_output" if version >= 4 => {
+                    state.outputs.insert(name, registry.bind(name, 4, qh, ()));
+                }
+                _ => {}
+            },
+            wl_registry::Event::GlobalRemove { name } => {
+                if let Some(output) = state.outputs.remove(&name) {
+                    state.events.push(ToplevelEvent::OutputRemoved(id(&output)));
+                    output.release();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<wl_seat::WlSeat, ()> for Client {
+    fn event(
+        _: &mut Self,
+        _: &wl_seat::WlSeat,
+        _: wl_seat::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<wl_output::WlOutput, ()> for Client {
+    fn event(
+        state: &mut Self,
+        output: &wl_output::WlOutput,
+        event: wl_output::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let wl_output::Event::Name { name } = event {
+            state.events.push(ToplevelEvent::OutputName {
+                output: id(output),
+                name,
+            });
+        }
+    }
+}
+
+impl Dispatch<ZwlrForeignToplevelManagerV1, ()> for Client {
+    fn event(
+        state: &mut Self,
+        _: &ZwlrForeignToplevelManagerV1,
+        event: zwlr_foreign_toplevel_manager_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        use zwlr_foreign_toplevel_manager_v1::Event;
+        match event {
+            Event::Toplevel { toplevel } => {
+                state
+                    .events
+                    .push(ToplevelEvent::Toplevel(handle(&toplevel)));
+                state.handles.insert(handle(&toplevel), toplevel);
+            }
+            Event::Finished => state.finished = true,
+            _ => {}
+        }
+    }
+
+    event_created_child!(Client, ZwlrForeignToplevelManagerV1, [
+        zwlr_foreign_toplevel_manager_v1::EVT_TOPLEVEL_OPCODE => (ZwlrForeignToplevelHandleV1, next_handle()),
+    ]);
+}
+
+/// The `state` array as a bitfield of the `iwwc.toplevels` flags.
+fn state_bits(raw: &[u8]) -> u32 {
+    use zwlr_foreign_toplevel_handle_v1::State;
+    raw.chunks_exact(4)
+        .map(|c| u32::from_ne_bytes([c[0], c[1], c[2], c[3]]))
+        .map(|s| match State::try_from(s) {
+            Ok(State::Maximized) => MAXIMIZED,
+            Ok(State::Minimized) => MINIMIZED,
+            Ok(State::Activated) => ACTIVATED,
+            Ok(State::Fullscreen) => FULLSCREEN,
+            _ => 0,
+        })
+        .fold(0, |bits, b| bits | b)
+}
+
+impl Dispatch<ZwlrForeignToplevelHandleV1, u32> for Client {
+    fn event(
+        state: &mut Self,
+        handle: &ZwlrForeignToplevelHandleV1,
+        event: zwlr_foreign_toplevel_handle_v1::Event,
+        toplevel: &u32,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        use zwlr_foreign_toplevel_handle_v1::Event;
+        let toplevel = *toplevel;
+        let e = match event {
+            Event::Title { title } => ToplevelEvent::Title { toplevel, title },
+            Event::AppId { app_id } => ToplevelEvent::AppId { toplevel, app_id },
+            Event::OutputEnter { output } => ToplevelEvent::OutputEnter {
+                toplevel,
+                output: id(&output),
+            },
+            Event::OutputLeave { output } => ToplevelEvent::OutputLeave {
+                toplevel,
+                output: id(&output),
+            },
+            Event::State { state: raw } => ToplevelEvent::State {
+                toplevel,
+                state: state_bits(&raw),
+            },
+            Event::Done => ToplevelEvent::Done(toplevel),
+            Event::Closed => {
+                state.handles.remove(&toplevel);
+                handle.destroy();
+                ToplevelEvent::Closed(toplevel)
+            }
+            _ => return,
+        };
+        state.events.push(e);
+    }
+}
+
+impl Dispatch<ExtForeignToplevelListV1, ()> for Client {
+    fn event(
+        state: &mut Self,
+        _: &ExtForeignToplevelListV1,
+        event: ext_foreign_toplevel_list_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        use ext_foreign_toplevel_list_v1::Event;
+        match event {
+            Event::Toplevel { toplevel } => {
+                state
+                    .events
+                    .push(ToplevelEvent::Toplevel(handle(&toplevel)));
+            }
+            Event::Finished => state.finished = true,
+            _ => {}
+        }
+    }
+
+    event_created_child!(Client, ExtForeignToplevelListV1, [
+        ext_foreign_toplevel_list_v1::EVT_TOPLEVEL_OPCODE => (ExtForeignToplevelHandleV1, next_handle()),
+    ]);
+}
+
+impl Dispatch<ExtForeignToplevelHandleV1, u32> for Client {
+    fn event(
+        state: &mut Self,
+        handle: &ExtForeignToplevelHandleV1,
+        event: ext_foreign_toplevel_handle_v1::Event,
+        toplevel: &u32,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        use ext_foreign_toplevel_handle_v1::Event;
+        let toplevel = *toplevel;
+        let e = match event {
+            Event::Title { title } => ToplevelEvent::Title { toplevel, title },
+            Event::AppId { app_id } => ToplevelEvent::AppId { toplevel, app_id },
+            Event::Done => ToplevelEvent::Done(toplevel),
+            Event::Closed => {
+                handle.destroy();
+                ToplevelEvent::Closed(toplevel)
+            }
+            _ => return,
+        };
+        state.events.push(e);
+    }
+}
+
+async fn publish(output: &mut Sender<Message>, events: Vec<ToplevelEvent>) {
+    if !events.is_empty()
+        && crate::config::smart::update_toplevels(|t| {
+            for e in events {
+                t.apply(e);
+            }
+        })
+    {
+        let _ = output
+            .send(Message::SmartRefresh(vec!["iwwc.toplevels".into()]))
+            .await;
+    }
+}
+
+/// Runs until the compositor finishes the manager or the connection drops.
+/// Prefers wlr-foreign-toplevel-management, which carries state and takes
+/// requests; ext-foreign-toplevel-list only lists windows. `Ok(false)` when
+/// the compositor has neither.
+async fn watch(output: &mut Sender<Message>) -> Result<bool> {
+    let conn = Connection::connect_to_env()?;
+    let mut queue = conn.new_event_queue();
+    let qh = queue.handle();
+    let registry = conn.display().get_registry(&qh, ());
+    let mut client = Client::default();
+    queue.roundtrip(&mut client)?;
+    let mut rx = match (client.wlr, client.ext) {
+        (Some((name, version)), _) => {
+            let _manager: ZwlrForeignToplevelManagerV1 = registry.bind(name, version, &qh, ());
+            let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+            *CONTROL.lock().unwrap() = Some(tx);
+            Some(rx)
+        }
+        (None, Some(name)) => {
+            let _list: ExtForeignToplevelListV1 = registry.bind(name, 1, &qh, ());
+            log::info!("toplevels: no wlr-foreign-toplevel-management, windows are list-only");
+            None
+        }
+        (None, None) => return Ok(false),
+    };
+
+    let backend = conn.backend();
+    let fd = AsyncFd::new(backend.poll_fd().as_fd().as_raw_fd())?;
+    while !client.finished {
+        queue.dispatch_pending(&mut client)?;
+        publish(output, std::mem::take(&mut client.events)).await;
+        conn.flush()?;
+        let command = async {
+            match rx.as_mut() {
+                Some(rx) => rx.recv().await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::select! {
+            ready = fd.readable() => {
+                let mut ready = ready?;
+                if let Some(guard) = queue.prepare_read() {
+                    match guard.read() {
+                        Ok(_) => {}
+                        Err(WaylandError::Io(e)) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                            ready.clear_ready();
+                        }
+                        Err(e) => return Err(e.into()),
+                    }
+                }
+            }
+            Some((toplevel, command)) = command => {
+                match (client.handles.get(&toplevel), command) {
+                    (Some(handle), ToplevelCommand::Activate) => {
+                        handle.unset_minimized();
+                        if let Some(seat) = &client.seat {
+                            handle.activate(seat);
+                        }
+                    }
+                    (Some(handle), ToplevelCommand::Minimize) => handle.set_minimized(),
+                    (Some(handle), ToplevelCommand::Close) => handle.close(),
+                    (None, _) => {}
+                }
+            }
+        }
+    }
+    Ok(true)
+}
+
+fn toplevels_stream() -> impl Stream<Item = Message> {
+    iced::stream::channel(16, async |mut output| {
+        loop {
+            let supported = watch(&mut output).await;
+            CONTROL.lock().unwrap().take();
+            // a new manager announces every window again
+            if crate::config::smart::update_toplevels(|t| *t = Default::default()) {
+                let _ = output
+                    .send(Message::SmartRefresh(vec!["iwwc.toplevels".into()]))
+                    .await;
+            }
+            match supported {
+                Ok(false) => {
+                    log::warn!(
+                        "toplevels: the compositor supports neither wlr-foreign-toplevel-management nor ext-foreign-toplevel-list"
+                    );
+                    return;
+                }
+                Ok(true) => {}
+                Err(e) => log::debug!("toplevels: {e}"),
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+        }
+    })
+}
+
+pub fn subscription() -> Subscription<Message> {
+    Subscription::run(toplevels_stream)
+}
+
+/// Asks the compositor to activate, minimize or close a window by handle.
+/// False when no wlr manager is watched.
+pub fn control(toplevel: u32, command: ToplevelCommand) -> bool {
+    CONTROL
+        .lock()
+        .unwrap()
+        .as_ref()
+        .is_some_and(|tx| tx.send((toplevel, command)).is_ok())
+}
@@ -0,0 +1,180 @@
+use std::time::Duration;
+
+use futures::channel::mpsc::Sender;
+use futures::sink::SinkExt;
+use futures::stream::{Stream, StreamExt, select_all};
+use iced::Subscription;
+use zbus::proxy::CacheProperties;
+use zbus::zvariant::OwnedObjectPath;
+
+use crate::config::smart::upower::{Device, UPower};
+use crate::daemon::Message;
+
+const UPOWER: &str = "org.freedesktop.UPower";
+const PATH: &str = "/org/freedesktop/UPower";
+
+#[zbus::proxy(
+    interface = "org.freedesktop.UPower",
+    default_service = "org.freedesktop.UPower",
+    default_path = "/org/freedesktop/UPower"
+)]
+trait UPowerDaemon {
+    fn enumerate_devices(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+    fn get_display_device(&self) -> zbus::Result<OwnedObjectPath>;
+    #[zbus(property)]
+    fn on_battery(&self) -> zbus::Result<bool>;
+    #[zbus(signal)]
+    fn device_added(&self, device: OwnedObjectPath) -> zbus::Result<()>;
+    #[zbus(signal)]
+    fn device_removed(&self, device: OwnedObjectPath) -> zbus::Result<()>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.UPower.Device",
+    default_service = "org.freedesktop.UPower"
+)]
+trait UPowerDevice {
+    #[zbus(property, name = "Type")]
+    fn kind(&self) -> zbus::Result<u32>;
+    #[zbus(property)]
+    fn state(&self) -> zbus::Result<u32>;
+    #[zbus(property)]
+    fn percentage(&self) -> zbus::Result<f64>;
+    #[zbus(property)]
+    fn icon_name(&self) -> zbus::Result<String>;
+    #[zbus(property)]
+    fn model(&self) -> zbus::Result<String>;
+    #[zbus(property)]
+    fn time_to_empty(&self) -> zbus::Result<i64>;
+    #[zbus(property)]
+    fn time_to_full(&self) -> zbus::Result<i64>;
+    #[zbus(property)]
+    fn power_supply(&self) -> zbus::Result<bool>;
+    #[zbus(property)]
+    fn is_present(&self) -> zbus::Result<bool>;
+}
+
+async fn read_device(conn: &zbus::Connection, path: &str) -> Option<Device> {
+    let p = UPowerDeviceProxy::builder(conn)
+        .path(path.to_string())
+        .ok()?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await
+        .ok()?;
+    Some(Device {
+        path: path.to_string(),
+        kind: p.kind().await.ok()?,
+        state: p.state().await.unwrap_or(0),
+        percentage: p.percentage().await.unwrap_or(0.0),
+        icon: p.icon_name().await.unwrap_or_default(),
+        model: p.model().await.unwrap_or_default(),
+        time_to_empty: p.time_to_empty().await.unwrap_or(0),
+        time_to_full: p.time_to_full().await.unwrap_or(0),
+        power_supply: p.power_supply().await.unwrap_or(false),
+        present: p.is_present().await.unwrap_or(false),
+    })
+}
+
+async fn publish(output: &mut Sender<Message>, f: impl FnOnce(&mut UPower)) {
+    if crate::config::smart::update_upower(f) {
+        let _ = output
+            .send(Message::SmartRefresh(vec!["iwwc.upower".into()]))
+            .await;
+    }
+}
+
+/// Reads every device, then follows `PropertiesChanged` per device,
+/// rereading only the one that changed. A device coming or going starts
+/// over.
+async fn watch(output: &mut Sender<Message>) -> zbus::Result<()> {
+    let conn = crate::dbus::system().await?;
+    let upower = UPowerDaemonProxy::builder(conn)
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+    let dbus = zbus::fdo::DBusProxy::new(conn).await?;
+    let mut owner_changes = dbus
+        .receive_name_owner_changed_with_args(&[(0, UPOWER)])
+        .await?;
+    let mut added = upower.receive_device_added().await?;
+    let mut removed = upower.receive_device_removed().await?;
+    loop {
+        let display = upower.get_display_device().await?.to_string();
+        let paths: Vec<String> = upower
+            .enumerate_devices()
+            .await?
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        let mut state = UPower {
+            running: true,
+            on_battery: upower.on_battery().await?,
+            display: read_device(conn, &display).await.unwrap_or_default(),
+            devices: Vec::new(),
+        };
+        for path in &paths {
+            if let Some(d) = read_device(conn, path).await {
+                state.devices.push(d);
+            }
+        }
+        publish(output, |u| *u = state).await;
+
+        // the daemon's own properties (`OnBattery`) come first
+        let watched: Vec<String> = [PATH.to_string(), display]
+            .into_iter()
+            .chain(paths)
+            .collect();
+        let mut changes = Vec::new();
+        for (i, path) in watched.iter().enumerate() {
+            if let Ok(b) = zbus::fdo::PropertiesProxy::builder(conn).destination(UPOWER)
+                && let Ok(b) = b.path(path.clone())
+                && let Ok(props) = b.build().await
+                && let Ok(s) = props.receive_properties_changed().await
+            {
+                changes.push(s.map(move |_| i).boxed());
+            }
+        }
+        let mut changes = select_all(changes);
+
+        loop {
+            tokio::select! {
+                Some(i) = changes.next() => {
+                    if i == 0 {
+                        let on_battery = upower.on_battery().await?;
+                        publish(output, |u| u.on_battery = on_battery).await;
+                    } else if let Some(d) = read_device(conn, &watched[i]).await {
+                        publish(output, |u| {
+                            if let Some(old) = u.device_mut(&d.path) {
+                                *old = d;
+                            }
+                        })
+                        .await;
+                    }
+                }
+                Some(_) = added.next() => break,
+                Some(_) = removed.next() => break,
+                change = owner_changes.next() => {
+                    let Some(_) = change else { return Ok(()) };
+                    break;
+                }
+            }
+        }
+    }
+}
+
+fn upower_stream() -> impl Stream<Item = Message> {
+    iced::stream::channel(16, async |mut output| {
+        loop {
+            if let Err(e) = watch(&mut output).await {
+                log::debug!("upower: {e}");
+            }
+            publish(&mut output, |u| *u = UPower::default()).await;
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        }
+    })
+}
+
+pub fn subscription() -> Subscription<Message> {
+    Subscription::run(upower_stream)
+}
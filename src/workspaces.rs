@@ -0,0 +1,156 @@
+//! `iwwc.workspaces` from the layer shell's ext-workspace-v1 events. The
+//! event loop binds the compositor's workspace manager and forwards its
+//! events on the shell stream, next to the output changes; activation goes
+//! back the same way.
+
+use iced_wayland_subscriber::shell::{ShellEvent, ShellReceiver};
+
+use crate::config::smart::workspaces::WorkspaceEvent;
+
+/// The workspace part of a shell event, `None` for the others.
+fn workspace_event(event: &ShellEvent) -> Option<WorkspaceEvent> {
+    use WorkspaceEvent as W;
+    let e = match event.clone() {
+        ShellEvent::WorkspaceGroupCreated { group } => W::Group(group),
+        ShellEvent::WorkspaceGroupOutputEnter { group, output } => W::GroupOutputEnter {
+            group,
+            output: output.name?,
+        },
+        ShellEvent::WorkspaceGroupOutputLeave { group, output } => W::GroupOutputLeave {
+            group,
+            output: output.name?,
+        },
+        ShellEvent::WorkspaceGroupWorkspaceEnter { group, workspace } => {
+            W::GroupWorkspaceEnter { group, workspace }
+        }
+        ShellEvent::WorkspaceGroupWorkspaceLeave { group, workspace } => {
+            W::GroupWorkspaceLeave { group, workspace }
+        }
+        ShellEvent::WorkspaceGroupRemoved { group } => W::GroupRemoved(group),
+        ShellEvent::WorkspaceCreated { workspace } => W::Workspace(workspace),
+        ShellEvent::WorkspaceId { workspace, id } => W::Id { workspace, id },
+        ShellEvent::WorkspaceName { workspace, name } => W::Name { workspace, name },
+        ShellEvent::WorkspaceCoordinates {
+            workspace,
+            coordinates,
+        } => W::Coordinates {
+            workspace,
+            coordinates,
+        },
+        ShellEvent::WorkspaceState { workspace, state } => W::State { workspace, state },
+        ShellEvent::WorkspaceRemoved { workspace } => W::WorkspaceRemoved(workspace),
+        ShellEvent::WorkspacesDone => W::Done,
+        _ => return None,
+    };
+    Some(e)
+}
+
+/// Applies a shell event to `iwwc.workspaces`; true when the variables
+/// changed. Other events are ignored.
+pub fn apply(event: &ShellEvent) -> bool {
+    let Some(e) = workspace_event(event) else {
+        return false;
+    };
+    crate::config::smart::update_workspaces(|ws| ws.apply(e))
+}
+
+/// Asks the compositor to activate the workspace `name` (a key from
+/// `iwwc.workspaces.list`, a workspace name or id). False when unknown.
+pub fn activate(shell: &ShellReceiver, name: &str) -> bool {
+    let Some(workspace) = crate::config::smart::workspace_handle(name) else {
+        return false;
+    };
+    shell.activate_workspace(workspace);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::smart::testutil::get;
+    use crate::config::smart::workspaces::Workspaces;
+    use crate::config::types::VarValue;
+    use iced_wayland_subscriber::shell::OutputInfo;
+
+    fn output(name: &str) -> OutputInfo {
+        OutputInfo {
+            name: Some(name.into()),
+            ..Default::default()
+        }
+    }
+
+    /// A compositor's first transaction as the shell stream delivers it,
+    /// with unrelated output events in between.
+    fn stream() -> Vec<ShellEvent> {
+        vec![
+            ShellEvent::WorkspaceGroupCreated { group: 10 },
+            ShellEvent::WorkspaceGroupOutputEnter {
+                group: 10,
+                output: output("DP-1"),
+            },
+            ShellEvent::WorkspaceCreated { workspace: 20 },
+            ShellEvent::WorkspaceName {
+                workspace: 20,
+                name: "web".into(),
+            },
+            ShellEvent::WorkspaceCoordinates {
+                workspace: 20,
+                coordinates: vec![1],
+            },
+            ShellEvent::WindowOutputChanged {
+                window: iced::window::Id::unique(),
+                output: Some(output("DP-1")),
+            },
+            ShellEvent::WorkspaceCreated { workspace: 21 },
+            ShellEvent::WorkspaceName {
+                workspace: 21,
+                name: "term".into(),
+            },
+            ShellEvent::WorkspaceCoordinates {
+                workspace: 21,
+                coordinates: vec![0],
+            },
+            ShellEvent::WorkspaceState {
+                workspace: 21,
+                state: 1,
+            },
+            ShellEvent::WorkspaceGroupWorkspaceEnter {
+                group: 10,
+                workspace: 20,
+            },
+            ShellEvent::WorkspaceGroupWorkspaceEnter {
+                group: 10,
+                workspace: 21,
+            },
+            ShellEvent::WorkspacesDone,
+        ]
+    }
+
+    #[test]
+    fn shell_events_feed_workspaces() {
+        let mut ws = Workspaces::default();
+        for e in stream().iter().filter_map(workspace_event) {
+            ws.apply(e);
+        }
+        let vals = ws.values();
+        let s = |v: &str| Some(VarValue::Str(v.into()));
+        assert_eq!(get(&vals, "iwwc.workspaces.list"), s("term web"));
+        assert_eq!(get(&vals, "iwwc.workspaces.active"), s("term"));
+        assert_eq!(get(&vals, "iwwc.workspaces.web.output"), s("DP-1"));
+        assert_eq!(
+            get(&vals, "iwwc.workspaces.output.dp_1.list"),
+            s("term web")
+        );
+        assert_eq!(ws.lookup("web"), Some(20));
+    }
+
+    #[test]
+    fn other_shell_events_ignored() {
+        let e = ShellEvent::WindowOutputChanged {
+            window: iced::window::Id::unique(),
+            output: None,
+        };
+        assert_eq!(workspace_event(&e), None);
+        assert!(!apply(&e));
+    }
+}